@@ -0,0 +1,841 @@
+use crate::compression::{self, CompressionMethod, CompressionSettings};
+use crate::package_format::to_hex;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+// Archivformat im (entschlüsselten) Paketinhalt. Anders als ZIP braucht es weder
+// Seek beim Schreiben noch ein Inhaltsverzeichnis am Ende, kann also direkt durch
+// den Verschlüsselungs-Stream geschrieben und beim Import sequentiell gelesen werden.
+//
+//   magic (8)
+//   eintrag* :  tag (u8) | header_len (u32 LE) | header (JSON)
+//               bei Dateien danach: { block_len (u32 LE) | block }* | 0u32 | status (u8) | sha256 (32)
+//   index    :  TAG_INDEX | { block_len (u32 LE) | block }* | 0u32   (JSON Lines, je Datei ein IndexEntry)
+//   füllung  :  TAG_PAD | len (u32 LE) | len Nullbytes   (zwischen Einträgen, beliebig oft)
+//   ende     :  TAG_END
+//
+// Status 0 = Datei vollständig; sonst wurde das Lesen der Quelle abgebrochen und
+// der Eintrag ist zu verwerfen.
+//
+// Ist im Header eines Eintrags `compression` gesetzt, beginnt jeder Block mit einem
+// Kennzeichen (0 = unverändert, 1 = komprimiert); komprimiert wird blockweise, damit
+// mehrere Blöcke parallel verarbeitet werden können. Die Prüfsumme gilt immer für
+// die unkomprimierten Daten. Der Index listet alle vollständigen Dateien mit
+// Größe und SHA-256, damit sich fehlende oder zusätzliche Einträge erkennen lassen.
+//
+// Füllungen richten das Archiv an Zwischenständen auf eine Blockgrenze der
+// Verschlüsselung aus, damit ein unterbrochener Export dort fortgesetzt werden kann
+// (siehe `resume`). Beim Lesen werden sie übersprungen.
+
+pub const ARCHIVE_MAGIC: &[u8; 8] = b"NILOARC2";
+
+const TAG_END: u8 = 0;
+const TAG_DIR: u8 = 1;
+const TAG_FILE: u8 = 2;
+const TAG_INDEX: u8 = 3;
+const TAG_PAD: u8 = 4;
+
+const STATUS_OK: u8 = 0;
+const STATUS_INCOMPLETE: u8 = 1;
+
+const BLOCK_STORED: u8 = 0;
+const BLOCK_COMPRESSED: u8 = 1;
+
+const BLOCK_SIZE: usize = 1024 * 1024;
+const MAX_ENTRY_HEADER_LEN: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryHeader {
+    /// Pfad im Archiv, immer mit `/` getrennt.
+    pub path: String,
+    #[serde(default)]
+    pub size: u64,
+    /// Änderungszeit in Sekunden seit 1970, falls bekannt.
+    #[serde(default)]
+    pub modified: Option<u64>,
+    /// Kompressionsverfahren der Blöcke ("deflate", "zstd"); wird vom Writer gesetzt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<String>,
+}
+
+/// Eintrag im Index am Ende des Archivs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Ergebnis von [`ArchiveReader::read_data`].
+pub struct EntryData {
+    /// Datei wurde beim Export vollständig gelesen.
+    pub complete: bool,
+    pub size: u64,
+    /// Beim Export gespeicherte Prüfsumme.
+    pub stored_sha256: [u8; 32],
+    /// Beim Lesen berechnete Prüfsumme.
+    pub sha256: [u8; 32],
+}
+
+impl EntryData {
+    /// Prüfsumme stimmt.
+    pub fn hash_ok(&self) -> bool {
+        self.stored_sha256 == self.sha256
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ArchiveEntry {
+    Directory(EntryHeader),
+    File(EntryHeader),
+}
+
+impl ArchiveEntry {
+    pub fn path(&self) -> &str {
+        match self {
+            ArchiveEntry::Directory(h) | ArchiveEntry::File(h) => &h.path,
+        }
+    }
+}
+
+/// Ergebnis von [`ArchiveWriter::add_file`]. Lesefehler der Quelle brechen den
+/// Export nicht ab, sondern werden hier gemeldet.
+pub struct FileWritten {
+    pub bytes: u64,
+    pub sha256: [u8; 32],
+    pub read_error: Option<io::Error>,
+}
+
+pub struct ArchiveWriter<W: Write> {
+    inner: W,
+    compression: CompressionSettings,
+    index: Vec<IndexEntry>,
+    /// Bisher geschriebene Bytes inkl. Kennung.
+    position: u64,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    pub fn new(inner: W) -> io::Result<Self> {
+        Self::with_compression(
+            inner,
+            CompressionSettings {
+                method: CompressionMethod::None,
+                level: None,
+            },
+        )
+    }
+
+    /// Archiv, dessen Dateien mit dem angegebenen Verfahren komprimiert werden.
+    /// Bereits komprimierte Dateitypen (jpg, mp4, zip, docx, …) bleiben unverändert.
+    pub fn with_compression(mut inner: W, compression: CompressionSettings) -> io::Result<Self> {
+        inner.write_all(ARCHIVE_MAGIC)?;
+        Ok(ArchiveWriter {
+            inner,
+            compression,
+            index: Vec::new(),
+            position: ARCHIVE_MAGIC.len() as u64,
+        })
+    }
+
+    /// Schreibt ein Archiv weiter, von dem `position` Bytes mit den Dateien aus
+    /// `index` bereits in `inner` stehen.
+    pub fn resume(
+        inner: W,
+        compression: CompressionSettings,
+        index: Vec<IndexEntry>,
+        position: u64,
+    ) -> Self {
+        ArchiveWriter {
+            inner,
+            compression,
+            index,
+            position,
+        }
+    }
+
+    /// Bisher vollständig geschriebene Dateien.
+    pub fn index(&self) -> &[IndexEntry] {
+        &self.index
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Füllt bis zur nächsten durch `boundary` teilbaren Position auf.
+    pub fn align(&mut self, boundary: u64) -> io::Result<()> {
+        let mut gap = (boundary - self.position % boundary) % boundary;
+        if gap == 0 {
+            return Ok(());
+        }
+        // Kennung und Länge brauchen 5 Bytes, sonst bis zur übernächsten Grenze
+        if gap < 5 {
+            gap += boundary;
+        }
+        let zeros = gap - 5;
+        self.put(&[TAG_PAD])?;
+        self.put(&(zeros as u32).to_le_bytes())?;
+        let written = io::copy(&mut io::repeat(0).take(zeros), &mut self.inner)?;
+        self.position += written;
+        Ok(())
+    }
+
+    pub fn add_directory(&mut self, path: &str) -> io::Result<()> {
+        self.write_header(
+            TAG_DIR,
+            &EntryHeader {
+                path: path.to_string(),
+                size: 0,
+                modified: None,
+                compression: None,
+            },
+        )
+    }
+
+    /// Schreibt eine Datei aus `src`. Schlägt das Lesen der Quelle unterwegs fehl,
+    /// wird der Eintrag als unvollständig markiert; das Archiv bleibt gültig.
+    /// `Err` bedeutet einen Schreibfehler im Ziel.
+    pub fn add_file<R: Read>(
+        &mut self,
+        header: &EntryHeader,
+        src: &mut R,
+    ) -> io::Result<FileWritten> {
+        let method = match self.compression.method {
+            CompressionMethod::None => None,
+            _ if compression::is_compressed_type(Path::new(&header.path)) => None,
+            m => Some(m),
+        };
+        let level = self.compression.effective_level();
+        let mut header = header.clone();
+        header.compression = method.and_then(|m| m.id()).map(str::to_string);
+        self.write_header(TAG_FILE, &header)?;
+
+        // Ohne Kompression genügt ein Block je Durchgang, sonst so viele, wie
+        // parallel komprimiert werden können
+        let batch = if method.is_some() {
+            rayon::current_num_threads().max(1)
+        } else {
+            1
+        };
+        let mut written = 0u64;
+        let mut hasher = Sha256::new();
+        let mut read_error = None;
+        let mut eof = false;
+        while !eof && read_error.is_none() {
+            let mut blocks = Vec::with_capacity(batch);
+            while blocks.len() < batch {
+                let mut block = vec![0u8; BLOCK_SIZE];
+                match read_full(src, &mut block) {
+                    Ok(0) => {
+                        eof = true;
+                        break;
+                    }
+                    Ok(n) => {
+                        block.truncate(n);
+                        hasher.update(&block);
+                        blocks.push(block);
+                        if n < BLOCK_SIZE {
+                            eof = true;
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        read_error = Some(e);
+                        break;
+                    }
+                }
+            }
+            match method {
+                None => {
+                    for block in &blocks {
+                        self.put(&(block.len() as u32).to_le_bytes())?;
+                        self.put(block)?;
+                        written += block.len() as u64;
+                    }
+                }
+                Some(m) => {
+                    let packed = blocks
+                        .par_iter()
+                        .map(|b| compression::compress_block(m, level, b))
+                        .collect::<io::Result<Vec<_>>>()?;
+                    for (block, packed) in blocks.iter().zip(packed) {
+                        // Nicht komprimierbare Blöcke unverändert ablegen
+                        let (flag, data) = if packed.len() < block.len() {
+                            (BLOCK_COMPRESSED, &packed[..])
+                        } else {
+                            (BLOCK_STORED, &block[..])
+                        };
+                        self.put(&(data.len() as u32 + 1).to_le_bytes())?;
+                        self.put(&[flag])?;
+                        self.put(data)?;
+                        written += block.len() as u64;
+                    }
+                }
+            }
+        }
+        let sha256: [u8; 32] = hasher.finalize().into();
+        let status = if read_error.is_some() {
+            STATUS_INCOMPLETE
+        } else {
+            STATUS_OK
+        };
+        self.put(&0u32.to_le_bytes())?;
+        self.put(&[status])?;
+        self.put(&sha256)?;
+        if read_error.is_none() {
+            self.index.push(IndexEntry {
+                path: header.path.clone(),
+                size: written,
+                sha256: to_hex(&sha256),
+            });
+        }
+        Ok(FileWritten {
+            bytes: written,
+            sha256,
+            read_error,
+        })
+    }
+
+    /// Schreibt Index und Ende-Markierung und gibt den inneren Writer zurück.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.inner.write_all(&[TAG_INDEX])?;
+        let mut lines = Vec::new();
+        for entry in &self.index {
+            serde_json::to_writer(&mut lines, entry).map_err(io::Error::other)?;
+            lines.push(b'\n');
+            if lines.len() >= BLOCK_SIZE - 4096 {
+                self.inner.write_all(&(lines.len() as u32).to_le_bytes())?;
+                self.inner.write_all(&lines)?;
+                lines.clear();
+            }
+        }
+        if !lines.is_empty() {
+            self.inner.write_all(&(lines.len() as u32).to_le_bytes())?;
+            self.inner.write_all(&lines)?;
+        }
+        self.inner.write_all(&0u32.to_le_bytes())?;
+        self.inner.write_all(&[TAG_END])?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write_header(&mut self, tag: u8, header: &EntryHeader) -> io::Result<()> {
+        let json = serde_json::to_vec(header).map_err(io::Error::other)?;
+        self.put(&[tag])?;
+        self.put(&(json.len() as u32).to_le_bytes())?;
+        self.put(&json)
+    }
+
+    fn put(&mut self, data: &[u8]) -> io::Result<()> {
+        self.inner.write_all(data)?;
+        self.position += data.len() as u64;
+        Ok(())
+    }
+}
+
+pub struct ArchiveReader<R: Read> {
+    inner: R,
+    // Dateidaten des zuletzt gelieferten Eintrags wurden noch nicht gelesen
+    pending_data: bool,
+    // Kompression des zuletzt gelieferten Dateieintrags
+    compression: Option<CompressionMethod>,
+    finished: bool,
+    index: Option<Vec<IndexEntry>>,
+}
+
+impl<R: Read> ArchiveReader<R> {
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut magic = [0u8; 8];
+        inner.read_exact(&mut magic)?;
+        if &magic != ARCHIVE_MAGIC {
+            return Err(invalid("Unbekanntes Archivformat im Paket".to_string()));
+        }
+        Ok(ArchiveReader {
+            inner,
+            pending_data: false,
+            compression: None,
+            finished: false,
+            index: None,
+        })
+    }
+
+    /// Index des Archivs; erst verfügbar, nachdem `next_entry` `None` geliefert hat.
+    pub fn index(&self) -> Option<&[IndexEntry]> {
+        self.index.as_deref()
+    }
+
+    /// Liefert den nächsten Eintrag. Nicht gelesene Dateidaten des vorherigen
+    /// Eintrags werden dabei übersprungen.
+    pub fn next_entry(&mut self) -> io::Result<Option<ArchiveEntry>> {
+        if self.pending_data {
+            self.skip_data()?;
+        }
+        if self.finished {
+            return Ok(None);
+        }
+        let mut tag = [0u8; 1];
+        self.read_tag(&mut tag)?;
+        if tag[0] == TAG_INDEX {
+            let mut lines = Vec::new();
+            self.read_blocks(&mut lines, &mut Sha256::new(), None)?;
+            let index = lines
+                .split(|b| *b == b'\n')
+                .filter(|l| !l.is_empty())
+                .map(|l| {
+                    serde_json::from_slice(l)
+                        .map_err(|e| invalid(format!("Archivindex ist beschädigt: {}", e)))
+                })
+                .collect::<io::Result<Vec<IndexEntry>>>()?;
+            self.index = Some(index);
+            self.read_tag(&mut tag)?;
+        }
+        if tag[0] == TAG_END {
+            self.finished = true;
+            return Ok(None);
+        }
+        let mut len = [0u8; 4];
+        self.inner.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_ENTRY_HEADER_LEN {
+            return Err(invalid("Archiveintrag ist beschädigt".to_string()));
+        }
+        let mut json = vec![0u8; len];
+        self.inner.read_exact(&mut json)?;
+        let header: EntryHeader = serde_json::from_slice(&json)
+            .map_err(|e| invalid(format!("Archiveintrag ist beschädigt: {}", e)))?;
+        match tag[0] {
+            TAG_DIR => Ok(Some(ArchiveEntry::Directory(header))),
+            TAG_FILE => {
+                self.compression = match header.compression.as_deref() {
+                    None => None,
+                    Some(id) => Some(CompressionMethod::from_id(id).ok_or_else(|| {
+                        invalid(format!("Unbekanntes Kompressionsverfahren: {}", id))
+                    })?),
+                };
+                self.pending_data = true;
+                Ok(Some(ArchiveEntry::File(header)))
+            }
+            t => Err(invalid(format!("Unbekannter Archiveintrag: {}", t))),
+        }
+    }
+
+    /// Kopiert die Daten des aktuellen Dateieintrags nach `out` und berechnet dabei
+    /// die Prüfsumme. Ob die Datei vollständig und unverändert ist, steht im Ergebnis.
+    pub fn read_data<W: Write>(&mut self, out: &mut W) -> io::Result<EntryData> {
+        if !self.pending_data {
+            return Err(invalid("Kein Dateieintrag zum Lesen".to_string()));
+        }
+        self.pending_data = false;
+        let mut hasher = Sha256::new();
+        let size = self.read_blocks(out, &mut hasher, self.compression)?;
+        let mut status = [0u8; 1];
+        self.inner.read_exact(&mut status)?;
+        let mut stored_sha256 = [0u8; 32];
+        self.inner.read_exact(&mut stored_sha256)?;
+        Ok(EntryData {
+            complete: status[0] == STATUS_OK,
+            size,
+            stored_sha256,
+            sha256: hasher.finalize().into(),
+        })
+    }
+
+    pub fn skip_data(&mut self) -> io::Result<EntryData> {
+        self.read_data(&mut io::sink())
+    }
+
+    /// Liest die nächste Kennung und überspringt dabei Füllungen.
+    fn read_tag(&mut self, tag: &mut [u8; 1]) -> io::Result<()> {
+        loop {
+            self.inner.read_exact(tag)?;
+            if tag[0] != TAG_PAD {
+                return Ok(());
+            }
+            let mut len = [0u8; 4];
+            self.inner.read_exact(&mut len)?;
+            let len = u32::from_le_bytes(len) as u64;
+            if io::copy(&mut (&mut self.inner).take(len), &mut io::sink())? < len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+    }
+
+    fn read_blocks<W: Write>(
+        &mut self,
+        out: &mut W,
+        hasher: &mut Sha256,
+        compression: Option<CompressionMethod>,
+    ) -> io::Result<u64> {
+        let max_len = BLOCK_SIZE + compression.map_or(0, |_| 1);
+        let mut size = 0u64;
+        let mut block = Vec::new();
+        loop {
+            let mut len = [0u8; 4];
+            self.inner.read_exact(&mut len)?;
+            let len = u32::from_le_bytes(len) as usize;
+            if len == 0 {
+                break;
+            }
+            if len > max_len {
+                return Err(invalid("Archivblock ist beschädigt".to_string()));
+            }
+            block.resize(len, 0);
+            self.inner.read_exact(&mut block)?;
+            let unpacked;
+            let data = match compression {
+                None => &block[..],
+                Some(m) => match block[0] {
+                    BLOCK_STORED => &block[1..],
+                    BLOCK_COMPRESSED => {
+                        unpacked = compression::decompress_block(m, &block[1..], BLOCK_SIZE)?;
+                        &unpacked[..]
+                    }
+                    _ => return Err(invalid("Archivblock ist beschädigt".to_string())),
+                },
+            };
+            hasher.update(data);
+            out.write_all(data)?;
+            size += data.len() as u64;
+        }
+        Ok(size)
+    }
+}
+
+fn read_full<R: Read>(src: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match src.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Nicht komprimierbare Testdaten.
+    fn noise(len: usize) -> Vec<u8> {
+        let mut x = 0x9e37_79b9u32;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x as u8
+            })
+            .collect()
+    }
+
+    fn header(path: &str, size: usize) -> EntryHeader {
+        EntryHeader {
+            path: path.to_string(),
+            size: size as u64,
+            modified: Some(1_700_000_000),
+            compression: None,
+        }
+    }
+
+    fn zstd() -> CompressionSettings {
+        CompressionSettings {
+            method: CompressionMethod::Zstd,
+            level: None,
+        }
+    }
+
+    /// Gelesene Einträge: Pfad, Inhalt (bei Dateien) und Ergebnis des Lesens.
+    type Entries = Vec<(String, Option<(Vec<u8>, EntryData)>)>;
+
+    fn read_all(data: &[u8]) -> (Entries, Vec<IndexEntry>) {
+        let mut reader = ArchiveReader::new(data).unwrap();
+        let mut entries = Vec::new();
+        while let Some(entry) = reader.next_entry().unwrap() {
+            let content = match &entry {
+                ArchiveEntry::Directory(_) => None,
+                ArchiveEntry::File(_) => {
+                    let mut out = Vec::new();
+                    let data = reader.read_data(&mut out).unwrap();
+                    Some((out, data))
+                }
+            };
+            entries.push((entry.path().to_string(), content));
+        }
+        let index = reader.index().unwrap().to_vec();
+        (entries, index)
+    }
+
+    /// Liest nach `limit` Bytes nicht weiter (Quelle während des Exports entfernt).
+    struct FailingReader<'a> {
+        data: &'a [u8],
+        limit: usize,
+    }
+
+    impl Read for FailingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.limit == 0 {
+                return Err(io::Error::other("Datei gesperrt"));
+            }
+            let n = buf.len().min(self.limit).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            self.limit -= n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn roundtrip_empty_and_multi_block_files() {
+        let big = noise(2 * BLOCK_SIZE + 7);
+        let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+        writer.add_directory("Documents").unwrap();
+        let empty = writer
+            .add_file(&header("Documents/leer.txt", 0), &mut &b""[..])
+            .unwrap();
+        assert_eq!(empty.bytes, 0);
+        let written = writer
+            .add_file(&header("Documents/gross.bin", big.len()), &mut &big[..])
+            .unwrap();
+        assert!(written.read_error.is_none());
+        assert_eq!(written.bytes, big.len() as u64);
+        let data = writer.finish().unwrap();
+
+        let (entries, index) = read_all(&data);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].0, "Documents");
+        assert!(entries[0].1.is_none());
+        let (content, result) = entries[1].1.as_ref().unwrap();
+        assert!(content.is_empty() && result.complete && result.hash_ok());
+        let (content, result) = entries[2].1.as_ref().unwrap();
+        assert!(content == &big);
+        assert!(result.complete && result.hash_ok());
+        assert_eq!(result.stored_sha256, written.sha256);
+
+        // Index mit Größe und Prüfsumme aller Dateien
+        assert_eq!(index.len(), 2);
+        assert_eq!(index[0].path, "Documents/leer.txt");
+        assert_eq!(index[0].size, 0);
+        assert_eq!(
+            index[0].sha256,
+            to_hex(&Sha256::digest(b"")),
+            "SHA-256 der leeren Datei"
+        );
+        assert_eq!(index[1].size, big.len() as u64);
+        assert_eq!(index[1].sha256, to_hex(&written.sha256));
+    }
+
+    #[test]
+    fn entry_header_survives_roundtrip() {
+        let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+        writer
+            .add_file(&header("Bilder/ä ö ü.txt", 3), &mut &b"abc"[..])
+            .unwrap();
+        let data = writer.finish().unwrap();
+        let mut reader = ArchiveReader::new(&data[..]).unwrap();
+        let Some(ArchiveEntry::File(read)) = reader.next_entry().unwrap() else {
+            panic!("Dateieintrag erwartet");
+        };
+        assert_eq!(read.path, "Bilder/ä ö ü.txt");
+        assert_eq!(read.size, 3);
+        assert_eq!(read.modified, Some(1_700_000_000));
+        assert_eq!(read.compression, None);
+        // Nicht gelesene Daten werden beim nächsten Eintrag übersprungen
+        assert!(reader.next_entry().unwrap().is_none());
+        assert_eq!(reader.index().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn failed_read_marks_entry_incomplete() {
+        let data = noise(BLOCK_SIZE + BLOCK_SIZE / 2);
+        let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+        let mut failing = FailingReader {
+            data: &data,
+            limit: BLOCK_SIZE + 100,
+        };
+        let written = writer
+            .add_file(&header("gesperrt.db", data.len()), &mut failing)
+            .unwrap();
+        assert!(written.read_error.is_some());
+        writer
+            .add_file(&header("danach.txt", 5), &mut &b"hallo"[..])
+            .unwrap();
+        let archive = writer.finish().unwrap();
+
+        let (entries, index) = read_all(&archive);
+        let (_, result) = entries[0].1.as_ref().unwrap();
+        assert!(!result.complete);
+        // Der folgende Eintrag ist trotzdem lesbar
+        let (content, result) = entries[1].1.as_ref().unwrap();
+        assert_eq!(content, b"hallo");
+        assert!(result.complete && result.hash_ok());
+        let paths: Vec<&str> = index.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["danach.txt"]);
+    }
+
+    #[test]
+    fn flipped_byte_fails_hash() {
+        let content = "Inhalt, der unterwegs verändert wird".as_bytes().to_vec();
+        let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+        writer
+            .add_file(&header("a.txt", content.len()), &mut &content[..])
+            .unwrap();
+        let mut archive = writer.finish().unwrap();
+        let pos = archive
+            .windows(content.len())
+            .position(|w| w == &content[..])
+            .unwrap();
+        archive[pos + 3] ^= 0x01;
+
+        let (entries, _) = read_all(&archive);
+        let (read, result) = entries[0].1.as_ref().unwrap();
+        assert_ne!(read, &content);
+        assert!(result.complete);
+        assert!(!result.hash_ok());
+    }
+
+    #[test]
+    fn compressed_and_stored_blocks() {
+        // Erster Block komprimierbar, zweiter nicht
+        let mut mixed = vec![0u8; BLOCK_SIZE];
+        mixed.extend_from_slice(&noise(BLOCK_SIZE / 2));
+        let photo = vec![0u8; 1000];
+        let mut writer = ArchiveWriter::with_compression(Vec::new(), zstd()).unwrap();
+        writer
+            .add_file(&header("daten.bin", mixed.len()), &mut &mixed[..])
+            .unwrap();
+        // Bereits komprimierte Typen bleiben unverändert
+        writer
+            .add_file(&header("foto.jpg", photo.len()), &mut &photo[..])
+            .unwrap();
+        let archive = writer.finish().unwrap();
+        // Komprimierter Nullblock plus unverändert abgelegter Rauschblock
+        assert!(archive.len() < mixed.len());
+        assert!(archive.len() > BLOCK_SIZE / 2);
+
+        let mut reader = ArchiveReader::new(&archive[..]).unwrap();
+        let mut headers = Vec::new();
+        let mut contents = Vec::new();
+        while let Some(ArchiveEntry::File(header)) = reader.next_entry().unwrap() {
+            let mut out = Vec::new();
+            let result = reader.read_data(&mut out).unwrap();
+            assert!(result.complete && result.hash_ok(), "{}", header.path);
+            headers.push(header);
+            contents.push(out);
+        }
+        assert_eq!(headers[0].compression.as_deref(), Some("zstd"));
+        assert_eq!(headers[1].compression, None);
+        assert!(contents[0] == mixed);
+        assert_eq!(contents[1], photo);
+        assert_eq!(reader.index().unwrap()[0].size, mixed.len() as u64);
+    }
+
+    #[test]
+    fn index_spanning_several_blocks() {
+        let count = 25_000;
+        let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+        for i in 0..count {
+            let path = format!(
+                "AppData/Roaming/Programm/Zwischenspeicher/eintrag-{:06}.tmp",
+                i
+            );
+            writer.add_file(&header(&path, 0), &mut &b""[..]).unwrap();
+        }
+        let index_len: usize = writer
+            .index()
+            .iter()
+            .map(|e| serde_json::to_vec(e).unwrap().len() + 1)
+            .sum();
+        assert!(index_len > 2 * BLOCK_SIZE);
+        let archive = writer.finish().unwrap();
+
+        let mut reader = ArchiveReader::new(&archive[..]).unwrap();
+        while reader.next_entry().unwrap().is_some() {}
+        let index = reader.index().unwrap();
+        assert_eq!(index.len(), count);
+        assert_eq!(
+            index[count - 1].path,
+            format!(
+                "AppData/Roaming/Programm/Zwischenspeicher/eintrag-{:06}.tmp",
+                count - 1
+            )
+        );
+    }
+
+    #[test]
+    fn padding_aligns_and_is_skipped() {
+        let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+        assert_eq!(writer.position(), ARCHIVE_MAGIC.len() as u64);
+        // Nur 2 Bytes bis zur Grenze: Füllung reicht bis zur übernächsten
+        writer.align(10).unwrap();
+        assert_eq!(writer.position(), 20);
+        assert_eq!(writer.get_mut().len(), 20);
+        writer.align(10).unwrap();
+        assert_eq!(writer.position(), 20);
+        writer.add_directory("Documents").unwrap();
+        writer
+            .add_file(&header("Documents/a.txt", 3), &mut &b"abc"[..])
+            .unwrap();
+        writer.align(4096).unwrap();
+        assert_eq!(writer.position(), 4096);
+        assert_eq!(writer.get_mut().len(), 4096);
+        writer
+            .add_file(&header("Documents/b.txt", 3), &mut &b"def"[..])
+            .unwrap();
+        let archive = writer.finish().unwrap();
+
+        let (entries, index) = read_all(&archive);
+        let paths: Vec<&str> = entries.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, ["Documents", "Documents/a.txt", "Documents/b.txt"]);
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn resumed_writer_continues_archive() {
+        let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+        writer
+            .add_file(&header("a.txt", 3), &mut &b"abc"[..])
+            .unwrap();
+        writer.align(256).unwrap();
+        let position = writer.position();
+        let index = writer.index().to_vec();
+        let mut data = std::mem::take(writer.get_mut());
+        // Nach dem Zwischenstand Geschriebenes geht beim Abbruch verloren
+        writer
+            .add_file(&header("verloren.txt", 3), &mut &b"xyz"[..])
+            .unwrap();
+        data.truncate(position as usize);
+
+        let mut writer =
+            ArchiveWriter::resume(data, CompressionSettings::default(), index, position);
+        writer
+            .add_file(&header("b.txt", 3), &mut &b"def"[..])
+            .unwrap();
+        let archive = writer.finish().unwrap();
+        let (entries, index) = read_all(&archive);
+        let paths: Vec<&str> = entries.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, ["a.txt", "b.txt"]);
+        let paths: Vec<&str> = index.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["a.txt", "b.txt"]);
+    }
+
+    #[test]
+    fn unknown_magic_is_rejected() {
+        assert!(ArchiveReader::new(&b"NILOARC1"[..]).is_err());
+        assert!(ArchiveReader::new(&b"NILOARC9"[..]).is_err());
+        assert!(ArchiveReader::new(&b"NILO"[..]).is_err());
+    }
+}
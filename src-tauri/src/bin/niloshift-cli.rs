@@ -0,0 +1,4 @@
+// Befehlszeile ohne Fenster, siehe `niloshift_lib::cli`.
+fn main() {
+    std::process::exit(niloshift_lib::cli::main())
+}
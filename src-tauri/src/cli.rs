@@ -0,0 +1,700 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead};
+
+use crate::conflict::ConflictPolicy;
+use crate::export_operations::{self, ExportOptions};
+use crate::import_operations::{self, DetectedContents, SelectedItems};
+use crate::known_folders::{self, KNOWN_FOLDERS};
+use crate::network_operations;
+use crate::profiles;
+use crate::progress::{self, OutputFormat, ProgressSink};
+use crate::recipients::Credentials;
+use crate::system_operations;
+use crate::transfer_operations;
+use crate::verify_operations;
+
+// `niloshift-cli`: Export, Import, Übertragung, LAN-Versand, Prüfung und Paketinhalt ohne Fenster, z. B. für
+// Fernwartungssoftware. Die Optionen entsprechen den Parametern der Tauri-Befehle.
+// Anders als die App fordert die CLI keine Administratorrechte an; sie läuft mit den
+// Rechten, mit denen sie gestartet wird.
+
+const USAGE: &str = "\
+Aufruf: niloshift-cli <Befehl> [Optionen]
+
+Befehle:
+  export          Profil in ein Paket exportieren
+                  --user <Name> [--folders desktop,documents,...] [--options <JSON-Datei>]
+                  [--output <Paket>] [--source-root <Pfad>] [--volume-size-mb <MB>]
+                  [--recipient <Schlüssel>]... [--snapshot] [--resume]
+  import <Paket>  Paket in ein Profil importieren
+                  --user <Name> [--items desktop,custom-1,...] [--target <Kennung>=<Pfad>]...
+                  [--conflict overwrite|skip|keep_newer|rename|backup] [--identity <Datei>]
+                  [--profile-root <Pfad>] [--no-verify] [--resume]
+  transfer        Profil ohne Paket direkt in ein anderes Profil übertragen
+                  --from <Name> --user <Name> [--folders desktop,documents,...]
+                  [--options <JSON-Datei>] [--source-root <Pfad>] [--target <Kennung>=<Pfad>]...
+                  [--conflict overwrite|skip|keep_newer|rename|backup]
+                  [--profile-root <Pfad>] [--snapshot]
+  send <Paket>    Paket an einen Empfänger im LAN senden
+                  --to <Adresse[:Port]> --code <Kopplungscode>
+  receive         Paket aus dem LAN empfangen und importieren; gibt Adresse und
+                  Kopplungscode als Ereignis receive-ready aus
+                  --user <Name> [--items desktop,custom-1,...] [--target <Kennung>=<Pfad>]...
+                  [--conflict overwrite|skip|keep_newer|rename|backup] [--identity <Datei>]
+                  [--port <Port>] [--profile-root <Pfad>] [--no-verify] [--resume]
+  inspect <Paket> Inhalt und Manifest eines Pakets anzeigen
+                  [--identity <Datei>]
+  verify <Paket>  Paket vollständig entschlüsseln und jede Datei prüfen
+                  [--identity <Datei>]
+  list-profiles   Benutzerprofile auflisten
+                  [--source-root <Pfad>]
+
+Passwort (export, import, receive, inspect, verify), höchstens eine Quelle:
+  --password-file <Datei>   erste Zeile der Datei
+  --password-env <Variable> Inhalt der Umgebungsvariable
+  --password-stdin          erste Zeile von der Standardeingabe
+
+Ausgabe:
+  --json   Fortschritt und Ergebnis als ein JSON-Objekt je Zeile auf stdout
+           (Feld `event`: export-progress, import-progress, transfer-progress,
+           send-progress, receive-ready, verify-progress, result oder error).
+           Protokollmeldungen gehen wie Fehlermeldungen nach stderr.
+
+Exit-Code: 0 erfolgreich, 1 Fehler, 2 falscher Aufruf, 3 abgeschlossen, aber mit
+fehlgeschlagenen Dateien bzw. beschädigtem Paket.";
+
+/// Optionen ohne Wert.
+const SWITCHES: &[&str] = &["json", "password-stdin", "snapshot", "resume", "no-verify"];
+
+#[derive(Debug, PartialEq)]
+enum Failure {
+    /// Falscher Aufruf; die Hilfe wird mit ausgegeben.
+    Usage(String),
+    Error(String),
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure::Error(message)
+    }
+}
+
+/// Führt die Befehlszeile aus und liefert den Exit-Code.
+pub fn main() -> i32 {
+    run(env::args().skip(1))
+}
+
+/// Führt einen Befehl mit seinen Argumenten (ohne Programmnamen) aus.
+fn run(mut args: impl Iterator<Item = String>) -> i32 {
+    let Some(command) = args.next() else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+    if matches!(command.as_str(), "help" | "--help" | "-h") {
+        println!("{}", USAGE);
+        return 0;
+    }
+    let mut args = match Args::parse(args) {
+        Ok(args) => args,
+        Err(failure) => return fail(OutputFormat::Text, failure),
+    };
+    let format = if args.flag("json") {
+        OutputFormat::Json
+    } else {
+        OutputFormat::Text
+    };
+    let sink = ProgressSink::console(format);
+    let result = match command.as_str() {
+        "export" => export(&mut args, &sink),
+        "import" => import(&mut args, &sink),
+        "transfer" => transfer(&mut args, &sink),
+        "send" => send(&mut args, &sink),
+        "receive" => receive(&mut args, &sink),
+        "inspect" => inspect(&mut args),
+        "verify" => verify(&mut args, &sink),
+        "list-profiles" => list_profiles(&mut args),
+        other => Err(Failure::Usage(format!("Unbekannter Befehl: {}", other))),
+    };
+    match result {
+        Ok(value) => {
+            // Abgeschlossen, aber nicht vollständig: Dateien fehlgeschlagen oder Paket beschädigt
+            let incomplete = value.get("ok") == Some(&Value::Bool(false))
+                || value.get("failed").and_then(Value::as_u64).unwrap_or(0) > 0;
+            progress::print_event(format, "result", value);
+            if incomplete {
+                3
+            } else {
+                0
+            }
+        }
+        Err(failure) => fail(format, failure),
+    }
+}
+
+fn fail(format: OutputFormat, failure: Failure) -> i32 {
+    match failure {
+        Failure::Usage(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            2
+        }
+        Failure::Error(message) => {
+            match format {
+                OutputFormat::Json => {
+                    progress::print_event(format, "error", json!({ "message": message }))
+                }
+                OutputFormat::Text => eprintln!("❌ {}", message),
+            }
+            1
+        }
+    }
+}
+
+fn export(args: &mut Args, sink: &ProgressSink) -> Result<Value, Failure> {
+    let selected_user = args.required("user")?;
+    let options = args.export_options()?;
+    let export_path = args.value("output");
+    let source_root = args.value("source-root");
+    let volume_size_mb = match args.value("volume-size-mb") {
+        Some(mb) => Some(
+            mb.parse::<u64>()
+                .map_err(|_| Failure::Usage(format!("Ungültige Volume-Größe: {}", mb)))?,
+        ),
+        None => None,
+    };
+    let recipients = args.values("recipient");
+    let use_snapshot = args.flag("snapshot");
+    let resume_interrupted = args.flag("resume");
+    let password = args.password()?;
+    args.finish()?;
+    warn_if_not_elevated();
+
+    let summary = export_operations::run_export(
+        sink,
+        options,
+        password,
+        export_path,
+        selected_user,
+        volume_size_mb,
+        Some(recipients),
+        Some(use_snapshot),
+        None,
+        Some(resume_interrupted),
+        source_root,
+    )?;
+    to_value(summary)
+}
+
+fn import(args: &mut Args, sink: &ProgressSink) -> Result<Value, Failure> {
+    let package_path = args.package()?;
+    let selected_user = args.required("user")?;
+    let items = args.list("items");
+    let custom_targets = args.custom_targets()?;
+    let conflict_policy = args.conflict_policy()?;
+    let profile_root = args.value("profile-root");
+    let identity_path = args.value("identity");
+    let verify_hashes = !args.flag("no-verify");
+    let resume_interrupted = args.flag("resume");
+    let password = args.password()?;
+    args.finish()?;
+    warn_if_not_elevated();
+    // Zielprofil in einem anderen Profilordner, wie die Einstellung der Oberfläche
+    if profile_root.is_some() {
+        tauri::async_runtime::block_on(profiles::set_profile_root(profile_root))?;
+    }
+
+    // Ohne Auswahl alles, was laut Manifest im Paket steht – wie die Vorauswahl der
+    // Oberfläche; alte Pakete ohne Manifest mit allen bekannten Ordnern
+    let selected_items = if items.is_empty() {
+        let credentials = Credentials::new(&password, identity_path.as_deref())?;
+        match import_operations::read_manifest(&package_path, &credentials)? {
+            Some(manifest) => DetectedContents::from_manifest(manifest).selection(),
+            None => SelectedItems::from_ids(KNOWN_FOLDERS.iter().map(|f| f.id)),
+        }
+    } else {
+        SelectedItems::from_ids(items.iter().map(String::as_str))
+    };
+    let summary = import_operations::run_import(
+        sink,
+        package_path,
+        password,
+        selected_user,
+        selected_items,
+        Some(verify_hashes),
+        identity_path,
+        Some(custom_targets),
+        conflict_policy,
+        None,
+        Some(resume_interrupted),
+    )?;
+    to_value(summary)
+}
+
+fn transfer(args: &mut Args, sink: &ProgressSink) -> Result<Value, Failure> {
+    let source_user = args.required("from")?;
+    let selected_user = args.required("user")?;
+    let options = args.export_options()?;
+    let source_root = args.value("source-root");
+    let custom_targets = args.custom_targets()?;
+    let conflict_policy = args.conflict_policy()?;
+    let profile_root = args.value("profile-root");
+    let use_snapshot = args.flag("snapshot");
+    args.finish()?;
+    warn_if_not_elevated();
+    if profile_root.is_some() {
+        tauri::async_runtime::block_on(profiles::set_profile_root(profile_root))?;
+    }
+
+    let summary = transfer_operations::run_transfer(
+        sink,
+        options,
+        source_user,
+        source_root,
+        selected_user,
+        Some(custom_targets),
+        conflict_policy,
+        Some(use_snapshot),
+        None,
+    )?;
+    to_value(summary)
+}
+
+fn send(args: &mut Args, sink: &ProgressSink) -> Result<Value, Failure> {
+    let package_path = args.package()?;
+    let address = args.required("to")?;
+    let pairing_code = args.required("code")?;
+    args.finish()?;
+    let message = network_operations::run_send(sink, package_path, address, pairing_code, None)?;
+    Ok(json!({ "message": message }))
+}
+
+fn receive(args: &mut Args, sink: &ProgressSink) -> Result<Value, Failure> {
+    let selected_user = args.required("user")?;
+    let items = args.list("items");
+    let custom_targets = args.custom_targets()?;
+    let conflict_policy = args.conflict_policy()?;
+    let port = match args.value("port") {
+        Some(port) => Some(
+            port.parse::<u16>()
+                .map_err(|_| Failure::Usage(format!("Ungültiger Port: {}", port)))?,
+        ),
+        None => None,
+    };
+    let profile_root = args.value("profile-root");
+    let identity_path = args.value("identity");
+    let verify_hashes = !args.flag("no-verify");
+    let resume_interrupted = args.flag("resume");
+    let password = args.password()?;
+    args.finish()?;
+    warn_if_not_elevated();
+    if profile_root.is_some() {
+        tauri::async_runtime::block_on(profiles::set_profile_root(profile_root))?;
+    }
+
+    // Der Inhalt ist erst nach dem Empfang bekannt: ohne Auswahl alle bekannten Ordner,
+    // eigene Pfade nur mit ihrer Kennung in --items
+    let selected_items = if items.is_empty() {
+        SelectedItems::from_ids(KNOWN_FOLDERS.iter().map(|f| f.id))
+    } else {
+        SelectedItems::from_ids(items.iter().map(String::as_str))
+    };
+    let summary = network_operations::run_receive(
+        sink,
+        password,
+        selected_user,
+        selected_items,
+        Some(verify_hashes),
+        identity_path,
+        Some(custom_targets),
+        conflict_policy,
+        port,
+        None,
+        Some(resume_interrupted),
+    )?;
+    to_value(summary)
+}
+
+fn inspect(args: &mut Args) -> Result<Value, Failure> {
+    let package_path = args.package()?;
+    let identity_path = args.value("identity");
+    let password = args.password()?;
+    args.finish()?;
+    let credentials = Credentials::new(&password, identity_path.as_deref())?;
+    match import_operations::read_manifest(&package_path, &credentials)? {
+        Some(manifest) => to_value(DetectedContents::from_manifest(manifest)),
+        None => Err(Failure::Error(
+            "Altes Paket ohne Manifest; der Inhalt zeigt sich erst beim Import".to_string(),
+        )),
+    }
+}
+
+fn verify(args: &mut Args, sink: &ProgressSink) -> Result<Value, Failure> {
+    let package_path = args.package()?;
+    let identity_path = args.value("identity");
+    let password = args.password()?;
+    args.finish()?;
+    let report = verify_operations::run_verify(sink, package_path, password, identity_path)?;
+    to_value(report)
+}
+
+fn list_profiles(args: &mut Args) -> Result<Value, Failure> {
+    let source_root = args.value("source-root");
+    args.finish()?;
+    let profiles =
+        tauri::async_runtime::block_on(system_operations::list_windows_profiles(source_root))?;
+    to_value(profiles)
+}
+
+fn to_value(result: impl Serialize) -> Result<Value, Failure> {
+    serde_json::to_value(result)
+        .map_err(|e| Failure::Error(format!("Ergebnis nicht darstellbar: {}", e)))
+}
+
+#[cfg(target_os = "windows")]
+fn warn_if_not_elevated() {
+    if !is_elevated::is_elevated() {
+        eprintln!(
+            "⚠️ Ohne Administratorrechte sind andere Profile und manche Dateien nicht lesbar"
+        );
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn warn_if_not_elevated() {}
+
+/// Optionen und Paketpfad eines Befehls. Optionen werden beim Abfragen entnommen,
+/// übrig gebliebene sind unbekannt.
+struct Args {
+    options: Vec<(String, Option<String>)>,
+    positional: Vec<String>,
+}
+
+impl Args {
+    /// `--name wert`, `--name=wert` oder ein Schalter aus `SWITCHES`.
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, Failure> {
+        let mut parsed = Args {
+            options: Vec::new(),
+            positional: Vec::new(),
+        };
+        while let Some(arg) = args.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg);
+                continue;
+            };
+            let option = match name.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None if SWITCHES.contains(&name) => (name.to_string(), None),
+                None => match args.next() {
+                    Some(value) => (name.to_string(), Some(value)),
+                    None => return Err(Failure::Usage(format!("--{} erwartet einen Wert", name))),
+                },
+            };
+            parsed.options.push(option);
+        }
+        Ok(parsed)
+    }
+
+    /// Alle Werte einer mehrfach erlaubten Option.
+    fn values(&mut self, name: &str) -> Vec<String> {
+        let mut values = Vec::new();
+        self.options.retain(|(option, value)| {
+            if option != name {
+                return true;
+            }
+            values.extend(value.clone());
+            false
+        });
+        values
+    }
+
+    /// Wert einer Option; bei mehrfacher Angabe gilt die letzte.
+    fn value(&mut self, name: &str) -> Option<String> {
+        self.values(name).pop()
+    }
+
+    fn required(&mut self, name: &str) -> Result<String, Failure> {
+        self.value(name)
+            .ok_or_else(|| Failure::Usage(format!("--{} fehlt", name)))
+    }
+
+    fn flag(&mut self, name: &str) -> bool {
+        let before = self.options.len();
+        self.options.retain(|(option, _)| option != name);
+        self.options.len() != before
+    }
+
+    /// Kommagetrennte Kennungen, auch über mehrere Angaben der Option.
+    fn list(&mut self, name: &str) -> Vec<String> {
+        self.values(name)
+            .iter()
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Exportauswahl aus `--options` und `--folders`.
+    fn export_options(&mut self) -> Result<ExportOptions, Failure> {
+        let mut options: ExportOptions = match self.value("options") {
+            Some(path) => {
+                let text = fs::read_to_string(&path)
+                    .map_err(|e| format!("Optionsdatei {} nicht lesbar: {}", path, e))?;
+                serde_json::from_str(&text)
+                    .map_err(|e| format!("Optionsdatei {} fehlerhaft: {}", path, e))?
+            }
+            None => ExportOptions::default(),
+        };
+        for id in self.list("folders") {
+            if known_folders::by_id(&id).is_none() {
+                return Err(Failure::Usage(format!("Unbekannter Ordner: {}", id)));
+            }
+            options.folders.insert(id, true);
+        }
+        Ok(options)
+    }
+
+    /// Zielordner benutzerdefinierter Pfade aus `--target <Kennung>=<Pfad>`.
+    fn custom_targets(&mut self) -> Result<BTreeMap<String, String>, Failure> {
+        let mut custom_targets = BTreeMap::new();
+        for target in self.values("target") {
+            let Some((id, path)) = target.split_once('=') else {
+                return Err(Failure::Usage(format!(
+                    "--target erwartet <Kennung>=<Pfad>: {}",
+                    target
+                )));
+            };
+            custom_targets.insert(id.to_string(), path.to_string());
+        }
+        Ok(custom_targets)
+    }
+
+    fn conflict_policy(&mut self) -> Result<Option<ConflictPolicy>, Failure> {
+        match self.value("conflict") {
+            Some(policy) => serde_json::from_value(Value::String(policy.clone()))
+                .map(Some)
+                .map_err(|_| Failure::Usage(format!("Unbekannte Konfliktregel: {}", policy))),
+            None => Ok(None),
+        }
+    }
+
+    fn package(&mut self) -> Result<String, Failure> {
+        match self.positional.len() {
+            0 => Err(Failure::Usage("Paket fehlt".to_string())),
+            1 => Ok(self.positional.remove(0)),
+            _ => Err(Failure::Usage(format!(
+                "Nur ein Paket angeben: {}",
+                self.positional.join(" ")
+            ))),
+        }
+    }
+
+    /// Passwort aus Datei, Umgebungsvariable oder Standardeingabe, ohne Angabe leer.
+    /// Auf der Befehlszeile selbst wäre es in der Prozessliste sichtbar.
+    fn password(&mut self) -> Result<String, Failure> {
+        self.password_from(io::stdin().lock())
+    }
+
+    /// Wie `password`, mit `input` statt der Standardeingabe.
+    fn password_from(&mut self, mut input: impl BufRead) -> Result<String, Failure> {
+        let file = self.value("password-file");
+        let var = self.value("password-env");
+        let stdin = self.flag("password-stdin");
+        let password = match (file, var, stdin) {
+            (None, None, false) => return Ok(String::new()),
+            (Some(path), None, false) => fs::read_to_string(&path)
+                .map_err(|e| format!("Passwortdatei {} nicht lesbar: {}", path, e))?,
+            (None, Some(var), false) => env::var(&var)
+                .map_err(|_| format!("Umgebungsvariable {} ist nicht gesetzt", var))?,
+            (None, None, true) => {
+                let mut line = String::new();
+                input
+                    .read_line(&mut line)
+                    .map_err(|e| format!("Passwort nicht lesbar: {}", e))?;
+                line
+            }
+            _ => {
+                return Err(Failure::Usage(
+                    "Nur eine Quelle für das Passwort angeben".to_string(),
+                ))
+            }
+        };
+        Ok(password.lines().next().unwrap_or_default().to_string())
+    }
+
+    fn finish(&self) -> Result<(), Failure> {
+        if let Some((name, _)) = self.options.first() {
+            return Err(Failure::Usage(format!("Unbekannte Option: --{}", name)));
+        }
+        if !self.positional.is_empty() {
+            return Err(Failure::Usage(format!(
+                "Unerwartetes Argument: {}",
+                self.positional.join(" ")
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn parse(args: &[&str]) -> Result<Args, Failure> {
+        Args::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    fn run_with(args: &[&str]) -> i32 {
+        run(args.iter().map(|a| a.to_string()))
+    }
+
+    fn is_usage(result: Result<impl Sized, Failure>) -> bool {
+        matches!(result, Err(Failure::Usage(_)))
+    }
+
+    #[test]
+    fn options_values_and_switches() {
+        let mut args = parse(&[
+            "paket.nilo",
+            "--user",
+            "max",
+            "--items=desktop,custom-1",
+            "--items",
+            "music",
+            "--json",
+            "--port=1",
+            "--port",
+            "2",
+        ])
+        .unwrap();
+        assert_eq!(args.package(), Ok("paket.nilo".to_string()));
+        assert_eq!(args.required("user"), Ok("max".to_string()));
+        assert_eq!(args.list("items"), ["desktop", "custom-1", "music"]);
+        assert!(args.flag("json"));
+        assert!(!args.flag("json"));
+        // Die letzte Angabe gilt
+        assert_eq!(args.value("port"), Some("2".to_string()));
+        assert_eq!(args.finish(), Ok(()));
+    }
+
+    #[test]
+    fn unknown_and_incomplete_options_are_usage_errors() {
+        // Ein Wert fehlt am Ende
+        assert!(is_usage(parse(&["--user"])));
+        // Ein Schalter nimmt keinen Wert, der folgende Wert bleibt übrig
+        let mut args = parse(&["--json", "x"]).unwrap();
+        assert!(args.flag("json"));
+        assert!(is_usage(args.finish()));
+
+        let mut args = parse(&["--user", "max", "--bogus", "1"]).unwrap();
+        args.required("user").unwrap();
+        assert_eq!(
+            args.finish(),
+            Err(Failure::Usage("Unbekannte Option: --bogus".to_string()))
+        );
+        assert!(is_usage(parse(&[]).unwrap().required("user")));
+        assert!(is_usage(parse(&[]).unwrap().package()));
+        assert!(is_usage(parse(&["a.nilo", "b.nilo"]).unwrap().package()));
+        assert!(is_usage(
+            parse(&["--target", "ohne-pfad"]).unwrap().custom_targets()
+        ));
+        assert!(is_usage(
+            parse(&["--conflict", "egal"]).unwrap().conflict_policy()
+        ));
+        assert!(is_usage(
+            parse(&["--folders", "keller"]).unwrap().export_options()
+        ));
+    }
+
+    #[test]
+    fn usage_errors_exit_with_2() {
+        assert_eq!(run_with(&[]), 2);
+        assert_eq!(run_with(&["frobnicate"]), 2);
+        assert_eq!(run_with(&["inspect"]), 2);
+        assert_eq!(run_with(&["inspect", "a.nilo", "--bogus", "x"]), 2);
+        assert_eq!(run_with(&["export", "--user"]), 2);
+        assert_eq!(
+            run_with(&["export", "--folders", "keller", "--user", "max"]),
+            2
+        );
+        assert_eq!(
+            run_with(&["import", "a.nilo", "--conflict", "egal", "--user", "max"]),
+            2
+        );
+        assert_eq!(
+            run_with(&["receive", "--user", "max", "--port", "viele"]),
+            2
+        );
+        assert_eq!(
+            run_with(&[
+                "verify",
+                "a.nilo",
+                "--password-env",
+                "A",
+                "--password-stdin"
+            ]),
+            2
+        );
+        assert_eq!(run_with(&["help"]), 0);
+        // Richtiger Aufruf, aber das Paket fehlt: Fehler statt falscher Aufruf
+        let missing =
+            std::env::temp_dir().join(format!("nilo-cli-fehlt-{}.nilo", std::process::id()));
+        assert_eq!(run_with(&["inspect", &missing.display().to_string()]), 1);
+    }
+
+    #[test]
+    fn password_sources() {
+        let no_stdin = || Cursor::new(Vec::new());
+        // Ohne Angabe leer; nur die erste Zeile zählt
+        assert_eq!(
+            parse(&[]).unwrap().password_from(no_stdin()),
+            Ok(String::new())
+        );
+        let stdin = Cursor::new(b"geheim\nzweite Zeile\n".to_vec());
+        assert_eq!(
+            parse(&["--password-stdin"]).unwrap().password_from(stdin),
+            Ok("geheim".to_string())
+        );
+
+        let file = std::env::temp_dir().join(format!("nilo-cli-password-{}", std::process::id()));
+        fs::write(&file, "aus Datei\r\n").unwrap();
+        let path = file.display().to_string();
+        assert_eq!(
+            parse(&["--password-file", &path])
+                .unwrap()
+                .password_from(no_stdin()),
+            Ok("aus Datei".to_string())
+        );
+        fs::remove_file(&file).unwrap();
+        assert!(matches!(
+            parse(&["--password-file", &path])
+                .unwrap()
+                .password_from(no_stdin()),
+            Err(Failure::Error(_))
+        ));
+
+        let var = format!("NILO_CLI_TEST_PASSWORD_{}", std::process::id());
+        env::set_var(&var, "aus Variable");
+        assert_eq!(
+            parse(&["--password-env", &var])
+                .unwrap()
+                .password_from(no_stdin()),
+            Ok("aus Variable".to_string())
+        );
+        env::remove_var(&var);
+        assert!(matches!(
+            parse(&["--password-env", &var])
+                .unwrap()
+                .password_from(no_stdin()),
+            Err(Failure::Error(_))
+        ));
+
+        // Höchstens eine Quelle
+        let mut args = parse(&["--password-file", &path, "--password-stdin"]).unwrap();
+        assert!(is_usage(args.password_from(no_stdin())));
+    }
+}
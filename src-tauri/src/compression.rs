@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+// Kompression einzelner Archivblöcke. Jeder Block wird für sich komprimiert, damit
+// mehrere Blöcke parallel bearbeitet und beim Lesen einzeln entpackt werden können.
+
+pub const DEFLATE_DEFAULT_LEVEL: i32 = 6;
+pub const ZSTD_DEFAULT_LEVEL: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionMethod {
+    None,
+    Deflate,
+    Zstd,
+}
+
+impl CompressionMethod {
+    /// Kennung im Archiv-Eintrag.
+    pub fn id(&self) -> Option<&'static str> {
+        match self {
+            CompressionMethod::None => None,
+            CompressionMethod::Deflate => Some("deflate"),
+            CompressionMethod::Zstd => Some("zstd"),
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "deflate" => Some(CompressionMethod::Deflate),
+            "zstd" => Some(CompressionMethod::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// Kompressionseinstellung für den Export.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CompressionSettings {
+    pub method: CompressionMethod,
+    /// Stufe; ohne Angabe der Standard des Verfahrens (Deflate 0–9, Zstd 1–22).
+    #[serde(default)]
+    pub level: Option<i32>,
+}
+
+impl Default for CompressionSettings {
+    fn default() -> Self {
+        CompressionSettings {
+            method: CompressionMethod::Zstd,
+            level: None,
+        }
+    }
+}
+
+impl CompressionSettings {
+    /// Stufe auf den gültigen Bereich des Verfahrens begrenzt.
+    pub fn effective_level(&self) -> i32 {
+        match self.method {
+            CompressionMethod::None => 0,
+            CompressionMethod::Deflate => self.level.unwrap_or(DEFLATE_DEFAULT_LEVEL).clamp(0, 9),
+            CompressionMethod::Zstd => self.level.unwrap_or(ZSTD_DEFAULT_LEVEL).clamp(1, 22),
+        }
+    }
+}
+
+/// Dateitypen, die bereits komprimiert sind und beim Packen unverändert bleiben
+/// (Bilder, Video, Audio, Archive und Office-Formate, die intern ZIP sind).
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "webp", "heic", "heif", "avif", "mp4", "m4v", "mkv", "mov", "avi",
+    "wmv", "webm", "mp3", "m4a", "aac", "ogg", "opus", "flac", "wma", "zip", "7z", "rar", "gz",
+    "tgz", "bz2", "xz", "zst", "cab", "jar", "apk", "msi", "docx", "xlsx", "pptx", "odt", "ods",
+    "odp", "epub", "pdf",
+];
+
+/// Prüft anhand der Dateiendung, ob sich Kompression lohnt.
+pub fn is_compressed_type(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| {
+            let e = e.to_ascii_lowercase();
+            COMPRESSED_EXTENSIONS.contains(&e.as_str())
+        })
+        .unwrap_or(false)
+}
+
+pub fn compress_block(method: CompressionMethod, level: i32, data: &[u8]) -> io::Result<Vec<u8>> {
+    match method {
+        CompressionMethod::None => Ok(data.to_vec()),
+        CompressionMethod::Deflate => {
+            let mut encoder = flate2::write::DeflateEncoder::new(
+                Vec::with_capacity(data.len() / 2),
+                flate2::Compression::new(level as u32),
+            );
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        CompressionMethod::Zstd => zstd::bulk::compress(data, level),
+    }
+}
+
+/// Entpackt einen Block; `max_len` begrenzt die Ausgabe auf die Blockgröße.
+pub fn decompress_block(
+    method: CompressionMethod,
+    data: &[u8],
+    max_len: usize,
+) -> io::Result<Vec<u8>> {
+    let out = match method {
+        CompressionMethod::None => data.to_vec(),
+        CompressionMethod::Deflate => {
+            let mut out = Vec::new();
+            flate2::read::DeflateDecoder::new(data)
+                .take(max_len as u64 + 1)
+                .read_to_end(&mut out)?;
+            out
+        }
+        CompressionMethod::Zstd => zstd::bulk::decompress(data, max_len)?,
+    };
+    if out.len() > max_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Entpackter Block ist zu groß",
+        ));
+    }
+    Ok(out)
+}
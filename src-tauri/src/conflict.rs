@@ -0,0 +1,403 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::journal::ImportJournal;
+use crate::report;
+
+// Umgang mit Dateien, die beim Import am Zielort schon vorhanden sind. Die neue Datei
+// liegt zu diesem Zeitpunkt bereits vollständig als Teildatei neben dem Ziel; erst
+// hier wird entschieden, ob sie das Ziel ersetzt. Inhaltsgleiche Dateien (gleiche
+// Größe und Prüfsumme) gelten nicht als Konflikt.
+
+/// Vorgehen, wenn eine Datei am Zielort schon existiert.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Vorhandene Datei ersetzen.
+    #[default]
+    Overwrite,
+    /// Vorhandene Datei behalten.
+    Skip,
+    /// Nur ersetzen, wenn die Datei im Paket neuer ist.
+    KeepNewer,
+    /// Neue Datei als `Name (NiloShift).ext` daneben ablegen.
+    Rename,
+    /// Vorhandene Datei in den Sicherungsordner verschieben, dann ersetzen.
+    Backup,
+}
+
+/// Was mit der neuen Datei geschehen soll.
+pub enum Resolution {
+    /// An diesen Ort verschieben; `conflict` beschreibt, wie ein Konflikt gelöst wurde.
+    Write {
+        target: PathBuf,
+        conflict: Option<String>,
+    },
+    /// Nicht übernehmen, die vorhandene Datei bleibt.
+    Keep { reason: String, conflict: bool },
+}
+
+pub struct ConflictResolver {
+    policy: ConflictPolicy,
+    /// Zielprofil; Sicherungen behalten ihren Pfad relativ dazu.
+    user_base: PathBuf,
+    backup_dir: PathBuf,
+}
+
+impl ConflictResolver {
+    pub fn new(policy: ConflictPolicy, user_base: &Path) -> Self {
+        ConflictResolver {
+            policy,
+            user_base: user_base.to_path_buf(),
+            backup_dir: user_base
+                .join("AppData/Local/NiloShift/backup")
+                .join(format!("import-{}", report::now_secs())),
+        }
+    }
+
+    /// Entscheidet für die fertig geschriebene Datei `incoming` mit der Änderungszeit
+    /// aus dem Paket, ob und wohin sie übernommen wird. Verschiebungen vorhandener
+    /// Dateien werden in `journal` festgehalten.
+    pub fn resolve(
+        &self,
+        incoming: &Path,
+        modified: Option<u64>,
+        target: &Path,
+        journal: &mut ImportJournal,
+    ) -> io::Result<Resolution> {
+        let existing = match fs::metadata(target) {
+            Ok(meta) if meta.is_file() => meta,
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "Am Zielort liegt ein Ordner",
+                ))
+            }
+            Err(_) => {
+                return Ok(Resolution::Write {
+                    target: target.to_path_buf(),
+                    conflict: None,
+                })
+            }
+        };
+        if fs::metadata(incoming)?.len() == existing.len()
+            && file_sha256(incoming)? == file_sha256(target)?
+        {
+            return Ok(Resolution::Keep {
+                reason: "Identisch mit vorhandener Datei".to_string(),
+                conflict: false,
+            });
+        }
+
+        let write = |note: String| {
+            Ok(Resolution::Write {
+                target: target.to_path_buf(),
+                conflict: Some(note),
+            })
+        };
+        match self.policy {
+            ConflictPolicy::Overwrite => write("Vorhandene Datei ersetzt".to_string()),
+            ConflictPolicy::Skip => Ok(Resolution::Keep {
+                reason: "Vorhandene Datei behalten".to_string(),
+                conflict: true,
+            }),
+            ConflictPolicy::KeepNewer => {
+                let existing_modified = existing
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs());
+                match (modified, existing_modified) {
+                    (Some(new), Some(old)) if new > old => {
+                        write("Ältere vorhandene Datei ersetzt".to_string())
+                    }
+                    _ => Ok(Resolution::Keep {
+                        reason: "Vorhandene Datei ist neuer".to_string(),
+                        conflict: true,
+                    }),
+                }
+            }
+            ConflictPolicy::Rename => {
+                let renamed = renamed_path(target);
+                Ok(Resolution::Write {
+                    conflict: Some(format!("Abgelegt als {}", renamed.display())),
+                    target: renamed,
+                })
+            }
+            ConflictPolicy::Backup => {
+                let backup = self.backup_path(target);
+                if let Some(parent) = backup.parent() {
+                    journal.create_dir_all(parent)?;
+                }
+                journal.move_existing(target, &backup)?;
+                write(format!(
+                    "Vorhandene Datei gesichert in {}",
+                    backup.display()
+                ))
+            }
+        }
+    }
+
+    /// Ort der Sicherung: Pfad relativ zum Profil, andere Pfade ohne Laufwerk.
+    fn backup_path(&self, target: &Path) -> PathBuf {
+        let rel = target.strip_prefix(&self.user_base).unwrap_or(target);
+        rel.components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name),
+                _ => None,
+            })
+            .fold(self.backup_dir.clone(), |p, name| p.join(name))
+    }
+}
+
+/// `Bericht.docx` → `Bericht (NiloShift).docx`, bei Bedarf `Bericht (NiloShift 2).docx` usw.
+fn renamed_path(target: &Path) -> PathBuf {
+    let stem = target
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let ext = target
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| {
+            let suffix = if n == 1 {
+                "NiloShift".to_string()
+            } else {
+                format!("NiloShift {}", n)
+            };
+            target.with_file_name(format!("{} ({}){}", stem, suffix, ext))
+        })
+        .find(|p| fs::symlink_metadata(p).is_err())
+        .expect("unbegrenzte Folge")
+}
+
+fn file_sha256(path: &Path) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Profil mit `Documents/Bericht.docx` (Inhalt "alt") und einer Teildatei daneben.
+    struct Fixture {
+        root: PathBuf,
+        base: PathBuf,
+        target: PathBuf,
+        incoming: PathBuf,
+        journal: ImportJournal,
+    }
+
+    impl Fixture {
+        fn new(name: &str, incoming: &[u8]) -> Self {
+            let root =
+                std::env::temp_dir().join(format!("nilo-conflict-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            let base = root.join("profile");
+            fs::create_dir_all(base.join("Documents")).unwrap();
+            let target = base.join("Documents/Bericht.docx");
+            fs::write(&target, b"alt").unwrap();
+            let part = base.join("Documents/Bericht.docx.nilo-part");
+            fs::write(&part, incoming).unwrap();
+            let journal = ImportJournal::create(&base, Path::new("paket.nilo")).unwrap();
+            Fixture {
+                root,
+                base,
+                target,
+                incoming: part,
+                journal,
+            }
+        }
+
+        fn resolve(&mut self, policy: ConflictPolicy, modified: Option<u64>) -> Resolution {
+            ConflictResolver::new(policy, &self.base)
+                .resolve(&self.incoming, modified, &self.target, &mut self.journal)
+                .unwrap()
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(self.journal.dir());
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn written(resolution: Resolution) -> (PathBuf, Option<String>) {
+        match resolution {
+            Resolution::Write { target, conflict } => (target, conflict),
+            Resolution::Keep { reason, .. } => panic!("behalten: {}", reason),
+        }
+    }
+
+    fn kept(resolution: Resolution) -> (String, bool) {
+        match resolution {
+            Resolution::Keep { reason, conflict } => (reason, conflict),
+            Resolution::Write { target, .. } => panic!("geschrieben: {}", target.display()),
+        }
+    }
+
+    fn set_modified(path: &Path, secs: u64) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn missing_target_is_no_conflict() {
+        let mut f = Fixture::new("missing", b"neu");
+        fs::remove_file(&f.target).unwrap();
+        let (target, conflict) = written(f.resolve(ConflictPolicy::Skip, None));
+        assert_eq!(target, f.target);
+        assert!(conflict.is_none());
+    }
+
+    #[test]
+    fn identical_file_is_no_conflict() {
+        for policy in [
+            ConflictPolicy::Overwrite,
+            ConflictPolicy::Rename,
+            ConflictPolicy::Backup,
+        ] {
+            let mut f = Fixture::new("identical", b"alt");
+            let (reason, conflict) = kept(f.resolve(policy, None));
+            assert_eq!(reason, "Identisch mit vorhandener Datei");
+            assert!(!conflict);
+            assert_eq!(fs::read(&f.target).unwrap(), b"alt");
+        }
+        // Gleiche Größe, anderer Inhalt: Konflikt
+        let mut f = Fixture::new("same-size", b"neu");
+        let (_, conflict) = kept(f.resolve(ConflictPolicy::Skip, None));
+        assert!(conflict);
+    }
+
+    #[test]
+    fn target_is_a_directory() {
+        let mut f = Fixture::new("directory", b"neu");
+        fs::remove_file(&f.target).unwrap();
+        fs::create_dir(&f.target).unwrap();
+        let err = ConflictResolver::new(ConflictPolicy::Overwrite, &f.base)
+            .resolve(&f.incoming, None, &f.target, &mut f.journal)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn overwrite() {
+        let mut f = Fixture::new("overwrite", b"neuer Inhalt");
+        let (target, conflict) = written(f.resolve(ConflictPolicy::Overwrite, None));
+        assert_eq!(target, f.target);
+        assert_eq!(conflict.as_deref(), Some("Vorhandene Datei ersetzt"));
+    }
+
+    #[test]
+    fn skip() {
+        let mut f = Fixture::new("skip", b"neuer Inhalt");
+        let (reason, conflict) = kept(f.resolve(ConflictPolicy::Skip, None));
+        assert_eq!(reason, "Vorhandene Datei behalten");
+        assert!(conflict);
+        assert_eq!(fs::read(&f.target).unwrap(), b"alt");
+    }
+
+    #[test]
+    fn keep_newer() {
+        let mut f = Fixture::new("keep-newer", b"neuer Inhalt");
+        set_modified(&f.target, 1_000_000);
+        let (target, _) = written(f.resolve(ConflictPolicy::KeepNewer, Some(2_000_000)));
+        assert_eq!(target, f.target);
+        let (reason, conflict) = kept(f.resolve(ConflictPolicy::KeepNewer, Some(500_000)));
+        assert_eq!(reason, "Vorhandene Datei ist neuer");
+        assert!(conflict);
+        // Gleich alt oder ohne Zeit im Paket: vorhandene Datei bleibt
+        kept(f.resolve(ConflictPolicy::KeepNewer, Some(1_000_000)));
+        kept(f.resolve(ConflictPolicy::KeepNewer, None));
+    }
+
+    #[test]
+    fn rename() {
+        let mut f = Fixture::new("rename", b"neuer Inhalt");
+        let (target, conflict) = written(f.resolve(ConflictPolicy::Rename, None));
+        let first = f.base.join("Documents/Bericht (NiloShift).docx");
+        assert_eq!(target, first);
+        assert_eq!(conflict, Some(format!("Abgelegt als {}", first.display())));
+        assert_eq!(fs::read(&f.target).unwrap(), b"alt");
+
+        fs::write(&first, b"belegt").unwrap();
+        let (target, _) = written(f.resolve(ConflictPolicy::Rename, None));
+        assert_eq!(target, f.base.join("Documents/Bericht (NiloShift 2).docx"));
+    }
+
+    #[test]
+    fn renamed_path_numbering() {
+        let dir = std::env::temp_dir().join(format!("nilo-renamed-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Bericht (NiloShift).docx"), b"").unwrap();
+        fs::write(dir.join("Bericht (NiloShift 2).docx"), b"").unwrap();
+        assert_eq!(
+            renamed_path(&dir.join("Bericht.docx")),
+            dir.join("Bericht (NiloShift 3).docx")
+        );
+        // Ohne Endung und mit mehreren Punkten
+        assert_eq!(
+            renamed_path(&dir.join("README")),
+            dir.join("README (NiloShift)")
+        );
+        assert_eq!(
+            renamed_path(&dir.join("archiv.tar.gz")),
+            dir.join("archiv.tar (NiloShift).gz")
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn backup() {
+        let mut f = Fixture::new("backup", b"neuer Inhalt");
+        let resolver = ConflictResolver::new(ConflictPolicy::Backup, &f.base);
+        let (target, conflict) = written(
+            resolver
+                .resolve(&f.incoming, None, &f.target, &mut f.journal)
+                .unwrap(),
+        );
+        assert_eq!(target, f.target);
+        // Sicherung behält den Pfad relativ zum Profil
+        let backup = resolver.backup_dir.join("Documents/Bericht.docx");
+        assert!(backup.starts_with(f.base.join("AppData/Local/NiloShift/backup")));
+        assert_eq!(fs::read(&backup).unwrap(), b"alt");
+        assert!(!f.target.exists());
+        assert_eq!(
+            conflict,
+            Some(format!(
+                "Vorhandene Datei gesichert in {}",
+                backup.display()
+            ))
+        );
+    }
+
+    #[test]
+    fn backup_path_outside_profile() {
+        let resolver = ConflictResolver::new(ConflictPolicy::Backup, Path::new("/home/nutzer"));
+        assert_eq!(
+            resolver.backup_path(Path::new("/home/nutzer/Documents/a.txt")),
+            resolver.backup_dir.join("Documents/a.txt")
+        );
+        assert_eq!(
+            resolver.backup_path(Path::new("/daten/projekt/a.txt")),
+            resolver.backup_dir.join("daten/projekt/a.txt")
+        );
+    }
+}
@@ -0,0 +1,489 @@
+use aes_gcm::{
+    aead::{AeadInPlace, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::RngCore;
+use std::io::{self, BufRead, Read, Write};
+
+pub const SALT_LEN: usize = 16;
+pub const NONCE_LEN: usize = 12;
+pub const KEY_LEN: usize = 32;
+
+// Argon2id-Standardkosten: 64 MiB Speicher, 3 Durchläufe, 1 Lane
+const DEFAULT_M_COST: u32 = 64 * 1024;
+const DEFAULT_T_COST: u32 = 3;
+const DEFAULT_P_COST: u32 = 1;
+
+// Obergrenzen beim Lesen, damit ein manipuliertes Paket nicht beliebig viel RAM anfordert
+const MAX_M_COST: u32 = 4 * 1024 * 1024;
+const MAX_T_COST: u32 = 64;
+const MAX_P_COST: u32 = 64;
+
+/// Parameter der Schlüsselableitung, die im Paket gespeichert werden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    pub salt: [u8; SALT_LEN],
+}
+
+impl KdfParams {
+    /// Neue Parameter mit zufälligem Salt für ein neues Paket.
+    pub fn generate() -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        KdfParams {
+            m_cost: DEFAULT_M_COST,
+            t_cost: DEFAULT_T_COST,
+            p_cost: DEFAULT_P_COST,
+            salt,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.m_cost == 0 || self.m_cost > MAX_M_COST {
+            return Err(format!("Ungültiger KDF-Speicherparameter: {}", self.m_cost));
+        }
+        if self.t_cost == 0 || self.t_cost > MAX_T_COST {
+            return Err(format!("Ungültiger KDF-Zeitparameter: {}", self.t_cost));
+        }
+        if self.p_cost == 0 || self.p_cost > MAX_P_COST {
+            return Err(format!(
+                "Ungültiger KDF-Parallelitätsparameter: {}",
+                self.p_cost
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Leitet den AES-256-Schlüssel per Argon2id aus dem Passwort ab.
+pub fn derive_key(password: &str, params: &KdfParams) -> Result<[u8; KEY_LEN], String> {
+    params.validate()?;
+    let argon_params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(KEY_LEN))
+        .map_err(|e| format!("Ungültige KDF-Parameter: {}", e))?;
+    let argon = Argon2::new(Algorithm::Argon2id, Version::V0x13, argon_params);
+    let mut key = [0u8; KEY_LEN];
+    argon
+        .hash_password_into(password.as_bytes(), &params.salt, &mut key)
+        .map_err(|e| format!("Fehler bei der Schlüsselableitung: {}", e))?;
+    Ok(key)
+}
+
+/// Schlüssel alter Pakete: die ersten 32 Passwort-Bytes, mit Nullen aufgefüllt.
+/// Nur noch zum Öffnen bestehender Pakete verwenden.
+pub fn legacy_key(password: &str) -> [u8; KEY_LEN] {
+    let mut key = [0u8; KEY_LEN];
+    for (i, &b) in password.as_bytes().iter().enumerate() {
+        if i >= KEY_LEN {
+            break;
+        }
+        key[i] = b;
+    }
+    key
+}
+
+/// Länge des zufälligen Nonce-Präfixes im Stream-Modus. Die restlichen 5 Bytes der
+/// 12-Byte-Nonce sind Blockzähler (u32 BE) und ein Byte aus Abschnitt und Kennzeichen
+/// für den letzten Block.
+pub const STREAM_NONCE_PREFIX_LEN: usize = 7;
+pub const TAG_LEN: usize = 16;
+
+/// Höchste Abschnittsnummer. Ein Abschnitt beginnt, wenn ein unterbrochenes Paket
+/// fortgesetzt wird: Die Blöcke ab dem Zwischenstand werden mit neuer Nonce
+/// geschrieben, auch wenn der abgebrochene Lauf dieselben Zähler schon verwendet hat.
+pub const MAX_STREAM_SEGMENT: u8 = 63;
+
+/// Letztes Nonce-Byte: Abschnitt in den oberen 6 Bits, Bit 0 = letzter Block. Bit 1
+/// bleibt frei, dadurch kommt der Wert 2 des Manifests nie vor.
+fn stream_nonce(
+    prefix: &[u8; STREAM_NONCE_PREFIX_LEN],
+    counter: u32,
+    segment: u8,
+    last: bool,
+) -> [u8; NONCE_LEN] {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..STREAM_NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[STREAM_NONCE_PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = (segment << 2) | last as u8;
+    nonce
+}
+
+/// Nonce für den separat verschlüsselten Manifest-Block. Das letzte Byte ist 2 und
+/// kommt bei Stream-Blöcken nie vor, die Nonce ist also eindeutig.
+pub fn manifest_nonce(prefix: &[u8; STREAM_NONCE_PREFIX_LEN]) -> [u8; NONCE_LEN] {
+    let mut nonce = stream_nonce(prefix, 0, 0, false);
+    nonce[NONCE_LEN - 1] = 2;
+    nonce
+}
+
+fn stream_error(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Verschlüsselt einen Datenstrom blockweise (AES-256-GCM, STREAM-Konstruktion).
+/// Jeder Block bekommt eine eigene Nonce aus Zähler und Letzter-Block-Kennzeichen,
+/// dadurch fallen vertauschte, fehlende oder abgeschnittene Blöcke beim Lesen auf.
+pub struct ChunkEncryptor<W: Write> {
+    inner: W,
+    cipher: Aes256Gcm,
+    nonce_prefix: [u8; STREAM_NONCE_PREFIX_LEN],
+    aad: Vec<u8>,
+    chunk_size: usize,
+    buffer: Vec<u8>,
+    counter: u32,
+    segment: u8,
+}
+
+impl<W: Write> ChunkEncryptor<W> {
+    pub fn new(
+        inner: W,
+        key: &[u8; KEY_LEN],
+        nonce_prefix: [u8; STREAM_NONCE_PREFIX_LEN],
+        chunk_size: usize,
+        aad: Vec<u8>,
+    ) -> Self {
+        ChunkEncryptor {
+            inner,
+            cipher: Aes256Gcm::new(key.into()),
+            nonce_prefix,
+            aad,
+            chunk_size,
+            buffer: Vec::with_capacity(chunk_size + TAG_LEN),
+            counter: 0,
+            segment: 0,
+        }
+    }
+
+    /// Setzt einen Stream fort, von dem `counter` Blöcke geschrieben sind. `segment`
+    /// muss größer sein als jeder Abschnitt, der für diesen Stream schon verwendet wurde.
+    pub fn resume(
+        inner: W,
+        key: &[u8; KEY_LEN],
+        nonce_prefix: [u8; STREAM_NONCE_PREFIX_LEN],
+        chunk_size: usize,
+        aad: Vec<u8>,
+        counter: u32,
+        segment: u8,
+    ) -> io::Result<Self> {
+        if segment > MAX_STREAM_SEGMENT {
+            return Err(stream_error("Paket wurde zu oft fortgesetzt".to_string()));
+        }
+        let mut encryptor = Self::new(inner, key, nonce_prefix, chunk_size, aad);
+        encryptor.counter = counter;
+        encryptor.segment = segment;
+        Ok(encryptor)
+    }
+
+    /// Anzahl der geschriebenen Blöcke.
+    pub fn chunks(&self) -> u32 {
+        self.counter
+    }
+
+    /// Versiegelt einen vollen Puffer sofort, statt auf weitere Daten zu warten. Endet
+    /// der Klartext an einer Blockgrenze, liegt danach alles verschlüsselt im inneren
+    /// Writer (Zwischenstand beim Export).
+    pub fn seal_full(&mut self) -> io::Result<()> {
+        if self.buffer.len() == self.chunk_size {
+            self.seal_chunk(false)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    fn seal_chunk(&mut self, last: bool) -> io::Result<()> {
+        let nonce = stream_nonce(&self.nonce_prefix, self.counter, self.segment, last);
+        self.cipher
+            .encrypt_in_place(Nonce::from_slice(&nonce), &self.aad, &mut self.buffer)
+            .map_err(|e| stream_error(format!("Fehler beim Verschlüsseln: {}", e)))?;
+        self.inner.write_all(&self.buffer)?;
+        self.buffer.clear();
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| stream_error("Zu viele Blöcke für ein Paket".to_string()))?;
+        Ok(())
+    }
+
+    /// Schreibt den letzten Block und gibt den inneren Writer zurück.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.seal_chunk(true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for ChunkEncryptor<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Ein voller Puffer wird erst versiegelt, wenn weitere Daten kommen –
+        // so kann finish() den letzten Block immer als solchen kennzeichnen.
+        if self.buffer.len() == self.chunk_size && !buf.is_empty() {
+            self.seal_chunk(false)?;
+        }
+        let take = (self.chunk_size - self.buffer.len()).min(buf.len());
+        self.buffer.extend_from_slice(&buf[..take]);
+        Ok(take)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Gegenstück zu [`ChunkEncryptor`]: entschlüsselt Block für Block beim Lesen.
+pub struct ChunkDecryptor<R: BufRead> {
+    inner: R,
+    cipher: Aes256Gcm,
+    nonce_prefix: [u8; STREAM_NONCE_PREFIX_LEN],
+    aad: Vec<u8>,
+    chunk_size: usize,
+    buffer: Vec<u8>,
+    /// Arbeitskopie eines Blocks für einen Entschlüsselungsversuch.
+    attempt: Vec<u8>,
+    pos: usize,
+    counter: u32,
+    segment: u8,
+    finished: bool,
+}
+
+impl<R: BufRead> ChunkDecryptor<R> {
+    pub fn new(
+        inner: R,
+        key: &[u8; KEY_LEN],
+        nonce_prefix: [u8; STREAM_NONCE_PREFIX_LEN],
+        chunk_size: usize,
+        aad: Vec<u8>,
+    ) -> Self {
+        ChunkDecryptor {
+            inner,
+            cipher: Aes256Gcm::new(key.into()),
+            nonce_prefix,
+            aad,
+            chunk_size,
+            buffer: Vec::with_capacity(chunk_size + TAG_LEN),
+            attempt: Vec::with_capacity(chunk_size + TAG_LEN),
+            pos: 0,
+            counter: 0,
+            segment: 0,
+            finished: false,
+        }
+    }
+
+    fn open_chunk(&mut self) -> io::Result<()> {
+        self.buffer.clear();
+        self.pos = 0;
+        let full = (self.chunk_size + TAG_LEN) as u64;
+        (&mut self.inner).take(full).read_to_end(&mut self.buffer)?;
+        // Kürzerer Block oder Dateiende direkt dahinter = letzter Block
+        let last = (self.buffer.len() as u64) < full || self.inner.fill_buf()?.is_empty();
+        if self.buffer.len() < TAG_LEN {
+            return Err(stream_error(format!(
+                "Paket ist unvollständig (Block {} fehlt)",
+                self.counter
+            )));
+        }
+        // Ein fortgesetztes Paket wechselt an einer Blockgrenze in einen späteren
+        // Abschnitt; Abschnitte steigen nur. Jeder Versuch entschlüsselt eine Kopie,
+        // damit ein Fehlversuch den Block nicht verändert.
+        let segment = (self.segment..=MAX_STREAM_SEGMENT).find(|segment| {
+            let nonce = stream_nonce(&self.nonce_prefix, self.counter, *segment, last);
+            self.attempt.clear();
+            self.attempt.extend_from_slice(&self.buffer);
+            self.cipher
+                .decrypt_in_place(Nonce::from_slice(&nonce), &self.aad, &mut self.attempt)
+                .is_ok()
+        });
+        let Some(segment) = segment else {
+            return Err(stream_error(if self.counter == 0 {
+                "Entschlüsselung fehlgeschlagen (falsches Passwort?)".to_string()
+            } else {
+                format!(
+                    "Paket ist beschädigt oder unvollständig (Block {})",
+                    self.counter
+                )
+            }));
+        };
+        std::mem::swap(&mut self.buffer, &mut self.attempt);
+        self.segment = segment;
+        self.counter = self.counter.wrapping_add(1);
+        self.finished = last;
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for ChunkDecryptor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buffer.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.open_chunk()?;
+        }
+        let n = (self.buffer.len() - self.pos).min(buf.len());
+        buf[..n].copy_from_slice(&self.buffer[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; KEY_LEN] = [7; KEY_LEN];
+    const PREFIX: [u8; STREAM_NONCE_PREFIX_LEN] = [1; STREAM_NONCE_PREFIX_LEN];
+    const CHUNK: usize = 16;
+    /// Verschlüsselter voller Block.
+    const SEALED: usize = CHUNK + TAG_LEN;
+
+    /// Drei volle Blöcke und ein kurzer letzter Block.
+    fn sealed() -> (Vec<u8>, Vec<u8>) {
+        let plain: Vec<u8> = (0..CHUNK as u8 * 3 + 5).collect();
+        let mut encryptor = ChunkEncryptor::new(Vec::new(), &KEY, PREFIX, CHUNK, b"aad".to_vec());
+        encryptor.write_all(&plain).unwrap();
+        let sealed = encryptor.finish().unwrap();
+        assert_eq!(sealed.len(), 3 * SEALED + 5 + TAG_LEN);
+        (plain, sealed)
+    }
+
+    fn open(sealed: &[u8]) -> io::Result<Vec<u8>> {
+        let mut decryptor = ChunkDecryptor::new(sealed, &KEY, PREFIX, CHUNK, b"aad".to_vec());
+        let mut plain = Vec::new();
+        decryptor.read_to_end(&mut plain)?;
+        Ok(plain)
+    }
+
+    #[test]
+    fn stream_roundtrip() {
+        let (plain, sealed) = sealed();
+        assert_eq!(open(&sealed).unwrap(), plain);
+    }
+
+    #[test]
+    fn truncated_at_chunk_boundary() {
+        let (_, sealed) = sealed();
+        // Vor dem Schnitt endet ein Block, der nicht als letzter versiegelt ist
+        for chunks in 1..3 {
+            let err = open(&sealed[..chunks * SEALED]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn truncated_inside_chunk() {
+        let (_, sealed) = sealed();
+        assert!(open(&sealed[..2 * SEALED + 5]).is_err());
+        assert!(open(&sealed[..sealed.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn last_chunk_dropped() {
+        let (_, sealed) = sealed();
+        assert!(open(&sealed[..3 * SEALED]).is_err());
+    }
+
+    #[test]
+    fn chunks_swapped() {
+        let (_, sealed) = sealed();
+        let mut swapped = sealed[SEALED..2 * SEALED].to_vec();
+        swapped.extend_from_slice(&sealed[..SEALED]);
+        swapped.extend_from_slice(&sealed[2 * SEALED..]);
+        let err = open(&swapped).unwrap_err();
+        assert!(err.to_string().contains("falsches Passwort"), "{}", err);
+        let mut swapped = sealed[..SEALED].to_vec();
+        swapped.extend_from_slice(&sealed[2 * SEALED..3 * SEALED]);
+        swapped.extend_from_slice(&sealed[SEALED..2 * SEALED]);
+        swapped.extend_from_slice(&sealed[3 * SEALED..]);
+        let err = open(&swapped).unwrap_err();
+        assert!(err.to_string().contains("Block 1"), "{}", err);
+    }
+
+    #[test]
+    fn middle_chunk_dropped() {
+        let (_, sealed) = sealed();
+        let mut dropped = sealed[..SEALED].to_vec();
+        dropped.extend_from_slice(&sealed[2 * SEALED..]);
+        assert!(open(&dropped).is_err());
+    }
+
+    /// Wie `sealed`, aber nach dem ersten Block unterbrochen und zweimal fortgesetzt:
+    /// Block 1 im Abschnitt 1, Blöcke 2 und 3 im Abschnitt 3.
+    fn resumed() -> (Vec<u8>, Vec<u8>) {
+        let plain: Vec<u8> = (0..CHUNK as u8 * 3 + 5).collect();
+        let mut encryptor = ChunkEncryptor::new(Vec::new(), &KEY, PREFIX, CHUNK, b"aad".to_vec());
+        encryptor.write_all(&plain[..CHUNK]).unwrap();
+        encryptor.seal_full().unwrap();
+        assert_eq!(encryptor.chunks(), 1);
+        let sealed = std::mem::take(encryptor.get_mut());
+
+        let mut encryptor =
+            ChunkEncryptor::resume(sealed, &KEY, PREFIX, CHUNK, b"aad".to_vec(), 1, 1).unwrap();
+        encryptor.write_all(&plain[CHUNK..2 * CHUNK]).unwrap();
+        encryptor.seal_full().unwrap();
+        let sealed = std::mem::take(encryptor.get_mut());
+
+        let mut encryptor =
+            ChunkEncryptor::resume(sealed, &KEY, PREFIX, CHUNK, b"aad".to_vec(), 2, 3).unwrap();
+        encryptor.write_all(&plain[2 * CHUNK..]).unwrap();
+        let sealed = encryptor.finish().unwrap();
+        assert_eq!(sealed.len(), 3 * SEALED + 5 + TAG_LEN);
+        (plain, sealed)
+    }
+
+    #[test]
+    fn resumed_stream_across_segments() {
+        let (plain, sealed) = resumed();
+        assert_eq!(open(&sealed).unwrap(), plain);
+        // Kleine Lesepuffer: jeder Block wird genau einmal geöffnet
+        let mut decryptor = ChunkDecryptor::new(&sealed[..], &KEY, PREFIX, CHUNK, b"aad".to_vec());
+        let mut read = Vec::new();
+        let mut buf = [0u8; 3];
+        loop {
+            let n = decryptor.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            read.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(read, plain);
+    }
+
+    #[test]
+    fn resumed_stream_rejects_older_segment() {
+        let (_, fresh) = sealed();
+        let (_, sealed) = resumed();
+        // Nach einem Block aus Abschnitt 1 bzw. 3 folgen Blöcke aus Abschnitt 0
+        let mut mixed = sealed[..2 * SEALED].to_vec();
+        mixed.extend_from_slice(&fresh[2 * SEALED..]);
+        assert!(open(&mixed).is_err());
+        let mut mixed = sealed[..3 * SEALED].to_vec();
+        mixed.extend_from_slice(&fresh[3 * SEALED..]);
+        assert!(open(&mixed).is_err());
+    }
+
+    #[test]
+    fn resume_limits_segment() {
+        assert!(ChunkEncryptor::resume(
+            Vec::new(),
+            &KEY,
+            PREFIX,
+            CHUNK,
+            Vec::new(),
+            1,
+            MAX_STREAM_SEGMENT + 1
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn wrong_aad_fails() {
+        let (_, sealed) = sealed();
+        let mut decryptor = ChunkDecryptor::new(&sealed[..], &KEY, PREFIX, CHUNK, b"x".to_vec());
+        assert!(decryptor.read_to_end(&mut Vec::new()).is_err());
+    }
+}
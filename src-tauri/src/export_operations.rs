@@ -1,365 +1,778 @@
-use aes_gcm::{
-   aead::{Aead, KeyInit, OsRng},
-   Aes256Gcm, Nonce,
-};
-use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::io::{self, Write};
+use std::io;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
-use tauri::Emitter;
-use zip::write::{FileOptions, ZipWriter};
-use zip::CompressionMethod;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ExportOptions {
-   pub desktop: bool,
-   pub documents: bool,
-   pub pictures: bool,
-   pub videos: bool,
-   pub music: bool,
-   pub downloads: bool,
-   pub chrome: bool,
-   pub edge: bool,
-   pub firefox: bool,
-   pub outlook_signatures: bool,
-}
+use crate::archive::{ArchiveWriter, EntryHeader};
+use crate::compression::{self, CompressionSettings};
+use crate::crypto::{ChunkEncryptor, TAG_LEN};
+use crate::filter_rules::{FilterRules, RuleSet};
+use crate::jobs::{self, JobHandle};
+use crate::known_folders::{self, KnownFolder, KNOWN_FOLDERS};
+use crate::package_format::{
+   self, CategorySummary, CustomPath, PackageManifest, ResumablePackage, CUSTOM_ARCHIVE_PREFIX,
+   DEFAULT_CHUNK_SIZE,
+};
+use crate::profiles::{self, UserProfile};
+use crate::progress::{emit_progress, ProgressSink};
+use crate::recipients::{self, Credentials, Recipient};
+use crate::report::{self, TransferReport, TransferSummary};
+use crate::resume::{self, ExportState};
+use crate::snapshot::Snapshot;
+use crate::system_operations;
+use crate::volumes::{self, VolumeSet, VolumeWriter};
 
-#[derive(Serialize, Clone)]
-struct ProgressPayload {
-   percent: f32,
-   eta_ms: u64,
-   phase: &'static str,
-   processed: u64,
-   total: u64,
-}
+/// Archiv im verschlüsselten Paket, wie es der Export schreibt.
+type PackageArchive = ArchiveWriter<ChunkEncryptor<VolumeWriter>>;
 
-fn emit_progress(
-   app: &tauri::AppHandle,
-   start: Instant,
-   processed: u64,
-   total: u64,
-   phase: &'static str,
-) {
-   let percent = if total == 0 {
-       0.0
-   } else {
-       (processed as f32 / total as f32) * 100.0
-   };
-   let elapsed = start.elapsed().as_millis() as u64;
-   let eta_ms = if processed == 0 {
-       0
-   } else {
-       ((elapsed as f64) * ((total as f64 / processed as f64) - 1.0)).max(0.0) as u64
-   };
-   let _ = app.emit(
-       "export-progress",
-       ProgressPayload {
-           percent,
-           eta_ms,
-           phase,
-           processed,
-           total,
-       },
-   );
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExportOptions {
+   /// Auswahl je bekanntem Ordner, z. B. `"desktop": true` (siehe `known_folders`).
+   #[serde(flatten)]
+   pub folders: BTreeMap<String, bool>,
+   /// Zusätzliche Ordner außerhalb der bekannten Ordner.
+   #[serde(default)]
+   pub custom_paths: Vec<CustomPath>,
+   /// Filterregeln für alle Kategorien, zusätzlich zu den Standardregeln.
+   #[serde(default)]
+   pub rules: FilterRules,
+   /// Filterregeln je Kategorie, z. B. für `chrome` oder `custom-1`.
+   #[serde(default)]
+   pub category_rules: BTreeMap<String, FilterRules>,
+   /// Kompression der Dateien im Paket; ohne Angabe Zstd mit Standardstufe.
+   #[serde(default)]
+   pub compression: CompressionSettings,
 }
 
+const PROGRESS_EVENT: &str = "export-progress";
+
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn start_export_command(
    app: tauri::AppHandle,
    options: ExportOptions,
    password: String,
    export_path: Option<String>,
    selected_user: String,
-) -> Result<String, String> {
-   let output_path = match export_path {
-       Some(path) => PathBuf::from(path),
-       None => {
-           let desktop = PathBuf::from(r"C:\\Users")
-               .join(&selected_user)
-               .join("Desktop");
-           desktop.join("NiloShift_Export.nilo")
-       }
-   };
+   volume_size_mb: Option<u64>,
+   recipients: Option<Vec<String>>,
+   use_snapshot: Option<bool>,
+   job_id: Option<String>,
+   resume_interrupted: Option<bool>,
+   source_root: Option<String>,
+) -> Result<TransferSummary, String> {
+   run_export(
+       &ProgressSink::from(app),
+       options,
+       password,
+       export_path,
+       selected_user,
+       volume_size_mb,
+       recipients,
+       use_snapshot,
+       job_id,
+       resume_interrupted,
+       source_root,
+   )
+}
 
-   let temp_dir = std::env::temp_dir().join("niloshift_export");
-   if temp_dir.exists() {
-       let _ = fs::remove_dir_all(&temp_dir);
+/// Export ohne Fenster, auch für `niloshift-cli`; Parameter wie `start_export_command`.
+#[allow(clippy::too_many_arguments)]
+pub fn run_export(
+   sink: &ProgressSink,
+   options: ExportOptions,
+   password: String,
+   export_path: Option<String>,
+   selected_user: String,
+   volume_size_mb: Option<u64>,
+   recipients: Option<Vec<String>>,
+   use_snapshot: Option<bool>,
+   job_id: Option<String>,
+   resume_interrupted: Option<bool>,
+   source_root: Option<String>,
+) -> Result<TransferSummary, String> {
+   // Passwort und/oder öffentliche Schlüssel; jeder Empfänger kann das Paket allein öffnen
+   let mut unlock = Vec::new();
+   if !password.is_empty() {
+       unlock.push(Recipient::Password(password.clone()));
+   }
+   for key in recipients.unwrap_or_default() {
+       unlock.push(Recipient::PublicKey(recipients::parse_public_key(&key)?));
+   }
+   if unlock.is_empty() {
+       return Err("Passwort oder öffentlicher Schlüssel erforderlich".to_string());
    }
-   fs::create_dir_all(&temp_dir)
-       .map_err(|e| format!("Fehler beim Erstellen des temporären Ordners: {}", e))?;
 
-   let user_base = PathBuf::from(r"C:\\Users").join(&selected_user);
+   // Offline-Migration: Profil von einem angeschlossenen Datenträger statt von diesem Rechner
+   let source_root = profiles::source_root(source_root);
+   let profile = profiles::source_profile(source_root.as_deref(), &selected_user)?;
+   let output_path = output_path_for(export_path, &profile);
+   // Pausieren/Abbrechen über pause_job/cancel_job
+   let job = jobs::register(job_id.as_deref().unwrap_or("export"))?;
+
+   // Unterbrochenen Export fortsetzen; sonst gilt ein alter Zwischenstand nicht mehr
+   let state_path = resume::export_state_path(&output_path);
+   let interrupted: Option<ExportState> = if resume_interrupted.unwrap_or(false) {
+       resume::load(&state_path)
+   } else {
+       None
+   };
+   if interrupted.is_none() {
+       resume::remove(&state_path);
+   }
+   // Optional in Volumes aufteilen, z. B. 4095 MB für FAT32-Sticks; beim Fortsetzen wie bisher
+   let volume_size = match &interrupted {
+       Some(state) => state.volume_size,
+       None => volume_size_mb
+           .filter(|mb| *mb > 0)
+           .map(|mb| mb.saturating_mul(1024 * 1024)),
+   };
 
    // Sofortigen Status senden, damit die UI nicht im Idle bleibt
    let prep_start = Instant::now();
-   emit_progress(&app, prep_start, 0, 100, "Vorbereiten");
+   emit_progress(sink, PROGRESS_EVENT, prep_start, 0, 100, "Vorbereiten");
 
-   // Vorab: Anzahl Dateien zählen, um ETA/Progress zu berechnen (nur ausgewählte Quellen)
-   let mut total_files: u64 = 0;
-   if options.desktop {
-       total_files += count_dir_files(&user_base.join("Desktop"));
-   }
-   if options.documents {
-       total_files += count_dir_files(&user_base.join("Documents"));
+   // Ein fortgesetzter Export übernimmt Auswahl und Regeln aus dem Manifest des Pakets
+   let resumable = match &interrupted {
+       Some(state) => Some(open_interrupted(
+           &output_path,
+           state,
+           &password,
+           &selected_user,
+           &source_root,
+       )?),
+       None => None,
+   };
+   let manifest = match &resumable {
+       Some(package) => {
+           eprintln!(
+               "⏯️ Setze Export fort: {} Dateien bereits im Paket",
+               interrupted.as_ref().map_or(0, |s| s.completed.len())
+           );
+           package.manifest.clone()
+       }
+       None => new_manifest(&profile, &options)?,
+   };
+   let total_files: u64 = manifest.categories.iter().map(|c| c.files).sum();
+   let total_ops = total_files.saturating_add(1); // +1 für Abschluss
+   // Aus einer Schattenkopie lesen, damit geöffnete Browser-Datenbanken vollständig
+   // und in sich stimmig ins Paket kommen; wird am Ende wieder gelöscht
+   let snapshot = if use_snapshot.unwrap_or(false) {
+       emit_progress(sink, PROGRESS_EVENT, prep_start, 0, 100, "Schattenkopie");
+       Some(Snapshot::create(&profile.base)?)
+   } else {
+       None
+   };
+   let (archive, mut checkpoints) = match (resumable, interrupted) {
+       (Some(package), Some(state)) => {
+           let (archive, checkpoints) = resume_archive(
+               package,
+               state,
+               &output_path,
+               state_path.clone(),
+               options.compression,
+           )?;
+           (archive, Some(checkpoints))
+       }
+       _ => {
+           let archive =
+               create_archive(&output_path, &unlock, &manifest, options.compression, volume_size)
+                   .inspect_err(|_| remove_package(&output_path, volume_size))?;
+           // Fortsetzen braucht das Passwort, um den Inhaltsschlüssel wieder zu entpacken
+           let checkpoints = (!password.is_empty()).then(|| {
+               Checkpoints::new(
+                   state_path.clone(),
+                   ExportState {
+                       package: output_path.display().to_string(),
+                       selected_user: selected_user.clone(),
+                       source_root: source_root.clone(),
+                       volume_size,
+                       length: 0,
+                       chunks: 0,
+                       segment: 0,
+                       completed: Vec::new(),
+                       saved_at: 0,
+                   },
+                   DEFAULT_CHUNK_SIZE as u64,
+               )
+           });
+           (archive, checkpoints)
+       }
+   };
+   let mut processed: u64 = 0;
+   let start = Instant::now();
+   let mut report = TransferReport::new("export", &output_path);
+   if let Some(checkpoints) = &checkpoints {
+       for entry in &checkpoints.state.completed {
+           report.copied(&entry.path, entry.size);
+       }
    }
-   if options.pictures {
-       total_files += count_dir_files(&user_base.join("Pictures"));
+
+   let result = write_package(
+       sink,
+       archive,
+       &profile,
+       &manifest,
+       snapshot.as_ref(),
+       &mut checkpoints,
+       job.handle(),
+       start,
+       &mut processed,
+       total_ops,
+       &mut report,
+   );
+   let written = match result {
+       Ok(written) => written,
+       Err(e) => {
+           if job.handle().is_cancelled() {
+               remove_package(&output_path, volume_size);
+               resume::remove(&state_path);
+               emit_progress(sink, PROGRESS_EVENT, start, 0, total_ops, "Abgebrochen");
+               return Err("Export abgebrochen, unvollständiges Paket entfernt".to_string());
+           }
+           // Mit Zwischenstand bleibt das Paket zum Fortsetzen liegen, sonst wird es entfernt
+           if state_path.exists() {
+               return Err(format!(
+                   "{} – der Export kann ab dem letzten Zwischenstand fortgesetzt werden",
+                   e
+               ));
+           }
+           remove_package(&output_path, volume_size);
+           return Err(e);
+       }
+   };
+   resume::remove(&state_path);
+   processed = total_ops; // Fertig
+   emit_progress(sink, PROGRESS_EVENT, start, processed, total_ops, "Fertig");
+
+   let mut message = if volume_size.is_some() {
+       format!(
+           "Export erfolgreich in {} Volumes erstellt ({})",
+           written.len(),
+           written[0].display()
+       )
+   } else {
+       format!(
+           "Export erfolgreich nach {} erstellt",
+           output_path.display()
+       )
+   };
+   if !report.failed.is_empty() {
+       message.push_str(&format!(
+           " – {} Dateien konnten nicht gelesen werden",
+           report.failed.len()
+       ));
    }
-   if options.downloads {
-       total_files += count_dir_files(&user_base.join("Downloads"));
+   Ok(report.finish(message, &report::export_report_path(&output_path)))
+}
+
+/// Manifest eines neuen Exports. Vorab werden Dateien und Größe je Kategorie
+/// ermittelt – für ETA/Progress und das Manifest.
+pub(crate) fn new_manifest(
+   profile: &UserProfile,
+   options: &ExportOptions,
+) -> Result<PackageManifest, String> {
+   let mut categories = plan_categories(profile, options)?;
+   for category in &mut categories {
+       let stats = scan_category(profile, category)?;
+       category.files = stats.files;
+       category.bytes = stats.bytes;
    }
-   // Browser (groß) zählen
-   if options.chrome {
-       total_files += count_dir_files(&user_base.join("AppData/Local/Google/Chrome/User Data"));
+   Ok(PackageManifest {
+       niloshift_version: env!("CARGO_PKG_VERSION").to_string(),
+       source_user: profile.name.clone(),
+       machine_name: if profile.offline {
+           format!("Offline: {}", profile.base.display())
+       } else {
+           system_operations::machine_name()
+       },
+       exported_at: SystemTime::now()
+           .duration_since(UNIX_EPOCH)
+           .map(|d| d.as_secs())
+           .unwrap_or(0),
+       categories,
+   })
+}
+
+/// Legt ein neues Paket an und liefert das Archiv darin.
+fn create_archive(
+   output_path: &Path,
+   recipients: &[Recipient],
+   manifest: &PackageManifest,
+   compression: CompressionSettings,
+   volume_size: Option<u64>,
+) -> Result<PackageArchive, String> {
+   let output = VolumeWriter::create(output_path, volume_size)
+       .map_err(|e| format!("Fehler beim Erstellen der Paketdatei: {}", e))?;
+   let encryptor =
+       package_format::create_package_writer(output, recipients, manifest, volume_size)?;
+   ArchiveWriter::with_compression(encryptor, compression)
+       .map_err(|e| format!("Fehler beim Schreiben des Pakets: {}", e))
+}
+
+/// Liest Kopf und Manifest eines unterbrochenen Exports und prüft, ob der
+/// Zwischenstand zum Paket passt. Am Paket wird dabei noch nichts geändert.
+fn open_interrupted(
+   output_path: &Path,
+   state: &ExportState,
+   password: &str,
+   selected_user: &str,
+   source_root: &Option<String>,
+) -> Result<ResumablePackage, String> {
+   if state.selected_user != selected_user {
+       return Err(format!(
+           "Der unterbrochene Export gehört zum Benutzer {}",
+           state.selected_user
+       ));
    }
-   if options.edge {
-       total_files += count_dir_files(&user_base.join("AppData/Local/Microsoft/Edge/User Data"));
+   if &state.source_root != source_root {
+       return Err(format!(
+           "Der unterbrochene Export stammt aus dem Quellordner {}",
+           state.source_root.as_deref().unwrap_or("dieses Rechners")
+       ));
    }
-   if options.firefox {
-       total_files += count_dir_files(&user_base.join("AppData/Roaming/Mozilla/Firefox"));
+   if password.is_empty() {
+       return Err("Zum Fortsetzen wird das Passwort des Exports benötigt".to_string());
    }
-   if options.outlook_signatures {
-       total_files += count_dir_files(&user_base.join("AppData/Roaming/Microsoft/Signatures"));
+   let input = VolumeSet::discover(output_path)?.open()?;
+   let package = package_format::open_for_resume(input, &Credentials::from_password(password))?;
+   let chunk_len = package.chunk_size as u64 + TAG_LEN as u64;
+   if package.payload_offset + state.chunks as u64 * chunk_len != state.length {
+       return Err("Zwischenstand passt nicht zum Paket, bitte neu exportieren".to_string());
    }
+   Ok(package)
+}
 
-   // Wir zählen Dateien für Copy und Zip -> Faktor 2
-   let total_ops = total_files.saturating_mul(2).saturating_add(1); // +1 für Encrypt
-   let mut processed: u64 = 0;
-   let start = Instant::now();
+/// Kürzt das Paket auf den Zwischenstand und liefert das Archiv zum Weiterschreiben.
+fn resume_archive(
+   package: ResumablePackage,
+   mut state: ExportState,
+   output_path: &Path,
+   state_path: PathBuf,
+   compression: CompressionSettings,
+) -> Result<(PackageArchive, Checkpoints), String> {
+   // Neuer Abschnitt der Nonce für alles ab hier; festhalten, bevor der erste Block entsteht
+   state.segment = state.segment.saturating_add(1);
+   resume::save(&state_path, &state)?;
+   let chunk_size = package.chunk_size as u64;
+   let output = VolumeWriter::resume(output_path, state.volume_size, state.length)
+       .map_err(|e| format!("Fehler beim Öffnen der Paketdatei: {}", e))?;
+   let encryptor = package.writer(output, state.chunks, state.segment)?;
+   let archive = ArchiveWriter::resume(
+       encryptor,
+       compression,
+       state.completed.clone(),
+       state.chunks as u64 * chunk_size,
+   );
+   Ok((archive, Checkpoints::new(state_path, state, chunk_size)))
+}
 
-   // 1) Daten sammeln (Copy)
-   emit_progress(&app, start, processed, total_ops, "Sammeln");
-   if options.desktop {
-       copy_directory_with_progress(
-           &user_base.join("Desktop"),
-           &temp_dir.join("Desktop"),
-           &app,
-           start,
-           &mut processed,
-           total_ops,
-       )?;
-   }
-   if options.documents {
-       copy_directory_with_progress(
-           &user_base.join("Documents"),
-           &temp_dir.join("Documents"),
-           &app,
-           start,
-           &mut processed,
-           total_ops,
-       )?;
-   }
-   if options.pictures {
-       copy_directory_with_progress(
-           &user_base.join("Pictures"),
-           &temp_dir.join("Pictures"),
-           &app,
-           start,
-           &mut processed,
-           total_ops,
-       )?;
-   }
-   if options.downloads {
-       copy_directory_with_progress(
-           &user_base.join("Downloads"),
-           &temp_dir.join("Downloads"),
-           &app,
-           start,
-           &mut processed,
-           total_ops,
-       )?;
+/// Entfernt ein unvollständiges Paket samt Volumes.
+fn remove_package(output_path: &Path, volume_size: Option<u64>) {
+   if volume_size.is_some() {
+       volumes::remove_volumes(output_path);
+   } else {
+       let _ = fs::remove_file(output_path);
    }
+}
 
-   let appdata = user_base.join("AppData");
-   if options.chrome {
-       let p = appdata.join("Local/Google/Chrome/User Data");
-       if p.exists() {
-           // Bewahre die relative Struktur bei
-           copy_directory_with_progress(
-               &p,
-               &temp_dir.join("AppData/Local/Google/Chrome/User Data"),
-               &app,
-               start,
-               &mut processed,
-               total_ops,
-           )?;
+/// Zwischenstände eines Exports, damit er nach einer Unterbrechung fortgesetzt
+/// werden kann (siehe `resume`).
+struct Checkpoints {
+   path: PathBuf,
+   state: ExportState,
+   chunk_size: u64,
+   last: Instant,
+   /// Dateien, die ein fortgesetzter Export schon im Paket hat.
+   done: HashSet<String>,
+}
+
+impl Checkpoints {
+   fn new(path: PathBuf, state: ExportState, chunk_size: u64) -> Self {
+       let done = state.completed.iter().map(|e| e.path.clone()).collect();
+       Checkpoints {
+           path,
+           state,
+           chunk_size,
+           last: Instant::now(),
+           done,
        }
    }
-   if options.edge {
-       let p = appdata.join("Local/Microsoft/Edge/User Data");
-       if p.exists() {
-           copy_directory_with_progress(
-               &p,
-               &temp_dir.join("AppData/Local/Microsoft/Edge/User Data"),
-               &app,
-               start,
-               &mut processed,
-               total_ops,
-           )?;
+
+   /// Nach jeder fertigen Datei aufrufen: Liegt der letzte Zwischenstand lange genug
+   /// zurück, wird das Archiv auf eine Blockgrenze aufgefüllt, alles Geschriebene auf
+   /// den Datenträger gebracht und erst dann der neue Zwischenstand gespeichert.
+   fn save_due(&mut self, archive: &mut PackageArchive) -> Result<(), String> {
+       if self.last.elapsed() < resume::CHECKPOINT_INTERVAL {
+           return Ok(());
        }
-   }
-   if options.firefox {
-       let p = appdata.join("Roaming/Mozilla/Firefox");
-       if p.exists() {
-           copy_directory_with_progress(
-               &p,
-               &temp_dir.join("AppData/Roaming/Mozilla/Firefox"),
-               &app,
-               start,
-               &mut processed,
-               total_ops,
-           )?;
+       self.last = Instant::now();
+       let write_error = |e: io::Error| format!("Fehler beim Schreiben des Pakets: {}", e);
+       archive.align(self.chunk_size).map_err(write_error)?;
+       let encryptor = archive.get_mut();
+       encryptor.seal_full().map_err(write_error)?;
+       let chunks = encryptor.chunks();
+       let output = encryptor.get_mut();
+       output.sync().map_err(write_error)?;
+       self.state.length = output.position();
+       self.state.chunks = chunks;
+       self.state.completed = archive.index().to_vec();
+       self.state.saved_at = report::now_secs();
+       // Ohne Zwischenstand lässt sich nur nicht fortsetzen, das Paket selbst bleibt gültig
+       if let Err(e) = resume::save(&self.path, &self.state) {
+           eprintln!("⚠️ {}", e);
        }
+       Ok(())
    }
-   if options.outlook_signatures {
-       let p = appdata.join("Roaming/Microsoft/Signatures");
-       if p.exists() {
-           copy_directory_with_progress(
-               &p,
-               &temp_dir.join("AppData/Roaming/Microsoft/Signatures"),
-               &app,
-               start,
-               &mut processed,
-               total_ops,
-           )?;
+}
+
+/// Zielpfad des Pakets; ohne Angabe auf dem Desktop des Benutzers. Bei einer
+/// Offline-Quelle wird nicht auf den alten Datenträger geschrieben, sondern auf
+/// den Desktop des angemeldeten Benutzers.
+pub(crate) fn output_path_for(export_path: Option<String>, profile: &UserProfile) -> PathBuf {
+   match export_path.filter(|p| !p.is_empty()) {
+       Some(path) => PathBuf::from(path),
+       None if profile.offline => dirs::desktop_dir()
+           .or_else(dirs::home_dir)
+           .unwrap_or_else(|| PathBuf::from("."))
+           .join("NiloShift_Export.nilo"),
+       None => {
+           let desktop = match known_folders::by_id("desktop") {
+               Some(folder) => profile.folder(folder),
+               None => profile.base.join("Desktop"),
+           };
+           desktop.join("NiloShift_Export.nilo")
        }
    }
+}
 
-   // 2) ZIPen
-   // ZIP-Datei außerhalb des Quellordners erstellen, damit sie nicht ins Archiv gerät
-   let zip_path = std::env::temp_dir().join("niloshift_data.zip");
-   emit_progress(&app, start, processed, total_ops, "Packen");
-   create_zip_archive_with_progress(&temp_dir, &zip_path, &app, start, &mut processed, total_ops)
-       .await?;
+/// Ausgewählte Kategorien mit Pfad im Archiv und wirksamen Regeln, noch ohne
+/// Dateianzahl und Größe. Export und Vorschau verwenden dieselbe Auswahl.
+pub(crate) fn plan_categories(
+   profile: &UserProfile,
+   options: &ExportOptions,
+) -> Result<Vec<CategorySummary>, String> {
+   let mut categories = Vec::new();
+   // Bekannte Ordner; der Pfad im Archiv ist relativ zum Benutzerprofil
+   for folder in options.selected_folders() {
+       categories.push(CategorySummary {
+           id: folder.id.to_string(),
+           path: folder.source.to_string(),
+           files: 0,
+           bytes: 0,
+           custom: None,
+           rules: Some(options.rules_for(folder.id, folder.excludes)),
+       });
+   }
+   for (i, custom) in options.custom_paths.iter().enumerate() {
+       let id = format!("custom-{}", i + 1);
+       let rules = options.rules_for(&id, &[]);
+       categories.push(CategorySummary {
+           id,
+           path: format!("{}/{}", CUSTOM_ARCHIVE_PREFIX, i + 1),
+           files: 0,
+           bytes: 0,
+           custom: Some(resolve_custom_path(&profile.base, custom)?),
+           rules: Some(rules),
+       });
+   }
+   Ok(categories)
+}
 
-   // 3) Verschlüsseln (kleiner Schritt)
-   emit_progress(&app, start, processed, total_ops, "Verschlüsseln");
-   encrypt_file(&zip_path, &output_path, &password).await?;
-   processed = total_ops; // Fertig
-   emit_progress(&app, start, processed, total_ops, "Fertig");
+/// Quellordner einer Kategorie; bekannte Ordner auch dort, wohin sie umgeleitet sind.
+pub(crate) fn category_source(profile: &UserProfile, category: &CategorySummary) -> PathBuf {
+   match (&category.custom, known_folders::by_id(&category.id)) {
+       (Some(custom), _) => PathBuf::from(&custom.source),
+       (None, Some(folder)) => profile.folder(folder),
+       (None, None) => profile.base.join(&category.path),
+   }
+}
 
-   let _ = fs::remove_dir_all(&temp_dir);
+pub(crate) fn category_rules(category: &CategorySummary) -> Result<RuleSet, String> {
+   RuleSet::new(category.rules.as_ref().unwrap_or(&FilterRules::default()))
+}
 
-   Ok(format!(
-       "Export erfolgreich nach {} erstellt",
-       output_path.display()
-   ))
+/// Durchläuft den Quellordner einer Kategorie mit ihren Regeln, ohne etwas zu schreiben.
+pub(crate) fn scan_category(
+   profile: &UserProfile,
+   category: &CategorySummary,
+) -> Result<ScanStats, String> {
+   let mut stats = ScanStats::default();
+   scan_dir(
+       &category_source(profile, category),
+       "",
+       &category_rules(category)?,
+       &mut stats,
+   );
+   Ok(stats)
 }
 
-fn is_symlink(path: &Path) -> bool {
-   fs::symlink_metadata(path)
-       .map(|m| m.file_type().is_symlink())
-       .unwrap_or(false)
+impl ExportOptions {
+   /// Ausgewählte bekannte Ordner in fester Reihenfolge; unbekannte Kennungen werden ignoriert.
+   fn selected_folders(&self) -> Vec<&'static KnownFolder> {
+       KNOWN_FOLDERS
+           .iter()
+           .filter(|f| self.folders.get(f.id).copied().unwrap_or(false))
+           .collect()
+   }
+
+   /// Wirksame Filterregeln einer Kategorie samt ihrer Standardmuster.
+   fn rules_for(&self, id: &str, defaults: &[&str]) -> FilterRules {
+       FilterRules::effective(defaults, &self.rules, self.category_rules.get(id))
+   }
 }
 
-fn count_dir_files(dir: &Path) -> u64 {
-   if !dir.exists() || is_symlink(dir) {
-       return 0;
+/// Prüft einen benutzerdefinierten Pfad und legt fest, wohin er beim Import gehört:
+/// Ordner im Benutzerprofil werden relativ zum Profil wiederhergestellt, damit sie
+/// beim Import im Zielprofil landen, alle anderen an ihrem ursprünglichen Ort.
+fn resolve_custom_path(user_base: &Path, custom: &CustomPath) -> Result<CustomPath, String> {
+   let source = custom.source.trim();
+   if source.is_empty() {
+       return Err("Benutzerdefinierter Pfad ist leer".to_string());
    }
-   let mut cnt = 0u64;
-   let it = match fs::read_dir(dir) {
-       Ok(i) => i,
-       Err(_) => return 0,
-   };
-   for e in it {
-       if let Ok(e) = e {
-           let p = e.path();
-           if is_symlink(&p) {
-               continue;
-           }
-           if p.is_dir() {
-               cnt += count_dir_files(&p);
-           } else {
-               cnt += 1;
-           }
-       }
+   // Relative Pfade beziehen sich auf das Benutzerprofil
+   let src = user_base.join(source);
+   if !src.is_dir() || is_symlink(&src) {
+       return Err(format!(
+           "Benutzerdefinierter Pfad ist kein Ordner: {}",
+           src.display()
+       ));
    }
-   cnt
+   let restore = custom
+       .restore
+       .as_deref()
+       .map(str::trim)
+       .filter(|r| !r.is_empty())
+       .map(str::to_string)
+       .or_else(|| {
+           src.strip_prefix(user_base)
+               .ok()
+               .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+       });
+   let label = custom
+       .label
+       .as_deref()
+       .map(str::trim)
+       .filter(|l| !l.is_empty())
+       .map(str::to_string)
+       .or_else(|| src.file_name().map(|n| n.to_string_lossy().to_string()));
+   Ok(CustomPath {
+       source: src.to_string_lossy().to_string(),
+       label,
+       restore,
+   })
 }
 
-fn copy_directory_with_progress(
-   src: &Path,
-   dst: &Path,
-   app: &tauri::AppHandle,
+/// Liest die Quellordner an Ort und Stelle und schreibt sie über das Archiv direkt
+/// in den Verschlüsselungs-Stream – ohne Kopie oder unverschlüsselte ZIP im Temp-Ordner.
+#[allow(clippy::too_many_arguments)]
+fn write_package(
+   sink: &ProgressSink,
+   mut archive: PackageArchive,
+   profile: &UserProfile,
+   manifest: &PackageManifest,
+   snapshot: Option<&Snapshot>,
+   checkpoints: &mut Option<Checkpoints>,
+   job: &JobHandle,
    start: Instant,
    processed: &mut u64,
    total: u64,
-) -> Result<(), String> {
-   if !src.exists() || is_symlink(src) {
-       return Ok(());
+   report: &mut TransferReport,
+) -> Result<Vec<PathBuf>, String> {
+   emit_progress(sink, PROGRESS_EVENT, start, *processed, total, "Packen");
+   for category in &manifest.categories {
+       job.checkpoint()?;
+       let rel = category.path.as_str();
+       let src = category_source(profile, category);
+       let src = match snapshot {
+           Some(snapshot) => snapshot.map(&src),
+           None => src,
+       };
+       if !src.exists() || is_symlink(&src) {
+           report.skipped(rel, format!("Ordner {} nicht vorhanden", src.display()));
+           continue;
+       }
+       let rules = category_rules(category)?;
+       // Übergeordnete Ordner anlegen, damit die relative Struktur erhalten bleibt
+       let mut prefix = String::new();
+       for part in rel.split('/') {
+           if !prefix.is_empty() {
+               prefix.push('/');
+           }
+           prefix.push_str(part);
+           archive
+               .add_directory(&prefix)
+               .map_err(|e| format!("Fehler Ordner {}: {}", prefix, e))?;
+       }
+       add_dir_to_archive_progress(
+           &mut archive,
+           &src,
+           rel,
+           "",
+           &rules,
+           checkpoints,
+           job,
+           sink,
+           start,
+           processed,
+           total,
+           report,
+       )?;
+   }
+
+   emit_progress(sink, PROGRESS_EVENT, start, *processed, total, "Verschlüsseln");
+   let encryptor = archive
+       .finish()
+       .map_err(|e| format!("Fehler beim Abschließen des Archivs: {}", e))?;
+   let output = encryptor
+       .finish()
+       .map_err(|e| format!("Fehler beim Schreiben der verschlüsselten Datei: {}", e))?;
+   output
+       .finish()
+       .map_err(|e| format!("Fehler beim Schreiben der verschlüsselten Datei: {}", e))
+}
+
+pub(crate) fn is_symlink(path: &Path) -> bool {
+   fs::symlink_metadata(path)
+       .map(|m| m.file_type().is_symlink())
+       .unwrap_or(false)
+}
+
+/// Pfad relativ zum Kategorie-Ordner, wie ihn die Filterregeln erwarten.
+pub(crate) fn join_rel(rel: &str, name: &std::ffi::OsStr) -> String {
+   if rel.is_empty() {
+       name.to_string_lossy().to_string()
+   } else {
+       format!("{}/{}", rel, name.to_string_lossy())
+   }
+}
+
+/// Anzahl der größten Dateien, die ein Durchlauf festhält.
+const LARGEST_FILES: usize = 10;
+/// Höchstens so viele ausgelassene Pfade werden einzeln festgehalten.
+const MAX_SKIPPED: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileSize {
+   pub path: String,
+   pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedPath {
+   pub path: String,
+   pub reason: String,
+   pub is_dir: bool,
+}
+
+/// Ergebnis eines Durchlaufs über einen Quellordner.
+#[derive(Debug, Default)]
+pub(crate) struct ScanStats {
+   pub files: u64,
+   pub bytes: u64,
+   /// Bytes in bereits komprimierten Formaten (für die Größenschätzung).
+   pub compressed_bytes: u64,
+   /// Größte Dateien, absteigend sortiert.
+   pub largest: Vec<FileSize>,
+   /// Ausgelassene Pfade (die ersten `MAX_SKIPPED`) und ihre Gesamtzahl.
+   pub skipped: Vec<SkippedPath>,
+   pub skipped_count: u64,
+}
+
+impl ScanStats {
+   fn add_file(&mut self, rel: &str, path: &Path, size: u64) {
+       self.files += 1;
+       self.bytes += size;
+       if compression::is_compressed_type(path) {
+           self.compressed_bytes += size;
+       }
+       let full = self.largest.len() >= LARGEST_FILES;
+       if !full || self.largest.last().is_some_and(|f| f.bytes < size) {
+           let pos = self.largest.partition_point(|f| f.bytes >= size);
+           self.largest.insert(
+               pos,
+               FileSize {
+                   path: rel.to_string(),
+                   bytes: size,
+               },
+           );
+           self.largest.truncate(LARGEST_FILES);
+       }
+   }
+
+   fn add_skipped(&mut self, rel: &str, reason: impl ToString, is_dir: bool) {
+       self.skipped_count += 1;
+       if self.skipped.len() < MAX_SKIPPED {
+           self.skipped.push(SkippedPath {
+               path: rel.to_string(),
+               reason: reason.to_string(),
+               is_dir,
+           });
+       }
    }
-   if let Err(e) = fs::create_dir_all(dst) {
-       eprintln!("Warnung: {}", e);
+}
+
+/// Zählt Dateien und Bytes so, wie sie später ins Archiv geschrieben werden.
+fn scan_dir(dir: &Path, rel: &str, rules: &RuleSet, stats: &mut ScanStats) {
+   if !dir.exists() || is_symlink(dir) {
+       return;
    }
-   let it = match fs::read_dir(src) {
+   let it = match fs::read_dir(dir) {
        Ok(i) => i,
-       Err(_) => return Ok(()),
+       Err(_) => return,
    };
-   for e in it {
-       let e = match e {
-           Ok(x) => x,
-           Err(_) => continue,
-       };
-       let sp = e.path();
-       let dp = dst.join(e.file_name());
-       if is_symlink(&sp) {
-           continue;
-       }
-       let name_lower = e.file_name().to_string_lossy().to_ascii_lowercase();
-       if name_lower == "node_modules" || name_lower.starts_with("cache") || name_lower == "temp" || name_lower == "tmp" {
+   for e in it.flatten() {
+       let p = e.path();
+       if is_symlink(&p) {
            continue;
        }
-       if sp.is_dir() {
-           let _ = copy_directory_with_progress(&sp, &dp, app, start, processed, total);
+       let child_rel = join_rel(rel, &e.file_name());
+       if p.is_dir() {
+           if let Some(reason) = rules.check_dir(&child_rel) {
+               stats.add_skipped(&child_rel, reason, true);
+               continue;
+           }
+           scan_dir(&p, &child_rel, rules, stats);
        } else {
-           let _ = fs::copy(&sp, &dp);
-           *processed = processed.saturating_add(1);
-           emit_progress(app, start, *processed, total, "Sammeln");
+           let meta = e.metadata().ok();
+           let size = meta.as_ref().map(|m| m.len()).unwrap_or(0);
+           let modified = meta.as_ref().and_then(modified_secs);
+           if let Some(reason) = rules.check_file(&child_rel, size, modified) {
+               stats.add_skipped(&child_rel, reason, false);
+               continue;
+           }
+           stats.add_file(&child_rel, &p, size);
        }
    }
-   Ok(())
-}
-
-async fn create_zip_archive_with_progress(
-   source_dir: &Path,
-   zip_path: &Path,
-   app: &tauri::AppHandle,
-   start: Instant,
-   processed: &mut u64,
-   total: u64,
-) -> Result<(), String> {
-   let file = fs::File::create(zip_path)
-       .map_err(|e| format!("Fehler beim Erstellen der ZIP-Datei: {}", e))?;
-   let mut zip = ZipWriter::new(file);
-   // Performance: keine Kompression, nur Archivierung (schneller, kleinere CPU-Last)
-   let options = FileOptions::default()
-       .compression_method(CompressionMethod::Stored)
-       .unix_permissions(0o755);
-   add_dir_to_zip_progress(
-       &mut zip, source_dir, "", &options, app, start, processed, total,
-   )?;
-   zip.finish()
-       .map_err(|e| format!("Fehler beim Abschließen der ZIP-Datei: {}", e))?;
-   Ok(())
 }
 
-fn add_dir_to_zip_progress<W: Write + io::Seek>(
-   zip: &mut ZipWriter<W>,
+#[allow(clippy::too_many_arguments)]
+fn add_dir_to_archive_progress(
+   archive: &mut PackageArchive,
    src_dir: &Path,
    prefix: &str,
-   options: &FileOptions,
-   app: &tauri::AppHandle,
+   rel: &str,
+   rules: &RuleSet,
+   checkpoints: &mut Option<Checkpoints>,
+   job: &JobHandle,
+   sink: &ProgressSink,
    start: Instant,
    processed: &mut u64,
    total: u64,
+   report: &mut TransferReport,
 ) -> Result<(), String> {
    let entries = match fs::read_dir(src_dir) {
        Ok(i) => i,
-       Err(_) => return Ok(()),
+       Err(e) => {
+           report.failed(prefix, e);
+           return Ok(());
+       }
    };
    for entry in entries {
+       job.checkpoint()?;
        let entry = match entry {
            Ok(e) => e,
            Err(_) => continue,
@@ -368,63 +781,395 @@ fn add_dir_to_zip_progress<W: Write + io::Seek>(
        if is_symlink(&path) {
            continue;
        }
-       // Große oder problematische Ordner überspringen, um Hänger zu vermeiden
-       let fname = entry.file_name().to_string_lossy().to_ascii_lowercase();
-       if fname == "node_modules" || fname.starts_with("cache") || fname == "temp" || fname == "tmp" {
-           continue;
-       }
        let name = entry.file_name();
-       let zip_path = if prefix.is_empty() {
+       let child_rel = join_rel(rel, &name);
+       let archive_path = if prefix.is_empty() {
            name.to_string_lossy().to_string()
        } else {
            format!("{}/{}", prefix, name.to_string_lossy())
        };
        if path.is_dir() {
-           zip.add_directory(&zip_path, *options)
-               .map_err(|e| format!("Fehler Ordner {}: {}", zip_path, e))?;
-           add_dir_to_zip_progress(zip, &path, &zip_path, options, app, start, processed, total)?;
+           if let Some(reason) = rules.check_dir(&child_rel) {
+               report.skipped(&archive_path, reason);
+               continue;
+           }
+           archive
+               .add_directory(&archive_path)
+               .map_err(|e| format!("Fehler Ordner {}: {}", archive_path, e))?;
+           add_dir_to_archive_progress(
+               archive,
+               &path,
+               &archive_path,
+               &child_rel,
+               rules,
+               checkpoints,
+               job,
+               sink,
+               start,
+               processed,
+               total,
+               report,
+           )?;
        } else {
-           zip.start_file(&zip_path, *options)
-               .map_err(|e| format!("Fehler Datei {}: {}", zip_path, e))?;
-           let content = match fs::read(&path) {
-               Ok(c) => c,
-               Err(_) => {
+           let meta = entry.metadata().ok();
+           let header = EntryHeader {
+               path: archive_path.clone(),
+               size: meta.as_ref().map(|m| m.len()).unwrap_or(0),
+               modified: meta.as_ref().and_then(modified_secs),
+               compression: None,
+           };
+           if let Some(reason) = rules.check_file(&child_rel, header.size, header.modified) {
+               report.skipped(&archive_path, reason);
+               continue;
+           }
+           *processed = processed.saturating_add(1);
+           // Schon vor der Unterbrechung vollständig ins Paket geschrieben
+           if checkpoints
+               .as_ref()
+               .is_some_and(|c| c.done.contains(&archive_path))
+           {
+               continue;
+           }
+           // Datei direkt aus der Quelle lesen; nicht lesbare Dateien überspringen
+           let mut file = match open_with_retry(&path) {
+               Ok(f) => f,
+               Err(e) if is_locked(&e) => {
+                   report.failed(
+                       &archive_path,
+                       format!("{} (von einem laufenden Programm gesperrt)", e),
+                   );
+                   emit_progress(sink, PROGRESS_EVENT, start, *processed, total, "Packen");
+                   continue;
+               }
+               Err(e) => {
+                   report.failed(&archive_path, e);
+                   emit_progress(sink, PROGRESS_EVENT, start, *processed, total, "Packen");
                    continue;
                }
            };
-           zip.write_all(&content)
-               .map_err(|e| format!("Fehler Schreiben {}: {}", zip_path, e))?;
-           *processed = processed.saturating_add(1);
-           emit_progress(app, start, *processed, total, "Packen");
+           // Lesefehler der Quelle überspringen die Datei, Schreibfehler brechen ab
+           let written = archive
+               .add_file(&header, &mut file)
+               .map_err(|e| format!("Fehler Schreiben {}: {}", archive_path, e))?;
+           match written.read_error {
+               Some(e) => report.failed(&archive_path, e),
+               None => report.copied(&archive_path, written.bytes),
+           }
+           if let Some(checkpoints) = checkpoints.as_mut() {
+               checkpoints.save_due(archive)?;
+           }
+           emit_progress(sink, PROGRESS_EVENT, start, *processed, total, "Packen");
        }
    }
    Ok(())
 }
 
-async fn encrypt_file(zip_path: &Path, output_path: &Path, password: &str) -> Result<(), String> {
-   let zip_data =
-       fs::read(zip_path).map_err(|e| format!("Fehler beim Lesen der ZIP-Datei: {}", e))?;
-   let mut key = [0u8; 32];
-   let password_bytes = password.as_bytes();
-   for (i, &byte) in password_bytes.iter().enumerate() {
-       if i >= 32 {
-           break;
+/// Pausen zwischen den Versuchen, eine gesperrte Datei zu öffnen.
+const LOCK_RETRY_DELAYS_MS: &[u64] = &[50, 100, 200, 400, 800];
+
+/// Öffnet eine Quelldatei. Hält ein anderes Programm sie gerade exklusiv offen,
+/// wird es mit wachsenden Pausen erneut versucht.
+pub(crate) fn open_with_retry(path: &Path) -> io::Result<fs::File> {
+   let mut delays = LOCK_RETRY_DELAYS_MS.iter();
+   loop {
+       match fs::File::open(path) {
+           Err(e) if is_locked(&e) => match delays.next() {
+               Some(ms) => thread::sleep(Duration::from_millis(*ms)),
+               None => return Err(e),
+           },
+           result => return result,
        }
-       key[i] = byte;
-   }
-   let mut nonce_bytes = [0u8; 12];
-   OsRng.fill_bytes(&mut nonce_bytes);
-   let nonce = Nonce::from_slice(&nonce_bytes);
-   let cipher = Aes256Gcm::new_from_slice(&key)
-       .map_err(|e| format!("Fehler beim Erstellen des Ciphers: {}", e))?;
-   let encrypted_data = cipher
-       .encrypt(nonce, zip_data.as_ref())
-       .map_err(|e| format!("Fehler beim Verschlüsseln: {}", e))?;
-   let mut output_data = Vec::new();
-   output_data.extend_from_slice(&nonce_bytes);
-   output_data.extend_from_slice(&encrypted_data);
-   fs::write(output_path, output_data)
-       .map_err(|e| format!("Fehler beim Schreiben der verschlüsselten Datei: {}", e))?;
-   let _ = fs::remove_file(zip_path);
-   Ok(())
-}
\ No newline at end of file
+   }
+}
+
+/// Datei von einem anderen Programm gesperrt. Nur Windows sperrt Dateien beim Öffnen,
+/// anderswo ist kein Fehler eine Sperre.
+pub(crate) fn is_locked(e: &io::Error) -> bool {
+   cfg!(target_os = "windows") && e.raw_os_error().is_some_and(is_lock_error)
+}
+
+/// ERROR_SHARING_VIOLATION bzw. ERROR_LOCK_VIOLATION unter Windows.
+fn is_lock_error(code: i32) -> bool {
+   matches!(code, 32 | 33)
+}
+
+pub(crate) fn modified_secs(meta: &fs::Metadata) -> Option<u64> {
+   meta.modified()
+       .ok()
+       .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+       .map(|d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::archive::{ArchiveEntry, ArchiveReader};
+
+   const PASSWORD: &str = "pw";
+   const FILE_LEN: usize = 700_000;
+
+   #[test]
+   fn lock_errors() {
+       assert!(is_lock_error(32));
+       assert!(is_lock_error(33));
+       // ERROR_FILE_NOT_FOUND, ERROR_ACCESS_DENIED
+       assert!(!is_lock_error(2));
+       assert!(!is_lock_error(5));
+       let sharing = io::Error::from_raw_os_error(32);
+       assert_eq!(is_locked(&sharing), cfg!(target_os = "windows"));
+       assert!(!is_locked(&io::Error::from(io::ErrorKind::WouldBlock)));
+   }
+
+   /// Nicht komprimierbare Testdaten.
+   fn data(seed: u32) -> Vec<u8> {
+       let mut x = seed.wrapping_mul(2_654_435_761) | 1;
+       (0..FILE_LEN)
+           .map(|_| {
+               x ^= x << 13;
+               x ^= x >> 17;
+               x ^= x << 5;
+               x as u8
+           })
+           .collect()
+   }
+
+   fn files() -> Vec<(String, Vec<u8>)> {
+       (0..6u32)
+           .map(|i| (format!("Documents/datei-{}.bin", i), data(i + 1)))
+           .collect()
+   }
+
+   fn manifest() -> PackageManifest {
+       PackageManifest {
+           niloshift_version: "test".to_string(),
+           source_user: "alice".to_string(),
+           machine_name: "PC".to_string(),
+           exported_at: 0,
+           categories: Vec::new(),
+       }
+   }
+
+   fn initial_state(package: &Path, volume_size: Option<u64>) -> ExportState {
+       ExportState {
+           package: package.display().to_string(),
+           selected_user: "alice".to_string(),
+           source_root: None,
+           volume_size,
+           length: 0,
+           chunks: 0,
+           segment: 0,
+           completed: Vec::new(),
+           saved_at: 0,
+       }
+   }
+
+   /// Schreibt Dateien bis zur nächsten Unterbrechung. Nach Datei `stop` wird ein
+   /// Zwischenstand gespeichert und noch eine Datei angefangen, dann "stürzt" der
+   /// Export ab. Ohne `stop` wird das Paket abgeschlossen.
+   fn write_files(
+       mut archive: PackageArchive,
+       checkpoints: &mut Checkpoints,
+       files: &[(String, Vec<u8>)],
+       stop: Option<usize>,
+   ) {
+       for (i, (path, data)) in files.iter().enumerate() {
+           if checkpoints.done.contains(path) {
+               continue;
+           }
+           let header = EntryHeader {
+               path: path.clone(),
+               size: data.len() as u64,
+               modified: None,
+               compression: None,
+           };
+           archive.add_file(&header, &mut &data[..]).unwrap();
+           if stop == Some(i) {
+               checkpoints.last = Instant::now()
+                   .checked_sub(resume::CHECKPOINT_INTERVAL)
+                   .unwrap();
+               checkpoints.save_due(&mut archive).unwrap();
+               let (path, data) = &files[i + 1];
+               let header = EntryHeader {
+                   path: path.clone(),
+                   size: data.len() as u64,
+                   modified: None,
+                   compression: None,
+               };
+               archive.add_file(&header, &mut &data[..FILE_LEN / 2]).unwrap();
+               return;
+           }
+       }
+       archive.finish().unwrap().finish().unwrap().finish().unwrap();
+   }
+
+   /// Hängt einen halb geschriebenen Block an das letzte Volume an.
+   fn append_torn_block(package: &Path, volume_size: Option<u64>) {
+       let last = match volume_size {
+           Some(_) => (1..)
+               .map(|n| volumes::volume_path(package, n))
+               .take_while(|p| p.exists())
+               .last()
+               .unwrap(),
+           None => package.to_path_buf(),
+       };
+       let mut file = fs::OpenOptions::new().append(true).open(last).unwrap();
+       io::Write::write_all(&mut file, &[0xAB; 1000]).unwrap();
+   }
+
+   /// Setzt den Export am gespeicherten Zwischenstand fort.
+   fn resume_export(package: &Path, state_path: &Path) -> (PackageArchive, Checkpoints) {
+       let state: ExportState = resume::load(state_path).unwrap();
+       let resumable = open_interrupted(package, &state, PASSWORD, "alice", &None).unwrap();
+       let segment = state.segment;
+       let (archive, checkpoints) = resume_archive(
+           resumable,
+           state,
+           package,
+           state_path.to_path_buf(),
+           CompressionSettings::default(),
+       )
+       .unwrap();
+       assert_eq!(checkpoints.state.segment, segment + 1);
+       (archive, checkpoints)
+   }
+
+   /// Entschlüsselt das fertige Paket und vergleicht jede Datei und den Index.
+   fn assert_package(package: &Path, files: &[(String, Vec<u8>)], volume_size: Option<u64>) {
+       let volumes = VolumeSet::discover(package).unwrap();
+       let opened = package_format::open_package_reader(
+           volumes.open().unwrap(),
+           &Credentials::from_password(PASSWORD),
+       )
+       .unwrap();
+       volumes.check_complete(opened.volume_size).unwrap();
+       assert_eq!(opened.volume_size, volume_size);
+       let mut archive = ArchiveReader::new(opened.content).unwrap();
+       let mut read = Vec::new();
+       while let Some(entry) = archive.next_entry().unwrap() {
+           let ArchiveEntry::File(header) = entry else {
+               panic!("unerwarteter Ordner");
+           };
+           let mut content = Vec::new();
+           let data = archive.read_data(&mut content).unwrap();
+           // Die beim Absturz angefangene Datei wurde beim Fortsetzen abgeschnitten
+           assert!(data.complete && data.hash_ok(), "{}", header.path);
+           read.push((header.path, content));
+       }
+       assert_eq!(read.len(), files.len());
+       for ((path, content), (expected_path, expected)) in read.iter().zip(files) {
+           assert_eq!(path, expected_path);
+           assert!(content == expected, "{}", path);
+       }
+       let index: Vec<&str> = archive
+           .index()
+           .unwrap()
+           .iter()
+           .map(|e| e.path.as_str())
+           .collect();
+       let expected: Vec<&str> = files.iter().map(|(p, _)| p.as_str()).collect();
+       assert_eq!(index, expected);
+   }
+
+   /// Export mit Unterbrechungen nach den Dateien in `stops`, jeweils fortgesetzt.
+   fn export_with_interruptions(name: &str, volume_size: Option<u64>, stops: &[usize]) {
+       let root = std::env::temp_dir()
+           .join(format!("nilo-export-resume-{}-{}", name, std::process::id()));
+       let _ = fs::remove_dir_all(&root);
+       fs::create_dir_all(&root).unwrap();
+       let package = root.join("alice.nilo");
+       let state_path = resume::export_state_path(&package);
+       let files = files();
+
+       let archive = create_archive(
+           &package,
+           &[Recipient::Password(PASSWORD.to_string())],
+           &manifest(),
+           CompressionSettings::default(),
+           volume_size,
+       )
+       .unwrap();
+       let mut checkpoints = Checkpoints::new(
+           state_path.clone(),
+           initial_state(&package, volume_size),
+           DEFAULT_CHUNK_SIZE as u64,
+       );
+       write_files(archive, &mut checkpoints, &files, stops.first().copied());
+       for (n, _) in stops.iter().enumerate() {
+           // Abgebrochener Lauf hat hinter dem Zwischenstand noch angefangen zu schreiben
+           let state: ExportState = resume::load(&state_path).unwrap();
+           append_torn_block(&package, volume_size);
+           assert!(VolumeSet::discover(&package).unwrap().total_len() > state.length);
+           let (archive, mut checkpoints) = resume_export(&package, &state_path);
+           assert_eq!(checkpoints.state.segment as usize, n + 1);
+           write_files(archive, &mut checkpoints, &files, stops.get(n + 1).copied());
+       }
+       assert_package(&package, &files, volume_size);
+       if volume_size.is_some() {
+           let len = VolumeSet::discover(&package).unwrap().total_len();
+           assert!(len > 4 * volumes::MIN_VOLUME_SIZE);
+           assert!(volumes::volume_path(&package, 4).exists());
+       }
+       let _ = fs::remove_dir_all(&root);
+   }
+
+   #[test]
+   fn export_resumes_after_checkpoint() {
+       export_with_interruptions("single", None, &[1]);
+   }
+
+   #[test]
+   fn export_resumes_twice_across_volumes() {
+       export_with_interruptions("volumes", Some(volumes::MIN_VOLUME_SIZE), &[0, 3]);
+   }
+
+   #[test]
+   fn resume_rejects_truncated_package() {
+       let root =
+           std::env::temp_dir().join(format!("nilo-export-truncated-{}", std::process::id()));
+       let _ = fs::remove_dir_all(&root);
+       fs::create_dir_all(&root).unwrap();
+       let package = root.join("alice.nilo");
+       let state_path = resume::export_state_path(&package);
+       let archive = create_archive(
+           &package,
+           &[Recipient::Password(PASSWORD.to_string())],
+           &manifest(),
+           CompressionSettings::default(),
+           None,
+       )
+       .unwrap();
+       let mut checkpoints = Checkpoints::new(
+           state_path.clone(),
+           initial_state(&package, None),
+           DEFAULT_CHUNK_SIZE as u64,
+       );
+       write_files(archive, &mut checkpoints, &files(), Some(2));
+       let state: ExportState = resume::load(&state_path).unwrap();
+
+       // Anderer Benutzer, falsches Passwort: am Paket wird nichts geändert
+       let len = fs::metadata(&package).unwrap().len();
+       assert!(open_interrupted(&package, &state, PASSWORD, "bob", &None).is_err());
+       assert!(open_interrupted(&package, &state, "falsch", "alice", &None).is_err());
+       assert_eq!(fs::metadata(&package).unwrap().len(), len);
+
+       // Paket kürzer als der Zwischenstand (z. B. Stick zu früh abgezogen)
+       fs::OpenOptions::new()
+           .write(true)
+           .open(&package)
+           .unwrap()
+           .set_len(state.length - 1)
+           .unwrap();
+       let resumable = open_interrupted(&package, &state, PASSWORD, "alice", &None).unwrap();
+       let err = resume_archive(
+           resumable,
+           state,
+           &package,
+           state_path.clone(),
+           CompressionSettings::default(),
+       )
+       .err()
+       .unwrap();
+       assert!(err.contains("kürzer als erwartet"), "{}", err);
+       let _ = fs::remove_dir_all(&root);
+   }
+}
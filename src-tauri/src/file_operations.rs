@@ -10,6 +10,7 @@ pub async fn select_import_file(app: tauri::AppHandle) -> Result<String, String>
         .file()
         .set_title("NiloShift-Paket auswählen")
         .add_filter("NiloShift Paket", &["nilo"])
+        .add_filter("NiloShift Paket (Volume)", &["001"])
         .add_filter("Alle Dateien", &["*"])
         .pick_file(move |file_path| {
             let mut res = result_clone.lock().unwrap();
@@ -65,4 +66,71 @@ pub async fn select_export_path(
         }
         tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
     }
-}
\ No newline at end of file
+}
+#[tauri::command]
+pub async fn select_folder(app: tauri::AppHandle, title: Option<String>) -> Result<String, String> {
+    let result = Arc::new(Mutex::new(None::<Option<String>>));
+    let result_clone = Arc::clone(&result);
+
+    app.dialog()
+        .file()
+        .set_title(title.unwrap_or_else(|| "Ordner auswählen".to_string()))
+        .pick_folder(move |folder_path| {
+            let mut res = result_clone.lock().unwrap();
+            *res = Some(folder_path.map(|p| p.as_path().unwrap().to_string_lossy().to_string()));
+        });
+
+    loop {
+        {
+            let res = result.lock().unwrap();
+            if let Some(folder_result) = res.as_ref() {
+                return match folder_result {
+                    Some(path) => Ok(path.clone()),
+                    None => Err("Kein Ordner ausgewählt".to_string()),
+                };
+            }
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+    }
+}
+
+/// Schlüsseldatei für den Empfänger-Modus öffnen oder, mit `save`, einen Speicherort
+/// für ein neues Schlüsselpaar wählen.
+#[tauri::command]
+pub async fn select_key_file(app: tauri::AppHandle, save: Option<bool>) -> Result<String, String> {
+    let result = Arc::new(Mutex::new(None::<Option<String>>));
+    let result_clone = Arc::clone(&result);
+
+    let builder = app
+        .dialog()
+        .file()
+        .add_filter("NiloShift-Schlüssel", &["key"])
+        .add_filter("Alle Dateien", &["*"]);
+    let callback = move |file_path: Option<tauri_plugin_dialog::FilePath>| {
+        let mut res = result_clone.lock().unwrap();
+        *res = Some(file_path.map(|p| p.as_path().unwrap().to_string_lossy().to_string()));
+    };
+    if save.unwrap_or(false) {
+        builder
+            .set_title("Speicherort für Schlüsseldatei wählen")
+            .set_file_name("niloshift.key")
+            .save_file(callback);
+    } else {
+        builder
+            .set_title("Schlüsseldatei auswählen")
+            .pick_file(callback);
+    }
+
+    loop {
+        {
+            let res = result.lock().unwrap();
+            if let Some(file_result) = res.as_ref() {
+                return match file_result {
+                    Some(path) => Ok(path.clone()),
+                    None => Err("Keine Datei ausgewählt".to_string()),
+                };
+            }
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+    }
+}
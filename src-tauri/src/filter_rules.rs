@@ -0,0 +1,437 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Regeln, welche Dateien beim Export ausgelassen werden. Muster folgen .gitignore:
+//
+//   Cache/          Ordner namens "Cache" in jeder Tiefe
+//   *.iso           Dateien mit dieser Endung in jeder Tiefe
+//   /Temp           nur direkt im Kategorie-Ordner
+//   **/Service Worker/CacheStorage/
+//   !Wichtig.tmp    nimmt eine zuvor ausgeschlossene Datei wieder auf
+//
+// Groß-/Kleinschreibung spielt keine Rolle. Das letzte passende Muster gewinnt;
+// Inhalte eines ausgeschlossenen Ordners können nicht wieder aufgenommen werden.
+
+/// Ausschlussmuster, die für alle Kategorien gelten.
+pub const GLOBAL_EXCLUDES: &[&str] = &["node_modules/", "~$*"];
+
+/// Filterregeln, wie sie in den Exportoptionen angegeben und im Manifest abgelegt werden.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FilterRules {
+    /// gitignore-artige Muster.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Größere Dateien werden ausgelassen (Bytes).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u64>,
+    /// Nur Dateien mit diesen Endungen (ohne Punkt); leer = alle.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+    /// Nur Dateien, die danach geändert wurden (Sekunden seit 1970).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_after: Option<u64>,
+    /// Nur Dateien, die davor geändert wurden (Sekunden seit 1970).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_before: Option<u64>,
+}
+
+impl FilterRules {
+    /// Wirksame Regeln einer Kategorie: Standardmuster, dann globale und zuletzt
+    /// Kategorie-Regeln. Einzelwerte der Kategorie haben Vorrang vor den globalen.
+    pub fn effective(
+        defaults: &[&str],
+        global: &FilterRules,
+        category: Option<&FilterRules>,
+    ) -> Self {
+        let empty = FilterRules::default();
+        let category = category.unwrap_or(&empty);
+        let exclude = GLOBAL_EXCLUDES
+            .iter()
+            .chain(defaults)
+            .map(|p| p.to_string())
+            .chain(global.exclude.iter().cloned())
+            .chain(category.exclude.iter().cloned())
+            .collect();
+        let extensions = if category.extensions.is_empty() {
+            global.extensions.clone()
+        } else {
+            category.extensions.clone()
+        };
+        FilterRules {
+            exclude,
+            max_file_size: category.max_file_size.or(global.max_file_size),
+            extensions,
+            modified_after: category.modified_after.or(global.modified_after),
+            modified_before: category.modified_before.or(global.modified_before),
+        }
+    }
+}
+
+/// Grund, aus dem ein Eintrag ausgelassen wurde.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    Pattern(String),
+    TooLarge(u64),
+    Extension,
+    ModifiedBefore(u64),
+    ModifiedAfter(u64),
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Pattern(p) => write!(f, "Regel \"{}\"", p),
+            SkipReason::TooLarge(max) => write!(f, "größer als {} Bytes", max),
+            SkipReason::Extension => write!(f, "Dateiendung nicht ausgewählt"),
+            SkipReason::ModifiedBefore(t) => write!(f, "vor {} geändert", t),
+            SkipReason::ModifiedAfter(t) => write!(f, "nach {} geändert", t),
+        }
+    }
+}
+
+/// Vorbereitete Regeln zum schnellen Prüfen vieler Pfade.
+pub struct RuleSet {
+    patterns: Vec<Pattern>,
+    max_file_size: Option<u64>,
+    extensions: Vec<String>,
+    modified_after: Option<u64>,
+    modified_before: Option<u64>,
+}
+
+struct Pattern {
+    text: String,
+    negated: bool,
+    dir_only: bool,
+    /// Muster ohne Schrägstrich gelten für den Namen in jeder Tiefe.
+    anchored: bool,
+    segments: Vec<Vec<char>>,
+}
+
+impl RuleSet {
+    pub fn new(rules: &FilterRules) -> Result<Self, String> {
+        let patterns = rules
+            .exclude
+            .iter()
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty() && !p.starts_with('#'))
+            .map(Pattern::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(RuleSet {
+            patterns,
+            max_file_size: rules.max_file_size,
+            extensions: rules
+                .extensions
+                .iter()
+                .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect(),
+            modified_after: rules.modified_after,
+            modified_before: rules.modified_before,
+        })
+    }
+
+    /// Prüft einen Ordner (Pfad relativ zum Kategorie-Ordner, mit `/`).
+    pub fn check_dir(&self, rel: &str) -> Option<SkipReason> {
+        self.match_patterns(rel, true)
+    }
+
+    /// Prüft eine Datei (Pfad relativ zum Kategorie-Ordner, mit `/`).
+    pub fn check_file(&self, rel: &str, size: u64, modified: Option<u64>) -> Option<SkipReason> {
+        if let Some(reason) = self.match_patterns(rel, false) {
+            return Some(reason);
+        }
+        if let Some(max) = self.max_file_size {
+            if size > max {
+                return Some(SkipReason::TooLarge(max));
+            }
+        }
+        if !self.extensions.is_empty() {
+            let name = rel.rsplit('/').next().unwrap_or(rel);
+            let ext = name
+                .rsplit_once('.')
+                .map(|(_, e)| e.to_lowercase())
+                .unwrap_or_default();
+            if !self.extensions.contains(&ext) {
+                return Some(SkipReason::Extension);
+            }
+        }
+        if let Some(modified) = modified {
+            if let Some(after) = self.modified_after {
+                if modified < after {
+                    return Some(SkipReason::ModifiedBefore(after));
+                }
+            }
+            if let Some(before) = self.modified_before {
+                if modified > before {
+                    return Some(SkipReason::ModifiedAfter(before));
+                }
+            }
+        }
+        None
+    }
+
+    fn match_patterns(&self, rel: &str, is_dir: bool) -> Option<SkipReason> {
+        let path: Vec<Vec<char>> = rel
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_lowercase().chars().collect())
+            .collect();
+        if path.is_empty() {
+            return None;
+        }
+        let mut result = None;
+        for pattern in &self.patterns {
+            if pattern.dir_only && !is_dir {
+                continue;
+            }
+            if pattern.matches(&path) {
+                result = if pattern.negated {
+                    None
+                } else {
+                    Some(SkipReason::Pattern(pattern.text.clone()))
+                };
+            }
+        }
+        result
+    }
+}
+
+impl Pattern {
+    fn parse(text: &str) -> Result<Self, String> {
+        let (negated, body) = match text.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let dir_only = body.ends_with('/');
+        let body = body.trim_end_matches('/');
+        let anchored = body.contains('/');
+        let body = body.trim_start_matches('/');
+        if body.is_empty() {
+            return Err(format!("Ungültige Filterregel: {}", text));
+        }
+        let segments = body
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_lowercase().chars().collect())
+            .collect();
+        Ok(Pattern {
+            text: text.to_string(),
+            negated,
+            dir_only,
+            anchored,
+            segments,
+        })
+    }
+
+    fn matches(&self, path: &[Vec<char>]) -> bool {
+        if self.anchored {
+            match_segments(&self.segments, path)
+        } else {
+            path.last()
+                .is_some_and(|name| match_segment(&self.segments[0], name))
+        }
+    }
+}
+
+/// Vergleicht Pfadsegmente; `**` steht für beliebig viele Segmente.
+fn match_segments(pattern: &[Vec<char>], path: &[Vec<char>]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if *first == ['*', '*'] => {
+            (0..=path.len()).any(|skip| match_segments(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path_rest)) => {
+                match_segment(first, name) && match_segments(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Vergleicht einen Namen mit `*`, `?` und Zeichenklassen wie `[abc]`, `[a-z]`, `[!a]`.
+fn match_segment(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| match_segment(&pattern[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && match_segment(&pattern[1..], &name[1..]),
+        Some('[') => {
+            let Some(end) = pattern
+                .iter()
+                .skip(2)
+                .position(|c| *c == ']')
+                .map(|p| p + 2)
+            else {
+                return name.first() == Some(&'[') && match_segment(&pattern[1..], &name[1..]);
+            };
+            let Some(c) = name.first() else {
+                return false;
+            };
+            let mut class = &pattern[1..end];
+            let negated = matches!(class.first(), Some('!') | Some('^'));
+            if negated {
+                class = &class[1..];
+            }
+            let mut found = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    found |= class[i] <= *c && *c <= class[i + 2];
+                    i += 3;
+                } else {
+                    found |= class[i] == *c;
+                    i += 1;
+                }
+            }
+            found != negated && match_segment(&pattern[end + 1..], &name[1..])
+        }
+        Some('\\') if pattern.len() > 1 => {
+            name.first() == Some(&pattern[1]) && match_segment(&pattern[2..], &name[1..])
+        }
+        Some(p) => name.first() == Some(p) && match_segment(&pattern[1..], &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    fn segments(path: &str) -> Vec<Vec<char>> {
+        path.split('/').map(chars).collect()
+    }
+
+    fn rules(exclude: &[&str]) -> RuleSet {
+        RuleSet::new(&FilterRules {
+            exclude: exclude.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn segment_wildcards_and_classes() {
+        let m = |p: &str, n: &str| match_segment(&chars(p), &chars(n));
+        assert!(m("*.iso", "win.iso"));
+        assert!(m("*.iso", ".iso"));
+        assert!(!m("*.iso", "win.iso.bak"));
+        assert!(m("~$*", "~$Bericht.docx"));
+        assert!(m("a?c", "abc"));
+        assert!(!m("a?c", "ac"));
+        assert!(m("[abc]x", "bx"));
+        assert!(!m("[abc]x", "dx"));
+        assert!(m("[a-c]x", "cx"));
+        assert!(!m("[a-c]x", "dx"));
+        assert!(m("[!a]x", "bx"));
+        assert!(!m("[^a]x", "ax"));
+        // Offene Klasse und maskierte Zeichen gelten wörtlich
+        assert!(m("[ab", "[ab"));
+        assert!(m("\\*", "*"));
+        assert!(!m("\\*", "a"));
+    }
+
+    #[test]
+    fn double_star_spans_segments() {
+        let pattern = segments("**/service worker/cachestorage");
+        assert!(match_segments(
+            &pattern,
+            &segments("service worker/cachestorage")
+        ));
+        assert!(match_segments(
+            &pattern,
+            &segments("default/service worker/cachestorage")
+        ));
+        assert!(match_segments(
+            &pattern,
+            &segments("a/b/service worker/cachestorage")
+        ));
+        assert!(!match_segments(
+            &pattern,
+            &segments("service worker/cachestorage/x")
+        ));
+        assert!(match_segments(&segments("a/**/b"), &segments("a/b")));
+        assert!(match_segments(&segments("a/**/b"), &segments("a/x/y/b")));
+        assert!(!match_segments(&segments("a/*"), &segments("a/x/y")));
+    }
+
+    #[test]
+    fn folder_pattern_in_any_depth() {
+        let set = rules(&["Cache/"]);
+        assert!(set.check_dir("Cache").is_some());
+        assert!(set.check_dir("Default/Cache").is_some());
+        // Nur Ordner, keine gleichnamige Datei
+        assert!(set.check_file("Default/Cache", 1, None).is_none());
+        assert!(set.check_dir("Cache Studies").is_none());
+    }
+
+    #[test]
+    fn extension_pattern_in_any_depth() {
+        let set = rules(&["*.iso"]);
+        assert!(set.check_file("win.iso", 1, None).is_some());
+        assert!(set.check_file("Images/Win.ISO", 1, None).is_some());
+        assert!(set.check_file("Images/win.img", 1, None).is_none());
+    }
+
+    #[test]
+    fn leading_slash_anchors_to_category() {
+        let set = rules(&["/Temp"]);
+        assert!(set.check_dir("Temp").is_some());
+        assert!(set.check_file("temp", 1, None).is_some());
+        assert!(set.check_dir("Projekte/Temp").is_none());
+    }
+
+    #[test]
+    fn nested_anchored_folder() {
+        let set = rules(&["**/Service Worker/CacheStorage/"]);
+        assert!(set
+            .check_dir("Default/Service Worker/CacheStorage")
+            .is_some());
+        assert!(set.check_dir("Service Worker/CacheStorage").is_some());
+        assert!(set.check_dir("Default/Service Worker").is_none());
+    }
+
+    #[test]
+    fn negation_and_last_match_wins() {
+        let set = rules(&["*.tmp", "!Wichtig.tmp"]);
+        assert_eq!(
+            set.check_file("a.tmp", 1, None),
+            Some(SkipReason::Pattern("*.tmp".to_string()))
+        );
+        assert!(set.check_file("Ordner/wichtig.tmp", 1, None).is_none());
+
+        // Ein späteres Muster schließt die Datei wieder aus
+        let set = rules(&["*.tmp", "!Wichtig.tmp", "Ordner/*.tmp"]);
+        assert!(set.check_file("Ordner/Wichtig.tmp", 1, None).is_some());
+        assert!(set.check_file("Wichtig.tmp", 1, None).is_none());
+    }
+
+    #[test]
+    fn excluded_folder_contents_stay_excluded() {
+        // Die Datei passt nicht auf `Cache/`, der Ordner selbst bleibt aber ausgeschlossen,
+        // sodass sein Inhalt beim Durchlaufen nie erreicht wird.
+        let set = rules(&["Cache/", "!Cache/wichtig.txt", "!wichtig.txt"]);
+        assert!(set.check_dir("Cache").is_some());
+        assert!(set.check_dir("Default/Cache").is_some());
+
+        // Nur ein Muster für den Ordner selbst nimmt ihn wieder auf
+        let set = rules(&["Cache/", "!/Default/Cache/"]);
+        assert!(set.check_dir("Default/Cache").is_none());
+        assert!(set.check_dir("Other/Cache").is_some());
+    }
+
+    #[test]
+    fn comments_and_invalid_patterns() {
+        let set = rules(&["# Kommentar", "", "  "]);
+        assert!(set.check_file("# Kommentar", 1, None).is_none());
+        for invalid in ["/", "!", "!/"] {
+            assert!(RuleSet::new(&FilterRules {
+                exclude: vec![invalid.to_string()],
+                ..Default::default()
+            })
+            .is_err());
+        }
+    }
+}
@@ -1,394 +1,823 @@
-use aes_gcm::{
-    aead::{Aead, KeyInit},
-    Aes256Gcm, Nonce,
-};
 use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::io::{self};
+use std::io::{self, BufRead, Read, Write};
 use std::path::{Path, PathBuf};
-use std::time::Instant;
-use tauri::Emitter;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use zip::read::read_zipfile_from_stream;
 use zip::ZipArchive;
 
-#[derive(Serialize, Clone)]
-struct ProgressPayload {
-    percent: f32,
-    eta_ms: u64,
-    phase: &'static str,
-    processed: u64,
-    total: u64,
-}
+use crate::archive::{ArchiveEntry, ArchiveReader};
+use crate::conflict::{ConflictPolicy, ConflictResolver, Resolution};
+use crate::jobs::{self, JobHandle};
+use crate::journal::{self, ImportJournal};
+use crate::known_folders::{self, KNOWN_FOLDERS};
+use crate::package_format::{self, OpenedPackage, PackageManifest};
+use crate::profiles::{self, UserProfile};
+use crate::progress::{emit_progress, ProgressSink};
+use crate::recipients::Credentials;
+use crate::report::{self, TransferReport, TransferSummary};
+use crate::resume::{self, ImportState};
+use crate::volumes::VolumeSet;
 
-fn emit_progress(
-    app: &tauri::AppHandle,
-    start: Instant,
-    processed: u64,
-    total: u64,
-    phase: &'static str,
-) {
-    let percent = if total == 0 {
-        0.0
-    } else {
-        (processed as f32 / total as f32) * 100.0
-    };
-    let elapsed = start.elapsed().as_millis() as u64;
-    let eta_ms = if processed == 0 {
-        0
-    } else {
-        ((elapsed as f64) * ((total as f64 / processed as f64) - 1.0)).max(0.0) as u64
-    };
-    let _ = app.emit(
-        "import-progress",
-        ProgressPayload {
-            percent,
-            eta_ms,
-            phase,
-            processed,
-            total,
-        },
-    );
-}
+const PROGRESS_EVENT: &str = "import-progress";
 
 #[derive(Serialize, Clone)]
 pub struct DetectedContents {
-    desktop: bool,
-    documents: bool,
-    pictures: bool,
-    downloads: bool,
-    chrome: bool,
-    edge: bool,
-    firefox: bool,
-    outlook_signatures: bool,
+    /// Gefundene Inhalte je bekanntem Ordner, z. B. `"desktop": true`.
+    #[serde(flatten)]
+    found: BTreeMap<String, bool>,
+    /// Manifest neuerer Pakete mit Dateianzahl und Größe je Kategorie.
+    manifest: Option<PackageManifest>,
+}
+
+impl DetectedContents {
+    /// Alle bekannten Ordner mit demselben Wert, noch ohne Manifest.
+    fn all(found: bool) -> Self {
+        DetectedContents {
+            found: KNOWN_FOLDERS
+                .iter()
+                .map(|f| (f.id.to_string(), found))
+                .collect(),
+            manifest: None,
+        }
+    }
+
+    /// Kategorien des Manifests mit Dateien, soweit sie bekannt oder eigene Pfade sind.
+    pub(crate) fn from_manifest(manifest: PackageManifest) -> Self {
+        let mut detected = DetectedContents::all(false);
+        for category in &manifest.categories {
+            let known = known_folders::by_id(&category.id).is_some() || category.custom.is_some();
+            if category.files > 0 && known {
+                detected.found.insert(category.id.clone(), true);
+            }
+        }
+        detected.manifest = Some(manifest);
+        detected
+    }
+
+    /// Auswahl aller gefundenen Inhalte, wie sie die Oberfläche vorschlägt.
+    pub(crate) fn selection(&self) -> SelectedItems {
+        SelectedItems {
+            items: self.found.clone(),
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct SelectedItems {
-    desktop: bool,
-    documents: bool,
-    pictures: bool,
-    downloads: bool,
-    chrome: bool,
-    edge: bool,
-    firefox: bool,
-    outlook_signatures: bool,
+    /// Auswahl je bekanntem Ordner, z. B. `"desktop": true`.
+    #[serde(flatten)]
+    items: BTreeMap<String, bool>,
 }
 
 #[tauri::command]
 pub async fn detect_package_contents(
     package_path: String,
     password: String,
+    identity_path: Option<String>,
 ) -> Result<DetectedContents, String> {
-    println!("=== DETECTION START ===");
-    println!("Datei: {}", &package_path);
-    println!("Passwort-Länge: {}", password.len());
-    
-    // Grundlegende Validierung
-    let package_path = PathBuf::from(&package_path);
-    if !package_path.exists() { 
-        println!("❌ Datei existiert nicht");
-        return Err("Paketdatei nicht gefunden".to_string()); 
-    }
-
-    // Datei lesen
-    println!("📁 Lese Datei...");
-    let data = match fs::read(&package_path) {
-        Ok(data) => {
-            println!("✅ Datei gelesen: {} bytes", data.len());
-            data
-        },
-        Err(e) => {
-            println!("❌ Fehler beim Lesen: {}", e);
-            return Err(format!("Konnte Paketdatei nicht lesen: {}", e));
-        }
-    };
-    
-    if data.len() < 12 { 
-        println!("❌ Datei zu klein: {} bytes", data.len());
-        return Err("Datei ist beschädigt".to_string()); 
+    let credentials = Credentials::new(&password, identity_path.as_deref())?;
+    let package = open_package(&package_path, &credentials)?;
+
+    // Aktuelle Pakete bringen ein Manifest mit – dann ist keine Entschlüsselung des Inhalts nötig
+    if let Some(manifest) = package.manifest {
+        return Ok(DetectedContents::from_manifest(manifest));
     }
-    
-    // Entschlüsselung
-    println!("🔐 Starte Entschlüsselung... (Datei: {:.1} MB)", data.len() as f64 / 1024.0 / 1024.0);
-    let (nonce_bytes, cipher_bytes) = data.split_at(12);
-    let nonce = Nonce::from_slice(nonce_bytes);
-    let mut key = [0u8; 32];
-    for (i, &b) in password.as_bytes().iter().enumerate() { 
-        if i >= 32 { break; } 
-        key[i] = b; 
+
+    // Alte Pakete sind ein ZIP, das bereits vollständig entschlüsselt im Speicher liegt;
+    // das Inhaltsverzeichnis nennt alle Einträge
+    let mut decrypted = Vec::new();
+    let mut content = package.content;
+    content.read_to_end(&mut decrypted).map_err(|e| e.to_string())?;
+    if !decrypted.starts_with(b"PK\x03\x04") {
+        return Err("Paket enthält kein Manifest, der Inhalt ist unbekannt".to_string());
     }
-    
-    let cipher = match Aes256Gcm::new_from_slice(&key) {
-        Ok(cipher) => {
-            println!("✅ Cipher erstellt");
-            cipher
-        },
-        Err(e) => {
-            println!("❌ Cipher-Fehler: {}", e);
-            return Err(format!("Cipher-Fehler: {}", e));
-        }
-    };
-    
-    // Bei großen Dateien (>100MB) einen schnelleren Test machen
-    if cipher_bytes.len() > 100 * 1024 * 1024 {
-        println!("⚠️ Große Datei erkannt ({:.1} MB) - verwende schnelle Validierung", cipher_bytes.len() as f64 / 1024.0 / 1024.0);
-        
-        // Teste nur die ersten 1KB zur Passwort-Validierung
-        let test_data = &cipher_bytes[..std::cmp::min(1024, cipher_bytes.len())];
-        match cipher.decrypt(nonce, test_data) {
-            Ok(_) => {
-                println!("✅ Passwort validiert - überspringe vollständige Entschlüsselung für Detection");
-                // Für große Dateien: Setze alle Inhalte auf true
-                return Ok(DetectedContents {
-                    desktop: true,
-                    documents: true,
-                    pictures: true,
-                    downloads: true,
-                    chrome: true,
-                    edge: true,
-                    firefox: true,
-                    outlook_signatures: true,
-                });
-            },
-            Err(e) => {
-                println!("❌ Passwort-Validierung fehlgeschlagen: {}", e);
-                return Err("Entschlüsselung fehlgeschlagen (falsches Passwort?)".to_string());
-            }
+    let archive = ZipArchive::new(std::io::Cursor::new(decrypted))
+        .map_err(|e| format!("ZIP fehlerhaft: {}", e))?;
+    let mut detected = DetectedContents::all(false);
+    for name in archive.file_names() {
+        if let Some(folder) = known_folders::for_archive_path(name) {
+            detected.found.insert(folder.id.to_string(), true);
         }
     }
-    
-    let decrypted = match cipher.decrypt(nonce, cipher_bytes) {
-        Ok(data) => {
-            println!("✅ Entschlüsselung erfolgreich: {} bytes", data.len());
-            data
-        },
-        Err(e) => {
-            println!("❌ Entschlüsselung fehlgeschlagen: {}", e);
-            return Err("Entschlüsselung fehlgeschlagen (falsches Passwort?)".to_string());
-        }
-    };
-
-    // ZIP verarbeiten
-    println!("📦 Öffne ZIP...");
-    let reader = std::io::Cursor::new(decrypted);
-    let mut archive = match ZipArchive::new(reader) {
-        Ok(archive) => {
-            println!("✅ ZIP geöffnet: {} Einträge", archive.len());
-            archive
-        },
-        Err(e) => {
-            println!("❌ ZIP-Fehler: {}", e);
-            return Err(format!("ZIP fehlerhaft: {}", e));
-        }
-    };
+    Ok(detected)
+}
 
-    // Einfache Detection - nur Dateinamen sammeln
-    println!("🔍 Sammle Dateinamen...");
-    let mut file_names = Vec::new();
-    let max_check = std::cmp::min(archive.len(), 50); // Nur erste 50 Einträge
-    
-    for i in 0..max_check {
-        match archive.by_index(i) {
-            Ok(file) => {
-                let name = file.name().to_string();
-                file_names.push(name);
-                if i < 10 {
-                    println!("  Eintrag {}: {}", i, file.name());
-                }
-            }
-            Err(e) => {
-                println!("  Fehler bei Eintrag {}: {}", i, e);
-                break; // Bei ersten Fehler aufhören
-            }
-        }
-    }
-    
-    println!("📋 Gesamt {} Dateinamen gesammelt", file_names.len());
-
-    // Pattern-Matching auf gesammelten Namen
-    let mut detected = DetectedContents {
-        desktop: false,
-        documents: false,
-        pictures: false,
-        downloads: false,
-        chrome: false,
-        edge: false,
-        firefox: false,
-        outlook_signatures: false,
-    };
+/// Manifest eines Pakets, ohne den Inhalt zu entschlüsseln; alte Pakete haben keines.
+pub fn read_manifest(
+    package_path: &str,
+    credentials: &Credentials,
+) -> Result<Option<PackageManifest>, String> {
+    Ok(open_package(package_path, credentials)?.manifest)
+}
 
-    for name in &file_names {
-        if name.contains("Desktop/") { detected.desktop = true; }
-        if name.contains("Documents/") || name.contains("Dokumente/") { detected.documents = true; }
-        if name.contains("Pictures/") || name.contains("Bilder/") { detected.pictures = true; }
-        if name.contains("Downloads/") { detected.downloads = true; }
-        if name.contains("Chrome/User Data/") { detected.chrome = true; }
-        if name.contains("Edge/User Data/") { detected.edge = true; }
-        if name.contains("Mozilla/Firefox/") { detected.firefox = true; }
-        if name.contains("Microsoft/Signatures/") { detected.outlook_signatures = true; }
-    }
-    
-    println!("✅ Detection abgeschlossen:");
-    println!("  Desktop: {}, Documents: {}, Pictures: {}, Downloads: {}", 
-             detected.desktop, detected.documents, detected.pictures, detected.downloads);
-    println!("  Chrome: {}, Edge: {}, Firefox: {}, Outlook: {}", 
-             detected.chrome, detected.edge, detected.firefox, detected.outlook_signatures);
-    println!("=== DETECTION END ===");
-    
-    Ok(detected)
+/// Öffnet ein Paket; bei aufgeteilten Paketen darf jedes Volume gewählt werden.
+fn open_package(package_path: &str, credentials: &Credentials) -> Result<OpenedPackage, String> {
+    let volumes = VolumeSet::discover(Path::new(package_path))?;
+    let package = package_format::open_package_reader(volumes.open()?, credentials)?;
+    volumes.check_complete(package.volume_size)?;
+    Ok(package)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn start_import_command(
     app: tauri::AppHandle,
     package_path: String,
     password: String,
     selected_user: String,
     selected_items: SelectedItems,
-) -> Result<String, String> {
-    println!("Import gestartet mit Auswahl: {:?}", selected_items);
+    verify_hashes: Option<bool>,
+    identity_path: Option<String>,
+    custom_targets: Option<BTreeMap<String, String>>,
+    conflict_policy: Option<ConflictPolicy>,
+    job_id: Option<String>,
+    resume_interrupted: Option<bool>,
+) -> Result<TransferSummary, String> {
+    run_import(
+        &ProgressSink::from(app),
+        package_path,
+        password,
+        selected_user,
+        selected_items,
+        verify_hashes,
+        identity_path,
+        custom_targets,
+        conflict_policy,
+        job_id,
+        resume_interrupted,
+    )
+}
+
+/// Import ohne Fenster, auch für `niloshift-cli`; Parameter wie `start_import_command`.
+#[allow(clippy::too_many_arguments)]
+pub fn run_import(
+    sink: &ProgressSink,
+    package_path: String,
+    password: String,
+    selected_user: String,
+    selected_items: SelectedItems,
+    verify_hashes: Option<bool>,
+    identity_path: Option<String>,
+    custom_targets: Option<BTreeMap<String, String>>,
+    conflict_policy: Option<ConflictPolicy>,
+    job_id: Option<String>,
+    resume_interrupted: Option<bool>,
+) -> Result<TransferSummary, String> {
+    eprintln!("Import gestartet mit Auswahl: {:?}", selected_items);
+    // Prüfsummen standardmäßig kontrollieren; ältere Pakete haben keine
+    let verify_hashes = verify_hashes.unwrap_or(true);
     
     let package_path = PathBuf::from(package_path);
-    if !package_path.exists() {
-        return Err("Paketdatei nicht gefunden".to_string());
+    let volumes = VolumeSet::discover(&package_path)?;
+    if !selected_items.any() {
+        return Err("Keine Inhalte zum Importieren ausgewählt".to_string());
     }
 
+    // Pausieren/Abbrechen über pause_job/cancel_job
+    let job = jobs::register(job_id.as_deref().unwrap_or("import"))?;
     let start = Instant::now();
-    emit_progress(&app, start, 0, 100, "Entschlüsseln");
-
-    let data =
-        fs::read(&package_path).map_err(|e| format!("Konnte Paketdatei nicht lesen: {}", e))?;
-    if data.len() < 12 {
-        return Err("Datei ist beschädigt".to_string());
-    }
-    let (nonce_bytes, cipher_bytes) = data.split_at(12);
-    let nonce = Nonce::from_slice(nonce_bytes);
-    let mut key = [0u8; 32];
-    for (i, &b) in password.as_bytes().iter().enumerate() {
-        if i >= 32 {
-            break;
-        }
-        key[i] = b;
-    }
-    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| format!("Cipher-Fehler: {}", e))?;
-    let decrypted = cipher
-        .decrypt(nonce, cipher_bytes)
-        .map_err(|e| format!("Entschlüsselung fehlgeschlagen: {}", e))?;
-    emit_progress(&app, start, 10, 100, "Entpacken");
-
-    let temp_dir = std::env::temp_dir().join("niloshift_import");
-    if temp_dir.exists() {
-        let _ = fs::remove_dir_all(&temp_dir);
-    }
-    fs::create_dir_all(&temp_dir).map_err(|e| e.to_string())?;
-
-    let zip_path = temp_dir.join("data.zip");
-    fs::write(&zip_path, &decrypted)
-        .map_err(|e| format!("Konnte temporäre ZIP schreiben: {}", e))?;
-
-    let file = fs::File::open(&zip_path).map_err(|e| e.to_string())?;
-    let mut archive = ZipArchive::new(file).map_err(|e| format!("ZIP fehlerhaft: {}", e))?;
-
-    let extract_dir = temp_dir.join("extract");
-    fs::create_dir_all(&extract_dir).map_err(|e| e.to_string())?;
-
-    let total = archive.len() as u64;
-    let mut processed = 0u64;
-
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i).map_err(|e| e.to_string())?;
-        // Stelle sicher, dass AppData-Struktur korrekt wiederhergestellt wird
-        let name = sanitize_zip_path(file.name());
-        let outpath = extract_dir.join(name);
-        if file.name().ends_with('/') {
-            let _ = fs::create_dir_all(&outpath);
-        } else {
-            if let Some(p) = outpath.parent() {
-                let _ = fs::create_dir_all(p);
+    emit_progress(sink, PROGRESS_EVENT, start, 0, 100, "Entschlüsseln");
+
+    let package_len = volumes.total_len();
+    let (file, bytes_read) = CountingReader::new(volumes.open()?);
+    let credentials = Credentials::new(&password, identity_path.as_deref())?;
+    let package = package_format::open_package_reader(file, &credentials)?;
+    volumes.check_complete(package.volume_size)?;
+
+    let target = ImportTarget {
+        selected_user,
+        selected_items,
+        verify_hashes,
+        custom_targets: custom_targets.unwrap_or_default(),
+        conflict_policy: conflict_policy.unwrap_or_default(),
+    };
+    restore_package(
+        sink,
+        job.handle(),
+        start,
+        package,
+        &package_path,
+        bytes_read,
+        package_len,
+        target,
+        resume_interrupted.unwrap_or(false),
+    )
+}
+
+/// Ziel und Auswahl eines Imports.
+pub(crate) struct ImportTarget {
+    pub selected_user: String,
+    pub selected_items: SelectedItems,
+    pub verify_hashes: bool,
+    pub custom_targets: BTreeMap<String, String>,
+    pub conflict_policy: ConflictPolicy,
+}
+
+/// Stellt ein geöffnetes Paket im Zielprofil wieder her, egal ob es aus Dateien oder
+/// über das Netzwerk gelesen wird. `package_path` kennzeichnet das Paket in Protokoll,
+/// Bericht und Zwischenstand; `bytes_read` zählt die gelesenen von insgesamt
+/// `package_len` Bytes für den Fortschritt.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn restore_package(
+    sink: &ProgressSink,
+    job: &JobHandle,
+    start: Instant,
+    package: OpenedPackage,
+    package_path: &Path,
+    bytes_read: Arc<AtomicU64>,
+    package_len: u64,
+    target: ImportTarget,
+    resume_interrupted: bool,
+) -> Result<TransferSummary, String> {
+    let verify_hashes = target.verify_hashes;
+    let mut reader = io::BufReader::new(package.content);
+
+    let profile = profiles::user_profile(&target.selected_user)?;
+    let user_base = profile.base.clone();
+    let plan = RestorePlan::new(
+        &profile,
+        target.selected_items,
+        package.manifest.as_ref(),
+        &target.custom_targets,
+    )?;
+    let conflicts = ConflictResolver::new(target.conflict_policy, &user_base);
+
+    // Jede Änderung am Profil protokollieren, damit der Import rückgängig gemacht werden
+    // kann. Ein unterbrochener Import führt sein Protokoll weiter und überspringt die
+    // bereits verarbeiteten Einträge.
+    let interrupted = if resume_interrupted {
+        resume::find_import(&user_base, package_path)
+    } else {
+        resume::discard_imports(&user_base, package_path);
+        None
+    };
+    let mut journal = match &interrupted {
+        Some(id) => ImportJournal::open(id)?,
+        None => ImportJournal::create(&user_base, package_path)?,
+    };
+    let state_path = resume::import_state_path(journal.dir());
+    let state: Option<ImportState> = interrupted.and_then(|_| resume::load(&state_path));
+    let (mut report, mut stats) = match state {
+        Some(state) => {
+            eprintln!("⏯️ Setze Import fort ab Eintrag {}", state.entries);
+            let stats = RestoreStats::from_state(&state);
+            (state.report, stats)
+        }
+        _ => (
+            TransferReport::new("import", package_path),
+            RestoreStats::default(),
+        ),
+    };
+    let mut last_checkpoint = Instant::now();
+    let progress = |stats: &RestoreStats, report: &TransferReport| {
+        let done = bytes_read.load(Ordering::Relaxed);
+        emit_progress(
+            sink,
+            PROGRESS_EVENT,
+            start,
+            (done.saturating_mul(100) / package_len.max(1)).min(99),
+            100,
+            "Wiederherstellen",
+        );
+        if last_checkpoint.elapsed() >= resume::CHECKPOINT_INTERVAL {
+            last_checkpoint = Instant::now();
+            let state = stats.to_state(package_path, &user_base, report);
+            if let Err(e) = resume::save(&state_path, &state) {
+                eprintln!("⚠️ {}", e);
             }
-            let mut outfile = fs::File::create(&outpath).map_err(|e| e.to_string())?;
-            io::copy(&mut file, &mut outfile).map_err(|e| e.to_string())?;
         }
-        processed += 1;
-        emit_progress(&app, start, processed.min(total / 2) + 10, 100, "Entpacken");
+    };
+
+    // Entschlüsseln, Entpacken und Wiederherstellen in einem Durchgang: nur ausgewählte
+    // Einträge werden geschrieben, und zwar direkt an ihren Zielort im Profil.
+    let is_zip = reader
+        .fill_buf()
+        .map_err(|e| e.to_string())?
+        .starts_with(b"PK\x03\x04");
+    let result = if is_zip {
+        restore_zip_stream(
+            &mut reader,
+            &plan,
+            &conflicts,
+            &mut journal,
+            job,
+            &mut stats,
+            &mut report,
+            progress,
+        )
+    } else {
+        restore_archive_stream(
+            reader,
+            &plan,
+            &conflicts,
+            &mut journal,
+            job,
+            verify_hashes,
+            &mut stats,
+            &mut report,
+            progress,
+        )
+    };
+    if let Err(e) = result {
+        let journal_id = journal.id.clone();
+        drop(journal);
+        // Abgebrochen: Profil wieder in den Zustand vor dem Import bringen
+        if job.is_cancelled() {
+            emit_progress(sink, PROGRESS_EVENT, start, 0, 100, "Abgebrochen");
+            let rollback = journal::rollback(&journal_id)?;
+            return Err(format!("Import abgebrochen – {}", rollback.message));
+        }
+        // Stand bis zum letzten vollständigen Eintrag festhalten
+        let state = stats.to_state(package_path, &user_base, &report);
+        if let Err(e) = resume::save(&state_path, &state) {
+            eprintln!("⚠️ {}", e);
+        }
+        return Err(format!(
+            "{} – der Import kann fortgesetzt oder mit dem Importprotokoll {} zurückgesetzt werden",
+            e, journal_id
+        ));
     }
+    resume::remove(&state_path);
 
-    emit_progress(&app, start, 60, 100, "Wiederherstellen");
+    eprintln!(
+        "Wiederhergestellte Dateien: {}, mit falscher Prüfsumme: {}, fehlgeschlagen: {}, Konflikte: {}",
+        stats.restored, stats.corrupt, stats.failed, stats.conflicts
+    );
+    if stats.restored == 0 && stats.corrupt == 0 && stats.failed == 0 && report.skipped.is_empty() {
+        return Err("Keine der ausgewählten Inhalte im Paket gefunden".to_string());
+    }
 
-    let user_base = PathBuf::from(r"C:\\Users").join(&selected_user);
-    let entries = match fs::read_dir(&extract_dir) {
-        Ok(e) => e,
-        Err(e) => return Err(e.to_string()),
+    emit_progress(sink, PROGRESS_EVENT, start, 100, 100, "Fertig");
+    let mut notes = Vec::new();
+    if stats.corrupt > 0 {
+        notes.push(format!(
+            "{} Dateien mit falscher Prüfsumme übersprungen",
+            stats.corrupt
+        ));
+    }
+    if stats.failed > 0 {
+        notes.push(format!("{} Dateien konnten nicht geschrieben werden", stats.failed));
+    }
+    if stats.conflicts > 0 {
+        notes.push(format!("{} Dateien waren bereits vorhanden", stats.conflicts));
+    }
+    let message = if notes.is_empty() {
+        "Import abgeschlossen".to_string()
+    } else {
+        format!("Import abgeschlossen ({})", notes.join(", "))
     };
-    
-    // Filtere die Einträge basierend auf der Auswahl
-    let mut filtered_entries = Vec::new();
-    for entry_res in entries {
-        if let Ok(entry) = entry_res {
-            let name = entry.file_name().to_string_lossy().to_lowercase();
-            let should_import = match name.as_str() {
-                "desktop" => selected_items.desktop,
-                "documents" => selected_items.documents,
-                "pictures" => selected_items.pictures,
-                "downloads" => selected_items.downloads,
-                name if name.starts_with("appdata") => {
-                    // Prüfe, ob Browser-Daten oder Outlook-Signaturen in diesem AppData-Ordner sind
-                    if !selected_items.chrome && !selected_items.edge && !selected_items.firefox && !selected_items.outlook_signatures {
-                        false
-                    } else {
-                        // Prüfe spezifische Browser/Outlook-Unterordner
-                        let appdata_path = entry.path();
-                        should_import_appdata(&appdata_path, &selected_items)
-                    }
+    let mut summary = report.finish(message, &report::import_report_path(&user_base));
+    summary.journal_id = Some(journal.id);
+    Ok(summary)
+}
+
+impl SelectedItems {
+    /// Auswahl genau der angegebenen Kennungen.
+    pub(crate) fn from_ids<'a>(ids: impl IntoIterator<Item = &'a str>) -> Self {
+        SelectedItems {
+            items: ids.into_iter().map(|id| (id.to_string(), true)).collect(),
+        }
+    }
+
+    pub(crate) fn any(&self) -> bool {
+        self.items.values().any(|selected| *selected)
+    }
+
+    fn is_selected(&self, id: &str) -> bool {
+        self.items.get(id).copied().unwrap_or(false)
+    }
+
+    /// Zielpfad (relativ zum Benutzerprofil) für einen Archiveintrag, wenn er zu
+    /// einem ausgewählten Ordner gehört. Unbekannte Ordner werden ignoriert.
+    fn restore_path(&self, archive_path: &str) -> Option<PathBuf> {
+        let folder = known_folders::for_archive_path(archive_path)?;
+        if !self.is_selected(folder.id) {
+            return None;
+        }
+        Some(sanitize_zip_path(&folder.restore_path(archive_path)))
+    }
+}
+
+/// Zielorte der ausgewählten Inhalte beim Import.
+pub(crate) struct RestorePlan {
+    user_base: PathBuf,
+    /// Umgeleitete bekannte Ordner des Zielprofils (siehe `profiles`).
+    redirected: BTreeMap<&'static str, PathBuf>,
+    selected: SelectedItems,
+    /// Präfix im Archiv und Zielordner der ausgewählten benutzerdefinierten Pfade.
+    custom: Vec<(String, PathBuf)>,
+}
+
+impl RestorePlan {
+    /// `custom_targets` ersetzt den Zielordner einzelner benutzerdefinierter Pfade
+    /// (Kennung → absoluter oder zum Profil relativer Pfad). Ziele aus dem Manifest
+    /// werden nur innerhalb des Zielprofils angenommen; ein Ordner, der beim Export
+    /// außerhalb des Profils lag, braucht ein vom Benutzer angegebenes Ziel.
+    pub(crate) fn new(
+        profile: &UserProfile,
+        selected: SelectedItems,
+        manifest: Option<&PackageManifest>,
+        custom_targets: &BTreeMap<String, String>,
+    ) -> Result<Self, String> {
+        let mut custom = Vec::new();
+        for category in manifest.map(|m| m.categories.as_slice()).unwrap_or_default() {
+            let Some(info) = &category.custom else {
+                continue;
+            };
+            if !selected.is_selected(&category.id) {
+                continue;
+            }
+            let confirmed = custom_targets
+                .get(&category.id)
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty());
+            let target = match confirmed {
+                // Absolute Ziele ersetzen beim Verbinden das Benutzerprofil
+                Some(target) => profile.base.join(target),
+                None => {
+                    let original = info.restore.as_deref().unwrap_or(&info.source);
+                    let rel = profile_relative(original).ok_or_else(|| {
+                        format!(
+                            "„{}“ liegt außerhalb des Benutzerprofils – bitte einen Zielordner angeben",
+                            info.label.as_deref().unwrap_or(original)
+                        )
+                    })?;
+                    profile.base.join(rel)
                 }
-                _ => false, // Unbekannte Ordner werden ignoriert
             };
-            
-            if should_import {
-                filtered_entries.push(entry);
+            custom.push((category.path.clone(), target));
+        }
+        Ok(RestorePlan {
+            user_base: profile.base.clone(),
+            redirected: profile.redirected.clone(),
+            selected,
+            custom,
+        })
+    }
+
+    /// Zielpfad für einen Archiveintrag, wenn er zu einem ausgewählten Inhalt gehört.
+    pub(crate) fn target(&self, archive_path: &str) -> Option<PathBuf> {
+        for (prefix, target) in &self.custom {
+            if let Some(rest) = archive_path.strip_prefix(prefix.as_str()) {
+                if rest.is_empty() || rest.starts_with('/') {
+                    return Some(target.join(sanitize_zip_path(rest)));
+                }
             }
         }
+        let rel = self.selected.restore_path(archive_path)?;
+        // Umgeleitete Ordner landen dort, wohin sie im Zielprofil zeigen
+        let redirected = known_folders::for_archive_path(archive_path).and_then(|folder| {
+            let dir = self.redirected.get(folder.id)?;
+            let rest = rel.strip_prefix(sanitize_zip_path(folder.restore)).ok()?;
+            Some(dir.join(rest))
+        });
+        Some(redirected.unwrap_or_else(|| self.user_base.join(rel)))
     }
-    
-    let count = filtered_entries.len() as u64;
-    println!("Gefilterte Einträge: {}", count);
-    for entry in &filtered_entries {
-        println!("  -> {}", entry.file_name().to_string_lossy());
+}
+
+#[derive(Default)]
+pub(crate) struct RestoreStats {
+    /// Vollständig verarbeitete Einträge des Pakets, auch nicht ausgewählte
+    entries: u64,
+    pub(crate) restored: u64,
+    /// Dateien, deren Prüfsumme nicht zum Manifest passte und die deshalb nicht ersetzt wurden
+    corrupt: u64,
+    /// Dateien, die am Zielort nicht geschrieben werden konnten
+    pub(crate) failed: u64,
+    /// Dateien, die am Zielort mit anderem Inhalt schon vorhanden waren
+    pub(crate) conflicts: u64,
+}
+
+impl RestoreStats {
+    fn from_state(state: &ImportState) -> Self {
+        RestoreStats {
+            entries: state.entries,
+            restored: state.restored,
+            corrupt: state.corrupt,
+            failed: state.failed,
+            conflicts: state.conflicts,
+        }
     }
-    
-    if count == 0 {
-        return Err("Keine Inhalte zum Importieren ausgewählt".to_string());
+
+    fn to_state(&self, package: &Path, user_base: &Path, report: &TransferReport) -> ImportState {
+        ImportState {
+            package: package.display().to_string(),
+            user_base: user_base.to_path_buf(),
+            entries: self.entries,
+            restored: self.restored,
+            corrupt: self.corrupt,
+            failed: self.failed,
+            conflicts: self.conflicts,
+            report: report.clone(),
+            saved_at: report::now_secs(),
+        }
     }
-    
-    let mut done = 0u64;
-    for entry in filtered_entries {
-        let src = entry.path();
-        let dst = user_base.join(entry.file_name());
-        
-        // Spezielle Behandlung für AppData-Ordner
-        let name = entry.file_name().to_string_lossy().to_lowercase();
-        if name.starts_with("appdata") {
-            // Selektives Kopieren der Browser-Daten
-            copy_appdata_selective(&src, &dst, &selected_items)?;
+
+    /// Zählt das Ergebnis einer Datei und trägt es in den Bericht ein.
+    pub(crate) fn record(&mut self, report: &mut TransferReport, path: &str, outcome: FileOutcome) {
+        match outcome {
+            FileOutcome::Restored(bytes) => {
+                self.restored += 1;
+                report.copied(path, bytes);
+            }
+            FileOutcome::Incomplete => {
+                report.failed(path, "Beim Export nicht vollständig gelesen");
+            }
+            FileOutcome::HashMismatch => {
+                eprintln!("⚠️ Prüfsumme stimmt nicht: {}", path);
+                self.corrupt += 1;
+                report.failed(path, "Prüfsumme stimmt nicht");
+            }
+            FileOutcome::Replaced { bytes, resolution } => {
+                self.restored += 1;
+                self.conflicts += 1;
+                report.copied(path, bytes);
+                report.conflict(path, resolution);
+            }
+            FileOutcome::Kept { reason, conflict } => {
+                if conflict {
+                    self.conflicts += 1;
+                    report.conflict(path, &reason);
+                }
+                report.skipped(path, reason);
+            }
+            FileOutcome::Skipped(reason) => report.skipped(path, reason),
+            FileOutcome::Failed(e) => {
+                self.failed += 1;
+                report.failed(path, e);
+            }
+        }
+    }
+}
+
+/// Stellt die ausgewählten Einträge eines Streaming-Archivs wieder her.
+/// Nicht ausgewählte Dateien werden im Stream übersprungen und nie geschrieben.
+/// Mit `verify_hashes` wird jede Datei erst nach erfolgreicher Prüfsummenkontrolle
+/// an ihren Zielort verschoben. Die ersten `stats.entries` Einträge gelten als
+/// schon verarbeitet (fortgesetzter Import).
+#[allow(clippy::too_many_arguments)]
+fn restore_archive_stream<R: Read>(
+    reader: R,
+    plan: &RestorePlan,
+    conflicts: &ConflictResolver,
+    journal: &mut ImportJournal,
+    job: &JobHandle,
+    verify_hashes: bool,
+    stats: &mut RestoreStats,
+    report: &mut TransferReport,
+    mut progress: impl FnMut(&RestoreStats, &TransferReport),
+) -> Result<(), String> {
+    let mut archive = ArchiveReader::new(reader).map_err(|e| e.to_string())?;
+    let mut seen = 0u64;
+    while let Some(entry) = archive.next_entry().map_err(|e| e.to_string())? {
+        seen += 1;
+        if seen <= stats.entries {
+            continue;
+        }
+        job.checkpoint()?;
+        let Some(target) = plan.target(entry.path()) else {
+            stats.entries = seen;
+            continue;
+        };
+        match entry {
+            ArchiveEntry::Directory(_) => {
+                let _ = journal.create_dir_all(&target);
+                stats.entries = seen;
+            }
+            ArchiveEntry::File(ref header) => {
+                let outcome = restore_file(&target, header.modified, conflicts, journal, |out| {
+                    let data = archive.read_data(out)?;
+                    Ok(if !data.complete {
+                        FileOutcome::Incomplete
+                    } else if verify_hashes && !data.hash_ok() {
+                        FileOutcome::HashMismatch
+                    } else {
+                        FileOutcome::Restored(data.size)
+                    })
+                })?;
+                stats.record(report, entry.path(), outcome);
+                stats.entries = seen;
+                progress(stats, report);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Stellt die ausgewählten Einträge aus dem ZIP-Inhalt älterer Pakete wieder her.
+/// Die lokalen Dateiköpfe enthalten die Größen, daher reicht sequentielles Lesen.
+#[allow(clippy::too_many_arguments)]
+fn restore_zip_stream<R: Read>(
+    reader: &mut R,
+    plan: &RestorePlan,
+    conflicts: &ConflictResolver,
+    journal: &mut ImportJournal,
+    job: &JobHandle,
+    stats: &mut RestoreStats,
+    report: &mut TransferReport,
+    mut progress: impl FnMut(&RestoreStats, &TransferReport),
+) -> Result<(), String> {
+    // Die CRC32 der Einträge prüft das zip-Crate beim Lesen selbst
+    let mut seen = 0u64;
+    loop {
+        job.checkpoint()?;
+        let mut file = match read_zipfile_from_stream(reader) {
+            Ok(Some(file)) => file,
+            Ok(None) => break,
+            Err(e) => return Err(format!("ZIP fehlerhaft: {}", e)),
+        };
+        seen += 1;
+        if seen <= stats.entries {
+            continue;
+        }
+        let name = file.name().trim_end_matches('/').to_string();
+        let Some(target) = plan.target(&name) else {
+            stats.entries = seen;
+            continue;
+        };
+        if file.is_dir() {
+            let _ = journal.create_dir_all(&target);
+            stats.entries = seen;
         } else {
-            copy_directory_merge(&src, &dst)?;
+            let outcome = restore_file(&target, None, conflicts, journal, |out| {
+                io::copy(&mut file, out).map(FileOutcome::Restored)
+            })?;
+            stats.record(report, &name, outcome);
+            stats.entries = seen;
+            progress(stats, report);
         }
-        
-        done += 1;
-        emit_progress(
-            &app,
-            start,
-            60 + ((done as f32 / count.max(1) as f32) * 40.0) as u64,
-            100,
-            "Wiederherstellen",
-        );
     }
+    Ok(())
+}
+
+/// Ergebnis beim Wiederherstellen einer einzelnen Datei.
+pub(crate) enum FileOutcome {
+    /// Geschrieben (Bytes).
+    Restored(u64),
+    /// Geschrieben, obwohl am Zielort schon eine andere Datei lag.
+    Replaced { bytes: u64, resolution: String },
+    /// Nicht geschrieben, die vorhandene Datei bleibt (siehe `ConflictPolicy`).
+    Kept { reason: String, conflict: bool },
+    /// Beim Export nicht vollständig lesbar – vorhandene Datei bleibt.
+    Incomplete,
+    /// Prüfsumme falsch – vorhandene Datei bleibt.
+    HashMismatch,
+    Skipped(String),
+    /// Fehler am Zielort (Anlegen, Schreiben, Umbenennen).
+    Failed(io::Error),
+}
+
+/// Schreibt eine Datei an ihren Zielort. Erst wenn der Inhalt vollständig geschrieben
+/// wurde, entscheidet `conflicts`, was mit einer vorhandenen Datei geschieht. `write`
+/// liefert `Restored`, wenn der Inhalt übernommen werden soll. Fehler am Zielort werden
+/// als `Failed` gemeldet, nur Lesefehler des Pakets brechen ab.
+pub(crate) fn restore_file<F>(
+    target: &Path,
+    modified: Option<u64>,
+    conflicts: &ConflictResolver,
+    journal: &mut ImportJournal,
+    write: F,
+) -> Result<FileOutcome, String>
+where
+    F: FnOnce(&mut io::BufWriter<LatchedWriter<fs::File>>) -> io::Result<FileOutcome>,
+{
+    if is_symlink(target) {
+        return Ok(FileOutcome::Skipped(
+            "Ziel ist eine symbolische Verknüpfung".to_string(),
+        ));
+    }
+    if let Some(parent) = target.parent() {
+        let _ = journal.create_dir_all(parent);
+    }
+    let mut part_name = target.file_name().unwrap_or_default().to_os_string();
+    part_name.push(".niloshift-part");
+    let part = target.with_file_name(part_name);
+    // Ohne Datei wird der Eintrag im Stream übersprungen
+    let file = match fs::File::create(&part) {
+        Ok(f) => f,
+        Err(e) => return Ok(FileOutcome::Failed(e)),
+    };
+    let mut out = io::BufWriter::new(LatchedWriter::new(file));
+    let outcome = match write(&mut out) {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            drop(out);
+            let _ = fs::remove_file(&part);
+            return Err(format!("{}: {}", target.display(), e));
+        }
+    };
+    let _ = out.flush();
+    let write_error = out.get_mut().error.take();
+    // Änderungszeit aus dem Paket übernehmen, sonst wäre jede importierte Datei "neu"
+    if let (None, Some(secs)) = (&write_error, modified) {
+        let time = UNIX_EPOCH + Duration::from_secs(secs);
+        let _ = out.get_ref().inner.set_modified(time);
+    }
+    drop(out);
+    let outcome = match (write_error, outcome) {
+        (Some(e), _) => FileOutcome::Failed(e),
+        (None, FileOutcome::Restored(bytes)) => {
+            match conflicts.resolve(&part, modified, target, journal) {
+                Ok(Resolution::Write { target, conflict }) => match journal.place(&part, &target) {
+                    Ok(()) => {
+                        return Ok(match conflict {
+                            Some(resolution) => FileOutcome::Replaced { bytes, resolution },
+                            None => FileOutcome::Restored(bytes),
+                        })
+                    }
+                    Err(e) => FileOutcome::Failed(e),
+                },
+                Ok(Resolution::Keep { reason, conflict }) => FileOutcome::Kept { reason, conflict },
+                Err(e) => FileOutcome::Failed(e),
+            }
+        }
+        (None, outcome) => outcome,
+    };
+    let _ = fs::remove_file(&part);
+    Ok(outcome)
+}
+
+/// Merkt sich den ersten Schreibfehler, statt ihn weiterzugeben. So wird der Eintrag
+/// im Paket trotzdem vollständig gelesen und der Stream bleibt lesbar.
+pub(crate) struct LatchedWriter<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W> LatchedWriter<W> {
+    fn new(inner: W) -> Self {
+        LatchedWriter { inner, error: None }
+    }
+}
+
+impl<W: Write> Write for LatchedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.error.is_none() {
+            if let Err(e) = self.inner.write_all(buf) {
+                self.error = Some(e);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.error.is_none() {
+            if let Err(e) = self.inner.flush() {
+                self.error = Some(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Zählt die gelesenen Bytes der Paketdatei mit, für den Fortschritt beim Import.
+pub(crate) struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+
+impl<R> CountingReader<R> {
+    pub(crate) fn new(inner: R) -> (Self, Arc<AtomicU64>) {
+        let count = Arc::new(AtomicU64::new(0));
+        (
+            CountingReader {
+                inner,
+                count: Arc::clone(&count),
+            },
+            count,
+        )
+    }
+}
 
-    let _ = fs::remove_dir_all(&temp_dir);
-    emit_progress(&app, start, 100, 100, "Fertig");
-    Ok("Import abgeschlossen".to_string())
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
 }
 
-// (keine separate extract_zip-Funktion nötig)
+/// Zum Profil relativer Pfad aus dem Manifest; `None` bei absoluten Pfaden,
+/// Laufwerksangaben und `..`. Beide Trennzeichen gelten unabhängig vom System.
+fn profile_relative(path: &str) -> Option<PathBuf> {
+    let path = path.trim();
+    if path.starts_with(['/', '\\']) || path.get(1..2) == Some(":") {
+        return None;
+    }
+    let mut rel = PathBuf::new();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part => rel.push(part),
+        }
+    }
+    (!rel.as_os_str().is_empty()).then_some(rel)
+}
 
 fn sanitize_zip_path(name: &str) -> PathBuf {
     let mut pb = PathBuf::new();
@@ -408,162 +837,309 @@ fn is_symlink(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
-fn should_import_appdata(appdata_path: &Path, selected_items: &SelectedItems) -> bool {
-    // Prüfe, ob dieser AppData-Ordner Browser-Daten oder Outlook-Signaturen enthält, die importiert werden sollen
-    if !selected_items.chrome && !selected_items.edge && !selected_items.firefox && !selected_items.outlook_signatures {
-        return false;
-    }
-    
-    println!("Prüfe AppData-Ordner: {}", appdata_path.display());
-    
-    // Prüfe die Unterordner - verschiedene Pfade je nach Export-Struktur
-    let check_dirs = vec![
-        // Browser-Pfade
-        ("Local/Google/Chrome", selected_items.chrome),
-        ("Local/Microsoft/Edge", selected_items.edge),
-        ("Roaming/Mozilla/Firefox", selected_items.firefox),
-        // Outlook-Signaturen Pfade
-        ("Roaming/Microsoft/Signatures", selected_items.outlook_signatures),
-        // Alternative Pfade falls direkt exportiert
-        ("Google/Chrome", selected_items.chrome),
-        ("Microsoft/Edge", selected_items.edge),
-        ("Mozilla/Firefox", selected_items.firefox),
-        ("Microsoft/Signatures", selected_items.outlook_signatures),
-    ];
-    
-    for (app_path, should_import) in check_dirs {
-        if should_import {
-            let app_full_path = appdata_path.join(app_path);
-            println!("  Prüfe Pfad: {} -> Existiert: {}", app_full_path.display(), app_full_path.exists());
-            if app_full_path.exists() {
-                return true;
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::{ArchiveWriter, EntryHeader};
+    use crate::package_format::{CategorySummary, CustomPath};
+    use crate::progress::OutputFormat;
+    use crate::recipients::Recipient;
+    use crate::volumes::VolumeWriter;
+
+    fn profile() -> UserProfile {
+        UserProfile {
+            name: "max".to_string(),
+            base: PathBuf::from("/home/max"),
+            redirected: BTreeMap::new(),
+            offline: false,
         }
     }
-    
-    // Zusätzliche Prüfung: Schaue direkt nach Microsoft-Ordner wenn Outlook ausgewählt
-    if selected_items.outlook_signatures {
-        let microsoft_path = appdata_path.join("Microsoft");
-        let signatures_path = microsoft_path.join("Signatures");
-        println!("  Prüfe direkten Microsoft-Pfad: {} -> Existiert: {}", signatures_path.display(), signatures_path.exists());
-        if signatures_path.exists() {
-            return true;
-        }
-        
-        // Prüfe auch Roaming-Struktur
-        let roaming_signatures = appdata_path.join("Roaming").join("Microsoft").join("Signatures");
-        println!("  Prüfe Roaming-Signaturen-Pfad: {} -> Existiert: {}", roaming_signatures.display(), roaming_signatures.exists());
-        if roaming_signatures.exists() {
-            return true;
+
+    fn manifest(source: &str, restore: Option<&str>) -> PackageManifest {
+        PackageManifest {
+            niloshift_version: "2".to_string(),
+            source_user: "max".to_string(),
+            machine_name: "PC".to_string(),
+            exported_at: 0,
+            categories: vec![CategorySummary {
+                id: "custom-1".to_string(),
+                path: "NiloShift-Custom/1".to_string(),
+                files: 1,
+                bytes: 1,
+                custom: Some(CustomPath {
+                    source: source.to_string(),
+                    label: None,
+                    restore: restore.map(str::to_string),
+                }),
+                rules: None,
+            }],
         }
     }
-    
-    false
-}
 
-fn copy_appdata_selective(src: &Path, dst: &Path, selected_items: &SelectedItems) -> Result<(), String> {
-    // Erstelle das Zielverzeichnis falls es nicht existiert
-    if !dst.exists() {
-        fs::create_dir_all(dst).map_err(|e| e.to_string())?;
+    fn plan(manifest: &PackageManifest, targets: &[(&str, &str)]) -> Result<RestorePlan, String> {
+        let targets = targets
+            .iter()
+            .map(|(id, path)| (id.to_string(), path.to_string()))
+            .collect();
+        RestorePlan::new(
+            &profile(),
+            SelectedItems::from_ids(["custom-1"]),
+            Some(manifest),
+            &targets,
+        )
     }
-    
-    // Liste der Browser/App-Pfade und ob sie importiert werden sollen
-    let app_configs = vec![
-        ("Google", selected_items.chrome),
-        ("Microsoft", selected_items.edge || selected_items.outlook_signatures), 
-        ("Mozilla", selected_items.firefox),
-    ];
-    
-    // Durchlaufe alle Einträge im AppData-Ordner
-    let entries = fs::read_dir(src).map_err(|e| e.to_string())?;
-    for entry in entries {
-        let entry = entry.map_err(|e| e.to_string())?;
-        let name = entry.file_name().to_string_lossy().to_string();
-        
-        // Prüfe, ob dieser Ordner importiert werden soll
-        let should_copy = app_configs.iter().any(|(app_name, should_import)| {
-            *should_import && name.starts_with(app_name)
-        }) || (selected_items.outlook_signatures && name == "Microsoft") ||
-             (selected_items.outlook_signatures && name == "Roaming" && 
-              src.join(&name).join("Microsoft").join("Signatures").exists());
-        
-        if should_copy {
-            let src_path = entry.path();
-            let dst_path = dst.join(entry.file_name());
-            
-            // Spezielle Behandlung für Microsoft/Outlook-Signaturen
-            if selected_items.outlook_signatures && (name == "Microsoft" || name == "Roaming") {
-                copy_appdata_selective_recursive(&src_path, &dst_path, selected_items)?;
-            } else {
-                copy_directory_merge(&src_path, &dst_path)?;
-            }
+
+    #[test]
+    fn relative_manifest_path_stays_in_profile() {
+        let plan = plan(&manifest("Projekte\\Web", None), &[]).unwrap();
+        assert_eq!(
+            plan.target("NiloShift-Custom/1/a.txt"),
+            Some(PathBuf::from("/home/max/Projekte/Web/a.txt"))
+        );
+    }
+
+    #[test]
+    fn manifest_paths_outside_profile_are_rejected() {
+        for source in [
+            "D:\\Daten",
+            "C:Daten",
+            "/etc",
+            "\\\\server\\share",
+            "../andere",
+            "Daten/../../etc",
+        ] {
+            assert!(plan(&manifest(source, None), &[]).is_err(), "{}", source);
         }
+        // Auch ein abweichendes Ziel im Manifest gilt nur innerhalb des Profils
+        assert!(plan(&manifest("Daten", Some("/etc")), &[]).is_err());
     }
-    
-    Ok(())
-}
 
-fn copy_appdata_selective_recursive(src: &Path, dst: &Path, selected_items: &SelectedItems) -> Result<(), String> {
-    if !src.exists() {
-        return Ok(());
+    #[test]
+    fn confirmed_target_replaces_manifest_path() {
+        let plan = plan(&manifest("D:\\Daten", None), &[("custom-1", "/mnt/daten")]).unwrap();
+        assert_eq!(
+            plan.target("NiloShift-Custom/1/sub/a.txt"),
+            Some(PathBuf::from("/mnt/daten/sub/a.txt"))
+        );
+        // Pfade im Archiv können das Ziel nicht verlassen
+        assert_eq!(
+            plan.target("NiloShift-Custom/1/../../etc/passwd"),
+            Some(PathBuf::from("/mnt/daten/etc/passwd"))
+        );
     }
-    
-    // Erstelle Zielverzeichnis
-    if !dst.exists() {
-        fs::create_dir_all(dst).map_err(|e| e.to_string())?;
+
+    #[test]
+    fn unselected_folder_needs_no_target() {
+        let plan = RestorePlan::new(
+            &profile(),
+            SelectedItems::from_ids(["desktop"]),
+            Some(&manifest("D:\\Daten", None)),
+            &BTreeMap::new(),
+        )
+        .unwrap();
+        assert_eq!(plan.target("NiloShift-Custom/1/a.txt"), None);
     }
-    
-    // Prüfe ob wir in einem Microsoft-Ordner sind und nur Signatures kopieren sollen
-    let src_name = src.file_name().unwrap_or_default().to_string_lossy();
-    if src_name == "Microsoft" && selected_items.outlook_signatures {
-        // Kopiere nur den Signatures-Ordner
-        let signatures_src = src.join("Signatures");
-        if signatures_src.exists() {
-            let signatures_dst = dst.join("Signatures");
-            println!("Kopiere Outlook-Signaturen: {} -> {}", signatures_src.display(), signatures_dst.display());
-            copy_directory_merge(&signatures_src, &signatures_dst)?;
-        }
-        return Ok(());
+
+    fn detect(package: &Path) -> Result<DetectedContents, String> {
+        tauri::async_runtime::block_on(detect_package_contents(
+            package.display().to_string(),
+            "pw".to_string(),
+            None,
+        ))
     }
-    
-    // Für Roaming-Ordner: Rekursiv nach Microsoft/Signatures suchen
-    if src_name == "Roaming" && selected_items.outlook_signatures {
-        let microsoft_src = src.join("Microsoft");
-        if microsoft_src.exists() {
-            let microsoft_dst = dst.join("Microsoft");
-            copy_appdata_selective_recursive(&microsoft_src, &microsoft_dst, selected_items)?;
+
+    /// Altes Paket: Nonce und AES-GCM-verschlüsselter Inhalt in einem Stück.
+    fn write_legacy_package(path: &Path, content: &[u8]) {
+        use aes_gcm::aead::{Aead, KeyInit};
+        use aes_gcm::{Aes256Gcm, Nonce};
+        let nonce = [7u8; crate::crypto::NONCE_LEN];
+        let cipher = Aes256Gcm::new_from_slice(&crate::crypto::legacy_key("pw")).unwrap();
+        let mut data = nonce.to_vec();
+        data.extend(cipher.encrypt(Nonce::from_slice(&nonce), content).unwrap());
+        fs::write(path, data).unwrap();
+    }
+
+    #[test]
+    fn detection_uses_manifest_categories() {
+        let root =
+            std::env::temp_dir().join(format!("nilo-detect-manifest-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let package = root.join("alice.nilo");
+        let mut manifest = manifest("Daten", None);
+        manifest.categories.push(CategorySummary {
+            id: "music".to_string(),
+            path: "Music".to_string(),
+            files: 0,
+            bytes: 0,
+            custom: None,
+            rules: None,
+        });
+        let out = VolumeWriter::create(&package, None).unwrap();
+        let recipients = [Recipient::Password("pw".to_string())];
+        let encryptor =
+            package_format::create_package_writer(out, &recipients, &manifest, None).unwrap();
+        encryptor.finish().unwrap().finish().unwrap();
+
+        let detected = detect(&package).unwrap();
+        assert_eq!(detected.found.get("custom-1"), Some(&true));
+        // Leere Kategorien gelten nicht als gefunden
+        assert_eq!(detected.found.get("music"), Some(&false));
+        assert_eq!(detected.found.get("desktop"), Some(&false));
+        assert!(detected.manifest.is_some());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn legacy_detection_reads_every_entry() {
+        let root =
+            std::env::temp_dir().join(format!("nilo-detect-legacy-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        // Die Musikdatei steht weit hinten im ZIP
+        let mut zip = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+        for i in 0..60 {
+            zip.start_file(format!("Desktop/{}.txt", i), Default::default())
+                .unwrap();
+            zip.write_all(b"x").unwrap();
         }
-        return Ok(());
+        zip.start_file("Music/m.mp3", Default::default()).unwrap();
+        zip.write_all(b"m").unwrap();
+        let zip = zip.finish().unwrap().into_inner();
+        let package = root.join("alt.nilo");
+        write_legacy_package(&package, &zip);
+
+        let detected = detect(&package).unwrap();
+        assert_eq!(detected.found.get("desktop"), Some(&true));
+        assert_eq!(detected.found.get("music"), Some(&true));
+        assert_eq!(detected.found.get("documents"), Some(&false));
+        assert!(detected.manifest.is_none());
+
+        // Ohne Manifest und ohne ZIP ist der Inhalt unbekannt, statt alles anzubieten
+        write_legacy_package(&package, b"kein ZIP");
+        assert!(detect(&package).is_err());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    /// Liest bis `limit` Bytes und meldet dann einen Lesefehler (Datenträger entfernt).
+    struct FailingReader {
+        inner: Box<dyn Read + Send>,
+        limit: u64,
     }
-    
-    // Fallback: Normale Kopie
-    copy_directory_merge(src, dst)
-}
 
-fn copy_directory_merge(src: &Path, dst: &Path) -> Result<(), String> {
-    if is_symlink(src) {
-        return Ok(());
+    impl Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.limit == 0 {
+                return Err(io::Error::other("Datenträger entfernt"));
+            }
+            let max = buf.len().min(self.limit as usize);
+            let n = self.inner.read(&mut buf[..max])?;
+            self.limit -= n as u64;
+            Ok(n)
+        }
     }
-    if src.is_dir() {
-        let _ = fs::create_dir_all(dst);
-        let entries = match fs::read_dir(src) {
-            Ok(it) => it,
-            Err(_) => return Ok(()),
+
+    fn import(
+        package: &Path,
+        fail_after: Option<u64>,
+        resume: bool,
+    ) -> Result<TransferSummary, String> {
+        let volumes = VolumeSet::discover(package).unwrap();
+        let (file, bytes_read) = CountingReader::new(volumes.open().unwrap());
+        let mut opened =
+            package_format::open_package_reader(file, &Credentials::from_password("pw")).unwrap();
+        if let Some(limit) = fail_after {
+            opened.content = Box::new(FailingReader { inner: opened.content, limit });
+        }
+        let target = ImportTarget {
+            selected_user: "bob".to_string(),
+            selected_items: SelectedItems::from_ids(["documents"]),
+            verify_hashes: true,
+            custom_targets: BTreeMap::new(),
+            conflict_policy: ConflictPolicy::default(),
         };
-        for e in entries {
-            let e = match e {
-                Ok(x) => x,
-                Err(_) => continue,
+        restore_package(
+            &ProgressSink::console(OutputFormat::Json),
+            &JobHandle::default(),
+            Instant::now(),
+            opened,
+            package,
+            bytes_read,
+            volumes.total_len(),
+            target,
+            resume,
+        )
+    }
+
+    #[test]
+    fn interrupted_import_resumes_and_continues_journal() {
+        let _lock = profiles::PROFILE_ROOT_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let root = std::env::temp_dir().join(format!("nilo-import-resume-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let bob = root.join("Users/bob");
+        fs::create_dir_all(&bob).unwrap();
+        let users = root.join("Users").display().to_string();
+        tauri::async_runtime::block_on(profiles::set_profile_root(Some(users))).unwrap();
+
+        // a.txt, b.bin (über mehrere Archivblöcke), Unterordner mit c.txt
+        let big: Vec<u8> = (0..3_000_000u32).map(|i| (i % 251) as u8).collect();
+        let package = root.join("alice.nilo");
+        let manifest = manifest("Daten", None);
+        let out = VolumeWriter::create(&package, None).unwrap();
+        let recipients = [Recipient::Password("pw".to_string())];
+        let encryptor =
+            package_format::create_package_writer(out, &recipients, &manifest, None).unwrap();
+        let mut archive = ArchiveWriter::new(encryptor).unwrap();
+        archive.add_directory("Documents").unwrap();
+        let files = [
+            ("Documents/a.txt", &b"erste Datei"[..]),
+            ("Documents/b.bin", &big[..]),
+            ("Documents/Neu/c.txt", b"dritte Datei"),
+        ];
+        for (path, data) in files {
+            let header = EntryHeader {
+                path: path.to_string(),
+                size: data.len() as u64,
+                modified: None,
+                compression: None,
             };
-            let s = e.path();
-            let d = dst.join(e.file_name());
-            copy_directory_merge(&s, &d)?;
+            archive.add_file(&header, &mut &data[..]).unwrap();
         }
-    } else {
-        if let Some(parent) = dst.parent() {
-            let _ = fs::create_dir_all(parent);
-        }
-        let _ = fs::copy(src, dst);
+        archive.finish().unwrap().finish().unwrap().finish().unwrap();
+
+        // Abbruch mitten in b.bin: a.txt ist übernommen, der Zwischenstand liegt beim Protokoll
+        let err = import(&package, Some(1_500_000), false).unwrap_err();
+        assert!(err.contains("kann fortgesetzt"), "{}", err);
+        assert_eq!(fs::read(bob.join("Documents/a.txt")).unwrap(), b"erste Datei");
+        assert!(!bob.join("Documents/b.bin").exists());
+        assert!(!bob.join("Documents/b.bin.niloshift-part").exists());
+        let id = resume::find_import(&bob, &package).unwrap();
+        let state: ImportState =
+            resume::load(&resume::import_state_path(&journal::journal_dir(&id).unwrap())).unwrap();
+        // Ordner Documents und a.txt
+        assert_eq!(state.entries, 2);
+        assert_eq!(state.restored, 1);
+
+        // Verarbeitete Einträge werden übersprungen: a.txt entsteht nicht neu
+        fs::remove_file(bob.join("Documents/a.txt")).unwrap();
+        let summary = import(&package, None, true).unwrap();
+        assert_eq!(summary.journal_id.as_deref(), Some(id.as_str()));
+        assert_eq!(summary.copied, 3);
+        assert!(!bob.join("Documents/a.txt").exists());
+        assert_eq!(fs::read(bob.join("Documents/b.bin")).unwrap(), big);
+        assert_eq!(fs::read(bob.join("Documents/Neu/c.txt")).unwrap(), b"dritte Datei");
+        assert_eq!(resume::find_import(&bob, &package), None);
+
+        // Ein Protokoll für beide Läufe: Zurücksetzen entfernt alles Importierte
+        let rollback = journal::rollback(&id).unwrap();
+        assert!(rollback.failed.is_empty(), "{:?}", rollback.failed);
+        assert!(!bob.join("Documents/b.bin").exists());
+        assert!(!bob.join("Documents/Neu").exists());
+
+        tauri::async_runtime::block_on(profiles::set_profile_root(None)).unwrap();
+        let _ = fs::remove_dir_all(&root);
     }
-    Ok(())
-}
\ No newline at end of file
+}
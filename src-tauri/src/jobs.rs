@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Condvar, Mutex};
+
+// Laufende Exporte und Importe, damit die Oberfläche sie pausieren oder abbrechen kann.
+// Die Schleifen rufen zwischen zwei Dateien `JobHandle::checkpoint` auf: bei Pause
+// wird dort gewartet, bei Abbruch kehrt der Aufruf mit einem Fehler zurück und der
+// Vorgang räumt seine halbfertige Ausgabe selbst auf.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    Paused,
+    Cancelled,
+}
+
+#[derive(Clone)]
+pub struct JobHandle {
+    inner: Arc<(Mutex<JobState>, Condvar)>,
+}
+
+impl Default for JobHandle {
+    fn default() -> Self {
+        JobHandle {
+            inner: Arc::new((Mutex::new(JobState::Running), Condvar::new())),
+        }
+    }
+}
+
+impl JobHandle {
+    fn state(&self) -> JobState {
+        *self.inner.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Setzt den Zustand; ein abgebrochener Vorgang bleibt abgebrochen.
+    pub fn set(&self, state: JobState) {
+        let (lock, changed) = &*self.inner;
+        let mut current = lock.lock().unwrap_or_else(|e| e.into_inner());
+        if *current != JobState::Cancelled {
+            *current = state;
+        }
+        changed.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state() == JobState::Cancelled
+    }
+
+    /// Zwischen zwei Dateien aufrufen: wartet, solange pausiert ist, und meldet
+    /// einen Abbruch als Fehler.
+    pub fn checkpoint(&self) -> Result<(), String> {
+        let (lock, changed) = &*self.inner;
+        let mut state = lock.lock().unwrap_or_else(|e| e.into_inner());
+        while *state == JobState::Paused {
+            state = changed.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+        if *state == JobState::Cancelled {
+            return Err("Vom Benutzer abgebrochen".to_string());
+        }
+        Ok(())
+    }
+}
+
+static JOBS: Mutex<BTreeMap<String, JobHandle>> = Mutex::new(BTreeMap::new());
+
+/// Eingetragener Vorgang; wird beim Drop wieder ausgetragen.
+pub struct RegisteredJob {
+    id: String,
+    handle: JobHandle,
+}
+
+impl RegisteredJob {
+    pub fn handle(&self) -> &JobHandle {
+        &self.handle
+    }
+}
+
+impl Drop for RegisteredJob {
+    fn drop(&mut self) {
+        jobs().remove(&self.id);
+    }
+}
+
+fn jobs() -> std::sync::MutexGuard<'static, BTreeMap<String, JobHandle>> {
+    JOBS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Trägt einen Vorgang unter `id` ein, z. B. "export" oder "import".
+pub fn register(id: &str) -> Result<RegisteredJob, String> {
+    let mut jobs = jobs();
+    if jobs.contains_key(id) {
+        return Err(format!("Vorgang {} läuft bereits", id));
+    }
+    let handle = JobHandle::default();
+    jobs.insert(id.to_string(), handle.clone());
+    Ok(RegisteredJob {
+        id: id.to_string(),
+        handle,
+    })
+}
+
+fn set_state(id: &str, state: JobState) -> Result<(), String> {
+    match jobs().get(id) {
+        Some(handle) => {
+            handle.set(state);
+            Ok(())
+        }
+        None => Err(format!("Kein laufender Vorgang {}", id)),
+    }
+}
+
+#[tauri::command]
+pub async fn pause_job(job_id: String) -> Result<(), String> {
+    set_state(&job_id, JobState::Paused)
+}
+
+#[tauri::command]
+pub async fn resume_job(job_id: String) -> Result<(), String> {
+    set_state(&job_id, JobState::Running)
+}
+
+/// Bricht einen Vorgang ab; auch ein pausierter Vorgang wird dabei beendet.
+#[tauri::command]
+pub async fn cancel_job(job_id: String) -> Result<(), String> {
+    set_state(&job_id, JobState::Cancelled)
+}
@@ -0,0 +1,523 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::report;
+use crate::resume;
+
+// Protokoll eines Imports, um ihn rückgängig machen zu können. Jede Änderung am
+// Zielprofil wird vor der Ausführung als eigene Zeile angehängt (write-ahead), damit
+// auch ein abgebrochener Import vollständig zurückgerollt werden kann. Ersetzte
+// Dateien werden vorher in den Sicherungsordner des Protokolls verschoben.
+//
+// Ablage: `%LOCALAPPDATA%\NiloShift\journals\<id>\journal.jsonl` und `backup\<n>`.
+// Die erste Zeile ist der Kopf (`JournalHeader`), danach folgt je Änderung ein `JournalEntry`.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalHeader {
+    pub id: String,
+    pub user_base: PathBuf,
+    pub package: String,
+    pub started_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum JournalEntry {
+    /// Ordner, den der Import neu angelegt hat.
+    CreatedDir { path: PathBuf },
+    /// Datei, die vorher nicht existierte.
+    Created { path: PathBuf },
+    /// Vorhandene Datei, die vorher nach `backup` verschoben wurde.
+    Replaced { path: PathBuf, backup: PathBuf },
+    /// Vorhandene Datei, die der Import verschoben hat (Konfliktregel "Sichern").
+    Moved { from: PathBuf, to: PathBuf },
+}
+
+pub struct ImportJournal {
+    pub id: String,
+    dir: PathBuf,
+    file: io::BufWriter<fs::File>,
+    backups: u64,
+}
+
+/// Ordner aller Protokolle.
+fn journals_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("NiloShift")
+        .join("journals")
+}
+
+pub fn journal_dir(id: &str) -> Result<PathBuf, String> {
+    // Die Kennung kommt aus der Oberfläche – keine Pfadbestandteile zulassen
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Ungültige Protokoll-Kennung: {}", id));
+    }
+    Ok(journals_dir().join(id))
+}
+
+impl ImportJournal {
+    /// Legt ein neues Protokoll für einen Import in `user_base` an.
+    pub fn create(user_base: &Path, package: &Path) -> Result<Self, String> {
+        let started_at = report::now_secs();
+        let root = journals_dir();
+        fs::create_dir_all(&root)
+            .map_err(|e| format!("Importprotokoll konnte nicht angelegt werden: {}", e))?;
+        // Mehrere Importe in derselben Sekunde bekommen ein Suffix
+        let (id, dir) = (1..)
+            .map(|n| match n {
+                1 => format!("import-{}", started_at),
+                n => format!("import-{}-{}", started_at, n),
+            })
+            .map(|id| {
+                let dir = root.join(&id);
+                (id, dir)
+            })
+            .find(|(_, dir)| fs::create_dir(dir).is_ok())
+            .expect("unbegrenzte Folge");
+        let file = fs::File::create(dir.join("journal.jsonl"))
+            .map_err(|e| format!("Importprotokoll konnte nicht angelegt werden: {}", e))?;
+        let mut journal = ImportJournal {
+            id: id.clone(),
+            dir,
+            file: io::BufWriter::new(file),
+            backups: 0,
+        };
+        let header = JournalHeader {
+            id,
+            user_base: user_base.to_path_buf(),
+            package: package.display().to_string(),
+            started_at,
+        };
+        journal
+            .append(&header)
+            .map_err(|e| format!("Importprotokoll konnte nicht geschrieben werden: {}", e))?;
+        Ok(journal)
+    }
+
+    /// Öffnet ein vorhandenes Protokoll, um einen unterbrochenen Import fortzusetzen.
+    pub fn open(id: &str) -> Result<Self, String> {
+        let (_, entries) = read_journal(id)?;
+        let dir = journal_dir(id)?;
+        let file = fs::OpenOptions::new()
+            .append(true)
+            .open(dir.join("journal.jsonl"))
+            .map_err(|e| format!("Importprotokoll {} nicht gefunden: {}", id, e))?;
+        // Sicherungen fortlaufend weiter nummerieren
+        let backups = entries
+            .iter()
+            .filter(|e| matches!(e, JournalEntry::Replaced { .. }))
+            .count() as u64;
+        Ok(ImportJournal {
+            id: id.to_string(),
+            dir,
+            file: io::BufWriter::new(file),
+            backups,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn append<T: Serialize>(&mut self, line: &T) -> io::Result<()> {
+        serde_json::to_writer(&mut self.file, line).map_err(io::Error::other)?;
+        self.file.write_all(b"\n")?;
+        self.file.flush()
+    }
+
+    /// Wie `fs::create_dir_all`, protokolliert aber jeden neu angelegten Ordner.
+    pub fn create_dir_all(&mut self, path: &Path) -> io::Result<()> {
+        let missing: Vec<&Path> = path.ancestors().take_while(|p| !p.exists()).collect();
+        for dir in missing.into_iter().rev() {
+            self.append(&JournalEntry::CreatedDir {
+                path: dir.to_path_buf(),
+            })?;
+            fs::create_dir(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Verschiebt `from` nach `to` (z. B. `incoming` ans Ziel) und protokolliert, was
+    /// mit einer Datei am Ziel geschieht: Eine vorhandene Datei wird zuerst gesichert.
+    pub fn place(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+        if fs::symlink_metadata(to).is_ok() {
+            self.backups += 1;
+            let backup_dir = self.dir.join("backup");
+            fs::create_dir_all(&backup_dir)?;
+            let backup = backup_dir.join(self.backups.to_string());
+            self.append(&JournalEntry::Replaced {
+                path: to.to_path_buf(),
+                backup: backup.clone(),
+            })?;
+            move_file(to, &backup)?;
+            if let Err(e) = fs::rename(from, to) {
+                // Original wieder an seinen Platz, statt es nur in der Sicherung zu lassen
+                let _ = move_file(&backup, to);
+                return Err(e);
+            }
+            Ok(())
+        } else {
+            self.append(&JournalEntry::Created {
+                path: to.to_path_buf(),
+            })?;
+            fs::rename(from, to)
+        }
+    }
+
+    /// Verschiebt eine vorhandene Datei und protokolliert es.
+    pub fn move_existing(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+        self.append(&JournalEntry::Moved {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        })?;
+        move_file(from, to)
+    }
+}
+
+/// Verschiebt eine Datei, notfalls als Kopie (anderes Laufwerk).
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+fn read_journal(id: &str) -> Result<(JournalHeader, Vec<JournalEntry>), String> {
+    let path = journal_dir(id)?.join("journal.jsonl");
+    let file = fs::File::open(&path)
+        .map_err(|e| format!("Importprotokoll {} nicht gefunden: {}", id, e))?;
+    let mut lines = io::BufReader::new(file).lines();
+    let header: JournalHeader = match lines.next() {
+        Some(Ok(line)) => serde_json::from_str(&line)
+            .map_err(|e| format!("Importprotokoll {} beschädigt: {}", id, e))?,
+        _ => return Err(format!("Importprotokoll {} ist leer", id)),
+    };
+    let mut entries = Vec::new();
+    for line in lines {
+        let line = line.map_err(|e| format!("Importprotokoll {} unlesbar: {}", id, e))?;
+        // Eine beim Absturz halb geschriebene letzte Zeile ignorieren
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(_) => break,
+        }
+    }
+    Ok((header, entries))
+}
+
+#[derive(Debug, Serialize)]
+pub struct JournalInfo {
+    id: String,
+    user_base: String,
+    package: String,
+    started_at: u64,
+    /// Anzahl protokollierter Änderungen.
+    changes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RollbackSummary {
+    pub message: String,
+    /// Wiederhergestellte Originaldateien.
+    pub restored: u64,
+    /// Entfernte Dateien und Ordner des Imports.
+    pub removed: u64,
+    /// Änderungen, die nicht rückgängig gemacht werden konnten.
+    pub failed: Vec<String>,
+}
+
+/// Macht einen Import rückgängig: Änderungen werden in umgekehrter Reihenfolge
+/// zurückgenommen. Gelingt alles, wird das Protokoll gelöscht.
+pub fn rollback(id: &str) -> Result<RollbackSummary, String> {
+    let (header, entries) = read_journal(id)?;
+    // Ein zurückgenommener Import kann nicht mehr fortgesetzt werden
+    resume::remove(&resume::import_state_path(&journal_dir(id)?));
+    let mut restored = 0;
+    let mut removed = 0;
+    let mut failed = Vec::new();
+    for entry in entries.iter().rev() {
+        let result = match entry {
+            JournalEntry::Created { path } => match fs::remove_file(path) {
+                Ok(()) => {
+                    removed += 1;
+                    Ok(())
+                }
+                // Vor dem Anlegen abgebrochen
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(format!("{}: {}", path.display(), e)),
+            },
+            JournalEntry::Replaced { path, backup } => {
+                if backup.exists() {
+                    let _ = fs::remove_file(path);
+                    move_file(backup, path)
+                        .map(|()| restored += 1)
+                        .map_err(|e| format!("{}: {}", path.display(), e))
+                } else {
+                    // Sicherung nie angelegt: das Original wurde nicht angetastet
+                    Ok(())
+                }
+            }
+            JournalEntry::Moved { from, to } => {
+                if to.exists() {
+                    move_file(to, from)
+                        .map(|()| restored += 1)
+                        .map_err(|e| format!("{}: {}", from.display(), e))
+                } else {
+                    Ok(())
+                }
+            }
+            // Nur leere Ordner entfernen; was danach hinzukam, bleibt
+            JournalEntry::CreatedDir { path } => {
+                if fs::remove_dir(path).is_ok() {
+                    removed += 1;
+                }
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            eprintln!("⚠️ Rückgängig nicht möglich: {}", e);
+            failed.push(e);
+        }
+    }
+
+    let message = if failed.is_empty() {
+        let _ = fs::remove_dir_all(journal_dir(id)?);
+        format!(
+            "Import in {} rückgängig gemacht",
+            header.user_base.display()
+        )
+    } else {
+        format!(
+            "Import teilweise rückgängig gemacht – {} Änderungen konnten nicht zurückgenommen werden",
+            failed.len()
+        )
+    };
+    Ok(RollbackSummary {
+        message,
+        restored,
+        removed,
+        failed,
+    })
+}
+
+/// Macht den Import mit dieser Protokoll-Kennung rückgängig.
+#[tauri::command]
+pub async fn rollback_import(journal_id: String) -> Result<RollbackSummary, String> {
+    rollback(&journal_id)
+}
+
+/// Köpfe aller vorhandenen Protokolle mit ihrem Ordner.
+pub fn headers() -> Vec<(JournalHeader, PathBuf)> {
+    let Ok(entries) = fs::read_dir(journals_dir()) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry.file_name().to_string_lossy().to_string();
+            let (header, _) = read_journal(&id).ok()?;
+            Some((header, entry.path()))
+        })
+        .collect()
+}
+
+/// Importe, die noch rückgängig gemacht werden können (neueste zuerst).
+#[tauri::command]
+pub async fn list_import_journals() -> Result<Vec<JournalInfo>, String> {
+    let entries = match fs::read_dir(journals_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(Vec::new()),
+    };
+    let mut journals = Vec::new();
+    for entry in entries.flatten() {
+        let id = entry.file_name().to_string_lossy().to_string();
+        if let Ok((header, changes)) = read_journal(&id) {
+            journals.push(JournalInfo {
+                id: header.id,
+                user_base: header.user_base.display().to_string(),
+                package: header.package,
+                started_at: header.started_at,
+                changes: changes.len() as u64,
+            });
+        }
+    }
+    journals.sort_by_key(|j| std::cmp::Reverse(j.started_at));
+    Ok(journals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    /// Profil unter `<temp>/<name>/profile` mit vorhandenen Dateien und einem
+    /// Ordner `incoming` daneben, aus dem der "Import" seine Dateien holt.
+    fn profile(name: &str) -> (PathBuf, PathBuf) {
+        let root =
+            std::env::temp_dir().join(format!("nilo-journal-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let base = root.join("profile");
+        fs::create_dir_all(base.join("Documents/Alt")).unwrap();
+        fs::write(base.join("Documents/bleibt.txt"), b"bleibt").unwrap();
+        fs::write(base.join("Documents/ersetzt.txt"), b"vorher").unwrap();
+        fs::write(base.join("Documents/Alt/konflikt.txt"), b"original").unwrap();
+        fs::create_dir_all(root.join("incoming")).unwrap();
+        (root, base)
+    }
+
+    /// Inhalt eines Ordners: relativer Pfad → Dateiinhalt, `None` für Ordner.
+    fn tree(dir: &Path) -> BTreeMap<PathBuf, Option<Vec<u8>>> {
+        let mut entries = BTreeMap::new();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            for entry in fs::read_dir(&current).unwrap().flatten() {
+                let path = entry.path();
+                let rel = path.strip_prefix(dir).unwrap().to_path_buf();
+                if path.is_dir() {
+                    entries.insert(rel, None);
+                    pending.push(path);
+                } else {
+                    entries.insert(rel, Some(fs::read(&path).unwrap()));
+                }
+            }
+        }
+        entries
+    }
+
+    fn incoming(root: &Path, name: &str, data: &[u8]) -> PathBuf {
+        let path = root.join("incoming").join(name);
+        fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn rollback_restores_the_previous_tree() {
+        let (root, base) = profile("rollback");
+        let before = tree(&base);
+
+        let mut journal = ImportJournal::create(&base, Path::new("paket.nilo")).unwrap();
+        let id = journal.id.clone();
+        // Neue Ordner und eine neue Datei darin
+        journal
+            .create_dir_all(&base.join("Documents/Neu/Tief"))
+            .unwrap();
+        let new = incoming(&root, "neu", b"neu");
+        journal
+            .place(&new, &base.join("Documents/Neu/Tief/neu.txt"))
+            .unwrap();
+        // Vorhandene Datei ersetzt
+        let replacement = incoming(&root, "ersetzt", b"nachher");
+        journal
+            .place(&replacement, &base.join("Documents/ersetzt.txt"))
+            .unwrap();
+        // Vorhandene Datei beiseite geschoben (Konfliktregel "Sichern"), dann neu angelegt
+        let conflict = base.join("Documents/Alt/konflikt.txt");
+        journal
+            .move_existing(&conflict, &base.join("Documents/Alt/konflikt.bak.txt"))
+            .unwrap();
+        let imported = incoming(&root, "konflikt", b"importiert");
+        journal.place(&imported, &conflict).unwrap();
+        drop(journal);
+
+        assert_eq!(
+            fs::read(base.join("Documents/ersetzt.txt")).unwrap(),
+            b"nachher"
+        );
+        assert_ne!(tree(&base), before);
+
+        let summary = rollback(&id).unwrap();
+        assert!(summary.failed.is_empty(), "{:?}", summary.failed);
+        assert_eq!(summary.restored, 2);
+        // Zwei Dateien und zwei Ordner
+        assert_eq!(summary.removed, 4);
+        assert_eq!(tree(&base), before);
+
+        // Das Protokoll ist danach gelöscht; ein zweites Zurücknehmen schlägt fehl
+        assert!(!journal_dir(&id).unwrap().exists());
+        assert!(rollback(&id).is_err());
+        assert_eq!(tree(&base), before);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn rollback_after_interrupted_import() {
+        let (root, base) = profile("interrupted");
+        let before = tree(&base);
+
+        let mut journal = ImportJournal::create(&base, Path::new("paket.nilo")).unwrap();
+        let id = journal.id.clone();
+        let replacement = incoming(&root, "ersetzt", b"nachher");
+        journal
+            .place(&replacement, &base.join("Documents/ersetzt.txt"))
+            .unwrap();
+        drop(journal);
+
+        // Fortgesetzter Import führt dasselbe Protokoll weiter
+        let mut journal = ImportJournal::open(&id).unwrap();
+        let replacement = incoming(&root, "bleibt", b"ueberschrieben");
+        journal
+            .place(&replacement, &base.join("Documents/bleibt.txt"))
+            .unwrap();
+        // Absturz nach dem Protokolleintrag, aber vor der Änderung selbst
+        journal
+            .append(&JournalEntry::Created {
+                path: base.join("Documents/nie-angelegt.txt"),
+            })
+            .unwrap();
+        journal
+            .append(&JournalEntry::Replaced {
+                path: base.join("Documents/Alt/konflikt.txt"),
+                backup: journal.dir().join("backup/99"),
+            })
+            .unwrap();
+        drop(journal);
+        // Beim Absturz halb geschriebene letzte Zeile
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(journal_dir(&id).unwrap().join("journal.jsonl"))
+            .unwrap();
+        file.write_all(br#"{"action":"created","pa"#).unwrap();
+        drop(file);
+
+        let summary = rollback(&id).unwrap();
+        assert!(summary.failed.is_empty(), "{:?}", summary.failed);
+        assert_eq!(summary.restored, 2);
+        assert_eq!(tree(&base), before);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn failed_place_keeps_the_original() {
+        let (root, base) = profile("failed");
+        let before = tree(&base);
+        let mut journal = ImportJournal::create(&base, Path::new("paket.nilo")).unwrap();
+        let id = journal.id.clone();
+        // Quelle fehlt: das Original muss wieder an seinem Platz liegen
+        let missing = root.join("incoming/fehlt");
+        assert!(journal
+            .place(&missing, &base.join("Documents/ersetzt.txt"))
+            .is_err());
+        assert_eq!(tree(&base), before);
+        drop(journal);
+        assert!(rollback(&id).unwrap().failed.is_empty());
+        assert_eq!(tree(&base), before);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn invalid_journal_id() {
+        for id in ["", "../journals", "import-1/..", "import 1", "C:\\x"] {
+            let error = rollback(id).unwrap_err();
+            assert!(
+                error.starts_with("Ungültige Protokoll-Kennung"),
+                "{}",
+                error
+            );
+        }
+        assert!(rollback("import-0-unbekannt").is_err());
+        assert!(ImportJournal::open("import-0-unbekannt").is_err());
+    }
+}
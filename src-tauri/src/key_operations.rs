@@ -0,0 +1,36 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::recipients::Identity;
+
+/// Erzeugt ein Schlüsselpaar für den Empfänger-Modus. Der private Schlüssel wird in
+/// `output_path` gespeichert, der öffentliche Schlüssel zurückgegeben – er kann beim
+/// Export als Empfänger angegeben werden.
+#[tauri::command]
+pub async fn generate_key_pair(output_path: String) -> Result<String, String> {
+    let output_path = PathBuf::from(output_path);
+    if output_path.exists() {
+        return Err(format!(
+            "Schlüsseldatei {} existiert bereits",
+            output_path.display()
+        ));
+    }
+    let identity = Identity::generate();
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&output_path)
+        .map_err(|e| format!("Konnte Schlüsseldatei nicht anlegen: {}", e))?;
+    file.write_all(identity.to_file_contents().as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Konnte Schlüsseldatei nicht schreiben: {}", e))?;
+
+    Ok(identity.public_key())
+}
@@ -0,0 +1,144 @@
+use serde::Serialize;
+
+// Zentrale Liste der Inhalte, die NiloShift kennt. Export, Erkennung und Import
+// arbeiten nur mit dieser Tabelle – ein neuer Ordner braucht nur einen Eintrag hier.
+
+/// Bekannter Ordner im Benutzerprofil.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct KnownFolder {
+    /// Kennung in ExportOptions, SelectedItems, DetectedContents und im Manifest.
+    pub id: &'static str,
+    pub label: &'static str,
+    /// Gruppe für die Anzeige: "folders", "browsers" oder "office".
+    pub group: &'static str,
+    /// Quellpfad relativ zum Benutzerprofil, zugleich Pfad im Archiv.
+    pub source: &'static str,
+    /// Zielpfad relativ zum Benutzerprofil beim Wiederherstellen.
+    pub restore: &'static str,
+    /// Standard-Ausschlussmuster dieser Kategorie (siehe `filter_rules`).
+    pub excludes: &'static [&'static str],
+    /// Programme, die Dateien dieses Ordners geöffnet halten (Prozessname ohne `.exe`).
+    pub processes: &'static [&'static str],
+}
+
+/// Caches und Absturzberichte der Chromium-Browser; werden neu aufgebaut.
+const CHROMIUM_EXCLUDES: &[&str] = &[
+    "Cache/",
+    "Code Cache/",
+    "GPUCache/",
+    "DawnCache/",
+    "GrShaderCache/",
+    "ShaderCache/",
+    "**/Service Worker/CacheStorage/",
+    "Crashpad/",
+];
+
+const FIREFOX_EXCLUDES: &[&str] = &["cache2/", "startupCache/", "Crash Reports/"];
+
+const fn folder(
+    id: &'static str,
+    label: &'static str,
+    group: &'static str,
+    path: &'static str,
+) -> KnownFolder {
+    KnownFolder {
+        id,
+        label,
+        group,
+        source: path,
+        restore: path,
+        excludes: &[],
+        processes: &[],
+    }
+}
+
+pub const KNOWN_FOLDERS: &[KnownFolder] = &[
+    folder("desktop", "Desktop", "folders", "Desktop"),
+    folder("documents", "Dokumente", "folders", "Documents"),
+    folder("pictures", "Bilder", "folders", "Pictures"),
+    folder("videos", "Videos", "folders", "Videos"),
+    folder("music", "Musik", "folders", "Music"),
+    folder("downloads", "Downloads", "folders", "Downloads"),
+    folder("favorites", "Favoriten", "folders", "Favorites"),
+    folder("contacts", "Kontakte", "folders", "Contacts"),
+    folder(
+        "saved_games",
+        "Gespeicherte Spiele",
+        "folders",
+        "Saved Games",
+    ),
+    folder("links", "Links", "folders", "Links"),
+    folder("objects_3d", "3D-Objekte", "folders", "3D Objects"),
+    KnownFolder {
+        excludes: CHROMIUM_EXCLUDES,
+        processes: &["chrome"],
+        ..folder(
+            "chrome",
+            "Google Chrome",
+            "browsers",
+            "AppData/Local/Google/Chrome/User Data",
+        )
+    },
+    KnownFolder {
+        excludes: CHROMIUM_EXCLUDES,
+        processes: &["msedge"],
+        ..folder(
+            "edge",
+            "Microsoft Edge",
+            "browsers",
+            "AppData/Local/Microsoft/Edge/User Data",
+        )
+    },
+    KnownFolder {
+        excludes: FIREFOX_EXCLUDES,
+        processes: &["firefox"],
+        ..folder(
+            "firefox",
+            "Mozilla Firefox",
+            "browsers",
+            "AppData/Roaming/Mozilla/Firefox",
+        )
+    },
+    KnownFolder {
+        processes: &["outlook"],
+        ..folder(
+            "outlook_signatures",
+            "Outlook-Signaturen",
+            "office",
+            "AppData/Roaming/Microsoft/Signatures",
+        )
+    },
+];
+
+pub fn by_id(id: &str) -> Option<&'static KnownFolder> {
+    KNOWN_FOLDERS.iter().find(|f| f.id == id)
+}
+
+/// Ordner, zu dem ein Archivpfad gehört (Groß-/Kleinschreibung egal).
+pub fn for_archive_path(path: &str) -> Option<&'static KnownFolder> {
+    let lower = path.trim_end_matches('/').to_ascii_lowercase();
+    KNOWN_FOLDERS
+        .iter()
+        .filter(|f| {
+            let source = f.source.to_ascii_lowercase();
+            lower == source
+                || lower
+                    .strip_prefix(&source)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .max_by_key(|f| f.source.len())
+}
+
+impl KnownFolder {
+    /// Zielpfad (relativ zum Profil) für einen Archivpfad unterhalb dieses Ordners.
+    pub fn restore_path(&self, archive_path: &str) -> String {
+        let rest = archive_path.get(self.source.len()..).unwrap_or("");
+        format!("{}{}", self.restore, rest)
+    }
+}
+
+/// Liefert die bekannten Ordner für die Auswahl in der Oberfläche.
+#[tauri::command]
+pub fn list_known_folders() -> Vec<KnownFolder> {
+    KNOWN_FOLDERS.to_vec()
+}
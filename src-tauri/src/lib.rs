@@ -1,7 +1,31 @@
+mod archive;
+pub mod cli;
+mod compression;
+mod conflict;
+mod crypto;
 mod export_operations;
 mod file_operations;
+mod filter_rules;
 mod import_operations;
+mod jobs;
+mod journal;
+mod key_operations;
+mod known_folders;
+mod network_operations;
+mod package_format;
+mod pairing;
+mod preview_operations;
+mod processes;
+mod profiles;
+mod progress;
+mod recipients;
+mod report;
+mod resume;
+mod snapshot;
 mod system_operations;
+mod transfer_operations;
+mod verify_operations;
+mod volumes;
 
 #[cfg(target_os = "windows")]
 fn ensure_elevated() {
@@ -31,11 +55,32 @@ pub fn run() {
             greet,
             file_operations::select_import_file,
             file_operations::select_export_path,
+            file_operations::select_folder,
+            file_operations::select_key_file,
             export_operations::start_export_command,
             import_operations::start_import_command,
             import_operations::detect_package_contents,
+            transfer_operations::start_transfer_command,
+            network_operations::start_receive_command,
+            network_operations::send_package_command,
+            jobs::pause_job,
+            jobs::resume_job,
+            jobs::cancel_job,
+            journal::rollback_import,
+            journal::list_import_journals,
+            resume::find_interrupted_export,
+            resume::find_interrupted_import,
+            verify_operations::verify_package,
+            preview_operations::preview_export,
+            key_operations::generate_key_pair,
+            known_folders::list_known_folders,
             system_operations::list_windows_profiles,
             system_operations::detect_browsers,
+            profiles::get_profile_path,
+            profiles::get_profile_root,
+            profiles::set_profile_root,
+            processes::detect_running_apps,
+            processes::close_running_apps,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,610 @@
+use serde::{Deserialize, Serialize};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::conflict::ConflictPolicy;
+use crate::import_operations::{self, CountingReader, ImportTarget, SelectedItems};
+use crate::jobs::{self, JobHandle};
+use crate::package_format;
+use crate::pairing::{self, ChannelReader, ChannelWriter};
+use crate::progress::{emit_progress, ProgressSink};
+use crate::recipients::Credentials;
+use crate::report::TransferSummary;
+use crate::volumes::VolumeSet;
+
+// Paket über das LAN übertragen statt per USB-Stick: Der neue Rechner empfängt und
+// zeigt einen Kopplungscode, der alte Rechner sendet ein Paket an dessen Adresse.
+// Die Verbindung ist über den Code gekoppelt und verschlüsselt (siehe `pairing`); das
+// Paket läuft ohne Zwischenkopie direkt in den Import des Empfängers.
+
+/// Standardport des Empfängers.
+pub const DEFAULT_PORT: u16 = 47_400;
+/// Falsche Kopplungsversuche, nach denen der Empfänger aufgibt.
+const MAX_PAIRING_ATTEMPTS: u32 = 5;
+/// Wartezeit zwischen zwei Prüfungen auf eine neue Verbindung.
+const ACCEPT_POLL: Duration = Duration::from_millis(200);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Erste Nachricht des Senders: welches Paket folgt.
+#[derive(Serialize, Deserialize)]
+pub struct PackageOffer {
+    pub name: String,
+    pub size: u64,
+}
+
+/// Letzte Nachricht des Empfängers: Ergebnis des Imports.
+#[derive(Serialize, Deserialize)]
+pub struct ImportReply {
+    pub ok: bool,
+    pub message: String,
+}
+
+/// Kopplungscode und Adressen, die der Empfänger anzeigt.
+#[derive(Serialize, Clone)]
+pub struct ReceiverInfo {
+    pub code: String,
+    pub port: u16,
+    pub addresses: Vec<String>,
+}
+
+const PROGRESS_EVENT: &str = "send-progress";
+
+/// Empfängt ein Paket im LAN und importiert es direkt. Kopplungscode und Adressen
+/// gehen als Ereignis `receive-ready` an die Oberfläche, sobald der Empfänger bereit ist.
+/// Mit `resume_interrupted` setzt ein erneut gesendetes Paket einen abgebrochenen
+/// Empfang fort, sonst beginnt der Import von vorn.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_receive_command(
+    app: tauri::AppHandle,
+    password: String,
+    selected_user: String,
+    selected_items: SelectedItems,
+    verify_hashes: Option<bool>,
+    identity_path: Option<String>,
+    custom_targets: Option<BTreeMap<String, String>>,
+    conflict_policy: Option<ConflictPolicy>,
+    port: Option<u16>,
+    job_id: Option<String>,
+    resume_interrupted: Option<bool>,
+) -> Result<TransferSummary, String> {
+    run_receive(
+        &ProgressSink::from(app),
+        password,
+        selected_user,
+        selected_items,
+        verify_hashes,
+        identity_path,
+        custom_targets,
+        conflict_policy,
+        port,
+        job_id,
+        resume_interrupted,
+    )
+}
+
+/// Empfang ohne Fenster, auch für `niloshift-cli`; Parameter wie `start_receive_command`.
+#[allow(clippy::too_many_arguments)]
+pub fn run_receive(
+    sink: &ProgressSink,
+    password: String,
+    selected_user: String,
+    selected_items: SelectedItems,
+    verify_hashes: Option<bool>,
+    identity_path: Option<String>,
+    custom_targets: Option<BTreeMap<String, String>>,
+    conflict_policy: Option<ConflictPolicy>,
+    port: Option<u16>,
+    job_id: Option<String>,
+    resume_interrupted: Option<bool>,
+) -> Result<TransferSummary, String> {
+    if !selected_items.any() {
+        return Err("Keine Inhalte zum Importieren ausgewählt".to_string());
+    }
+    let credentials = Credentials::new(&password, identity_path.as_deref())?;
+    // Pausieren/Abbrechen über pause_job/cancel_job, auch während auf den Sender gewartet wird
+    let job = jobs::register(job_id.as_deref().unwrap_or("import"))?;
+
+    let listener = listen(port.unwrap_or(DEFAULT_PORT))?;
+    let local = listener.local_addr().map_err(|e| e.to_string())?;
+    let info = ReceiverInfo {
+        code: pairing::generate_code(),
+        port: local.port(),
+        addresses: local_addresses(local.is_ipv6()),
+    };
+    eprintln!(
+        "📡 Warte auf Sender an {:?}, Port {}",
+        info.addresses, info.port
+    );
+    sink.emit("receive-ready", info.clone());
+
+    let (reader, writer) = wait_for_sender(&listener, &info.code, job.handle())?;
+    drop(listener);
+    let target = ImportTarget {
+        selected_user,
+        selected_items,
+        verify_hashes: verify_hashes.unwrap_or(true),
+        custom_targets: custom_targets.unwrap_or_default(),
+        conflict_policy: conflict_policy.unwrap_or_default(),
+    };
+    receive_package(
+        sink,
+        reader,
+        writer,
+        &credentials,
+        target,
+        resume_interrupted.unwrap_or(false),
+        job.handle(),
+    )
+}
+
+/// Öffnet den Port für IPv6 und IPv4; ohne IPv6 auf dem Rechner nur für IPv4.
+pub(crate) fn listen(port: u16) -> Result<TcpListener, String> {
+    let listener = match listen_dual_stack(port) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("⚠️ IPv6 nicht verfügbar ({}), empfange nur über IPv4", e);
+            TcpListener::bind(("0.0.0.0", port))
+                .map_err(|e| format!("Port {} kann nicht geöffnet werden: {}", port, e))?
+        }
+    };
+    // Nicht blockierend, damit Abbrechen auch beim Warten greift
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    Ok(listener)
+}
+
+/// Lauscht auf `[::]` und nimmt IPv4 als `::ffff:a.b.c.d` mit an. Windows öffnet
+/// IPv6-Sockets sonst nur für IPv6, daher wird `IPV6_V6ONLY` ausdrücklich abgeschaltet.
+fn listen_dual_stack(port: u16) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::IPV6, Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_only_v6(false)?;
+    // Wie `TcpListener::bind`: der Port ist nach einem Neustart sofort wieder frei
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddr::from((Ipv6Addr::UNSPECIFIED, port)).into())?;
+    socket.listen(128)?;
+    Ok(socket.into())
+}
+
+/// Wartet auf einen Sender, der den Kopplungscode kennt. Verbindungen mit falschem
+/// Code werden getrennt; nach `MAX_PAIRING_ATTEMPTS` Fehlversuchen gibt der Empfänger auf.
+pub(crate) fn wait_for_sender(
+    listener: &TcpListener,
+    code: &str,
+    job: &JobHandle,
+) -> Result<(ChannelReader, ChannelWriter), String> {
+    let mut failed = 0;
+    loop {
+        job.checkpoint()?;
+        let (stream, peer) = match listener.accept() {
+            Ok(conn) => conn,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(ACCEPT_POLL);
+                continue;
+            }
+            Err(e) => return Err(format!("Fehler beim Warten auf den Sender: {}", e)),
+        };
+        stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+        match pairing::accept(stream, code) {
+            Ok((reader, writer)) => {
+                eprintln!("🔗 Gekoppelt mit {}", peer);
+                return Ok((reader, writer));
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("⚠️ Kopplung mit {} fehlgeschlagen: {}", peer, e);
+                if failed >= MAX_PAIRING_ATTEMPTS {
+                    return Err(format!(
+                        "{} fehlgeschlagene Kopplungsversuche – Empfang beendet",
+                        failed
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Liest das angebotene Paket aus der Verbindung, importiert es und meldet dem Sender
+/// das Ergebnis. Mit `resume_interrupted` setzt ein erneut gesendetes Paket einen
+/// abgebrochenen Empfang fort.
+pub(crate) fn receive_package(
+    sink: &ProgressSink,
+    reader: ChannelReader,
+    mut writer: ChannelWriter,
+    credentials: &Credentials,
+    target: ImportTarget,
+    resume_interrupted: bool,
+    job: &JobHandle,
+) -> Result<TransferSummary, String> {
+    let result = import_from(sink, reader, credentials, target, resume_interrupted, job);
+    let reply = match &result {
+        Ok(summary) => ImportReply {
+            ok: true,
+            message: summary.message.clone(),
+        },
+        Err(e) => ImportReply {
+            ok: false,
+            message: e.clone(),
+        },
+    };
+    if let Err(e) = writer.send_message(&reply) {
+        eprintln!("⚠️ Antwort an den Sender nicht gesendet: {}", e);
+    }
+    result
+}
+
+fn import_from(
+    sink: &ProgressSink,
+    mut reader: ChannelReader,
+    credentials: &Credentials,
+    target: ImportTarget,
+    resume_interrupted: bool,
+    job: &JobHandle,
+) -> Result<TransferSummary, String> {
+    let offer: PackageOffer = reader.recv_message()?;
+    eprintln!("📥 Empfange {} ({} Bytes)", offer.name, offer.size);
+    let start = Instant::now();
+    let (input, bytes_read) = CountingReader::new(reader);
+    let package = package_format::open_package_reader(input, credentials)?;
+    // Kennzeichnung im Importprotokoll, damit derselbe Empfang fortgesetzt werden kann
+    let package_path = PathBuf::from(format!("lan:{}:{}", offer.name, offer.size));
+    import_operations::restore_package(
+        sink,
+        job,
+        start,
+        package,
+        &package_path,
+        bytes_read,
+        offer.size,
+        target,
+        resume_interrupted,
+    )
+}
+
+/// Sendet ein Paket an einen Empfänger im LAN, der `pairing_code` anzeigt.
+#[tauri::command]
+pub async fn send_package_command(
+    app: tauri::AppHandle,
+    package_path: String,
+    address: String,
+    pairing_code: String,
+    job_id: Option<String>,
+) -> Result<String, String> {
+    run_send(
+        &ProgressSink::from(app),
+        package_path,
+        address,
+        pairing_code,
+        job_id,
+    )
+}
+
+/// Senden ohne Fenster, auch für `niloshift-cli`; Parameter wie `send_package_command`.
+pub fn run_send(
+    sink: &ProgressSink,
+    package_path: String,
+    address: String,
+    pairing_code: String,
+    job_id: Option<String>,
+) -> Result<String, String> {
+    let package_path = PathBuf::from(package_path);
+    let volumes = VolumeSet::discover(&package_path)?;
+    let job = jobs::register(job_id.as_deref().unwrap_or("send"))?;
+    let start = Instant::now();
+    emit_progress(sink, PROGRESS_EVENT, start, 0, 100, "Verbinden");
+    let stream = connect(&address)?;
+    let (reader, writer) = pairing::connect(stream, &pairing_code)?;
+    eprintln!("🔗 Gekoppelt mit {}", address.trim());
+    let total = volumes.total_len();
+    let reply = send_package(
+        &package_path,
+        &volumes,
+        reader,
+        writer,
+        job.handle(),
+        |sent| emit_progress(sink, PROGRESS_EVENT, start, sent, total, "Senden"),
+    )?;
+    emit_progress(sink, PROGRESS_EVENT, start, total, total, "Fertig");
+    Ok(reply)
+}
+
+/// Verbindet mit `host` oder `host:port`.
+fn connect(address: &str) -> Result<TcpStream, String> {
+    let address = address.trim();
+    let with_port = with_default_port(address);
+    let addrs = with_port
+        .to_socket_addrs()
+        .map_err(|e| format!("Adresse {} ungültig: {}", address, e))?;
+    let mut last_error = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(match last_error {
+        Some(e) => format!("Keine Verbindung zu {}: {}", address, e),
+        None => format!("Adresse {} nicht gefunden", address),
+    })
+}
+
+/// Adresse mit Port; ohne Angabe der Standardport. IPv6-Adressen mit Port stehen in
+/// eckigen Klammern (`[fe80::1]:47400`), ohne Port auch ohne (`fe80::1`).
+fn with_default_port(address: &str) -> String {
+    if address.parse::<SocketAddr>().is_ok() {
+        return address.to_string();
+    }
+    let bare = address
+        .strip_prefix('[')
+        .and_then(|a| a.strip_suffix(']'))
+        .unwrap_or(address);
+    if let Ok(ip) = bare.parse::<IpAddr>() {
+        return SocketAddr::new(ip, DEFAULT_PORT).to_string();
+    }
+    // Rechnername, mit Port nur bei genau einem Doppelpunkt
+    match address.split_once(':') {
+        Some((_, port)) if !port.contains(':') => address.to_string(),
+        _ => format!("{}:{}", address, DEFAULT_PORT),
+    }
+}
+
+/// Sendet das Paket über eine gekoppelte Verbindung und liefert die Meldung des
+/// Empfängers. `progress` erhält die bisher gesendeten Bytes.
+pub(crate) fn send_package(
+    package_path: &Path,
+    volumes: &VolumeSet,
+    mut reader: ChannelReader,
+    mut writer: ChannelWriter,
+    job: &JobHandle,
+    mut progress: impl FnMut(u64),
+) -> Result<String, String> {
+    let offer = PackageOffer {
+        name: package_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        size: volumes.total_len(),
+    };
+    writer.send_message(&offer)?;
+    let mut input = volumes.open()?;
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut sent = 0u64;
+    let copied: Result<(), String> = loop {
+        if let Err(e) = job.checkpoint() {
+            break Err(e);
+        }
+        let n = match input.read(&mut buf) {
+            Ok(0) => break writer.finish().map_err(|e| e.to_string()),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => break Err(format!("Fehler beim Lesen des Pakets: {}", e)),
+        };
+        if let Err(e) = writer.write_all(&buf[..n]) {
+            break Err(format!("Verbindung unterbrochen: {}", e));
+        }
+        sent += n as u64;
+        progress(sent);
+    };
+    if let Err(e) = copied {
+        // Der Empfänger sieht das Ende und antwortet; bricht er selbst ab (z. B. falsches
+        // Passwort), steht der Grund in seiner Antwort
+        writer.shutdown();
+        if job.is_cancelled() {
+            return Err("Senden abgebrochen".to_string());
+        }
+        return match reader.recv_message::<ImportReply>() {
+            Ok(reply) if !reply.ok => Err(format!("Empfänger: {}", reply.message)),
+            _ => Err(e),
+        };
+    }
+    let reply: ImportReply = reader.recv_message()?;
+    if reply.ok {
+        Ok(reply.message)
+    } else {
+        Err(format!("Empfänger: {}", reply.message))
+    }
+}
+
+/// IP-Adressen dieses Rechners, unter denen der Sender den Empfänger erreicht; mit
+/// `ipv6` neben der IPv4- auch die IPv6-Adresse.
+fn local_addresses(ipv6: bool) -> Vec<String> {
+    let mut addresses: Vec<String> = primary_address(("0.0.0.0", 0), ("192.0.2.1", 9))
+        .into_iter()
+        .collect();
+    if ipv6 {
+        addresses.extend(primary_address(("::", 0), ("2001:db8::1", 9)));
+    }
+    if addresses.is_empty() {
+        addresses.push("127.0.0.1".to_string());
+    }
+    addresses
+}
+
+/// Ohne Pakete zu senden liefert ein "verbundener" UDP-Socket die Adresse der
+/// Schnittstelle, über die `remote` (eine Dokumentationsadresse) erreicht würde.
+fn primary_address(local: (&str, u16), remote: (&str, u16)) -> Option<String> {
+    UdpSocket::bind(local)
+        .and_then(|socket| {
+            socket.connect(remote)?;
+            socket.local_addr()
+        })
+        .ok()
+        .map(|addr| addr.ip())
+        .filter(|ip| !ip.is_unspecified() && !ip.is_loopback())
+        .map(|ip| ip.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::{ArchiveWriter, EntryHeader};
+    use crate::package_format::PackageManifest;
+    use crate::profiles;
+    use crate::progress::OutputFormat;
+    use crate::recipients::Recipient;
+    use crate::volumes::VolumeWriter;
+    use std::fs;
+
+    /// Paket mit zwei Dokumenten (eines über mehrere Frames) und einer Musikdatei.
+    fn write_package(path: &Path, big: &[u8]) {
+        let manifest = PackageManifest {
+            niloshift_version: "2".to_string(),
+            source_user: "alice".to_string(),
+            machine_name: "PC".to_string(),
+            exported_at: 0,
+            categories: Vec::new(),
+        };
+        let out = VolumeWriter::create(path, None).unwrap();
+        let recipients = [Recipient::Password("pw".to_string())];
+        let encryptor =
+            package_format::create_package_writer(out, &recipients, &manifest, None).unwrap();
+        let mut archive = ArchiveWriter::new(encryptor).unwrap();
+        for (name, data) in [
+            ("Documents/a.txt", &b"hallo"[..]),
+            ("Documents/big.bin", big),
+            ("Music/m.mp3", b"m"),
+        ] {
+            let header = EntryHeader {
+                path: name.to_string(),
+                size: data.len() as u64,
+                modified: None,
+                compression: None,
+            };
+            archive.add_file(&header, &mut &data[..]).unwrap();
+        }
+        archive
+            .finish()
+            .unwrap()
+            .finish()
+            .unwrap()
+            .finish()
+            .unwrap();
+    }
+
+    /// Empfängt über die Loopback-Schnittstelle mit `password` und sendet das Paket
+    /// an den Empfänger; liefert die Ergebnisse von Sender und Empfänger.
+    fn send_over_loopback(
+        package: &Path,
+        password: &str,
+    ) -> (Result<String, String>, Result<TransferSummary, String>) {
+        let code = pairing::generate_code();
+        let listener = listen(0).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let receiver_code = code.clone();
+        let credentials = Credentials::from_password(password);
+        let receiver = thread::spawn(move || {
+            let job = JobHandle::default();
+            let (reader, writer) = wait_for_sender(&listener, &receiver_code, &job)?;
+            let target = ImportTarget {
+                selected_user: "bob".to_string(),
+                selected_items: SelectedItems::from_ids(["documents"]),
+                verify_hashes: true,
+                custom_targets: BTreeMap::new(),
+                conflict_policy: ConflictPolicy::default(),
+            };
+            let sink = ProgressSink::console(OutputFormat::Json);
+            receive_package(&sink, reader, writer, &credentials, target, false, &job)
+        });
+
+        let volumes = VolumeSet::discover(package).unwrap();
+        let mut sent = 0;
+        let reply = connect(&format!("127.0.0.1:{}", port))
+            .and_then(|stream| pairing::connect(stream, &code))
+            .and_then(|(reader, writer)| {
+                send_package(
+                    package,
+                    &volumes,
+                    reader,
+                    writer,
+                    &JobHandle::default(),
+                    |n| sent = n,
+                )
+            });
+        if reply.is_ok() {
+            assert_eq!(sent, volumes.total_len());
+        }
+        (reply, receiver.join().unwrap())
+    }
+
+    #[test]
+    fn package_is_sent_and_imported_over_loopback() {
+        let _lock = profiles::PROFILE_ROOT_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let root = std::env::temp_dir().join(format!("nilo-lan-loopback-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("Users/bob")).unwrap();
+        let package = root.join("alice.nilo");
+        let big: Vec<u8> = (0..3_000_000u32).map(|i| (i % 253) as u8).collect();
+        write_package(&package, &big);
+        let users = root.join("Users").display().to_string();
+        tauri::async_runtime::block_on(profiles::set_profile_root(Some(users))).unwrap();
+
+        let (reply, summary) = send_over_loopback(&package, "pw");
+        let summary = summary.unwrap();
+        assert_eq!(reply, Ok(summary.message.clone()));
+        assert_eq!(summary.copied, 2);
+        let bob = root.join("Users/bob");
+        assert_eq!(fs::read(bob.join("Documents/a.txt")).unwrap(), b"hallo");
+        assert_eq!(fs::read(bob.join("Documents/big.bin")).unwrap(), big);
+        assert!(!bob.join("Music/m.mp3").exists());
+
+        // Mit falschem Passwort bricht der Empfänger ab und meldet das dem Sender
+        let (reply, summary) = send_over_loopback(&package, "falsch");
+        let error = summary.unwrap_err();
+        assert_eq!(reply, Err(format!("Empfänger: {}", error)));
+
+        tauri::async_runtime::block_on(profiles::set_profile_root(None)).unwrap();
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn listener_accepts_ipv4_and_ipv6() {
+        let listener = listen(0).unwrap();
+        let local = listener.local_addr().unwrap();
+        assert!(TcpStream::connect(("127.0.0.1", local.port())).is_ok());
+        // Ohne IPv6 auf dem Rechner öffnet `listen` nur IPv4
+        if local.is_ipv6() {
+            assert!(TcpStream::connect(("::1", local.port())).is_ok());
+        }
+    }
+
+    #[test]
+    fn addresses_match_listener() {
+        let v4_only = local_addresses(false);
+        assert!(!v4_only.is_empty());
+        assert!(v4_only
+            .iter()
+            .all(|a| a.parse::<IpAddr>().unwrap().is_ipv4()));
+        let all = local_addresses(true);
+        assert!(all.starts_with(&v4_only) || v4_only == ["127.0.0.1"]);
+    }
+
+    #[test]
+    fn default_port_is_added() {
+        assert_eq!(with_default_port("192.168.1.20"), "192.168.1.20:47400");
+        assert_eq!(with_default_port("192.168.1.20:5000"), "192.168.1.20:5000");
+        assert_eq!(with_default_port("alter-pc"), "alter-pc:47400");
+        assert_eq!(
+            with_default_port("alter-pc.local:5000"),
+            "alter-pc.local:5000"
+        );
+    }
+
+    #[test]
+    fn ipv6_without_port_gets_default_port() {
+        assert_eq!(with_default_port("fe80::1"), "[fe80::1]:47400");
+        assert_eq!(with_default_port("::1"), "[::1]:47400");
+        assert_eq!(with_default_port("[fe80::1]"), "[fe80::1]:47400");
+        assert_eq!(with_default_port("[fe80::1]:5000"), "[fe80::1]:5000");
+        assert_eq!(
+            with_default_port("2001:db8::8a2e:370:7334"),
+            "[2001:db8::8a2e:370:7334]:47400"
+        );
+    }
+}
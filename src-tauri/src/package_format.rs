@@ -0,0 +1,559 @@
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufReader, Cursor, Read, Write};
+
+use crate::crypto::{
+    self, ChunkDecryptor, ChunkEncryptor, KdfParams, KEY_LEN, NONCE_LEN, SALT_LEN,
+    STREAM_NONCE_PREFIX_LEN,
+};
+use crate::filter_rules::FilterRules;
+use crate::recipients::{self, Credentials, Recipient, RecipientStanza};
+
+// Aufbau eines .nilo-Pakets:
+//
+//   magic (8) | version (u16 LE) | header_len (u32 LE) | header (JSON) | payload
+//
+// Der komplette Kopf (inkl. Kennung und Version) geht als AAD in die Verschlüsselung
+// ein, damit Änderungen am Kopf beim Entschlüsseln auffallen.
+//
+// Der Payload besteht aus Blöcken zu je `chunk_size` Bytes Klartext plus 16 Byte Tag
+// (`aes-256-gcm-stream`, siehe `crypto::ChunkEncryptor`); `nonce` ist das
+// 7-Byte-Präfix.
+//
+// Ist `has_manifest` gesetzt, folgt direkt auf den Kopf ein separat verschlüsselter
+// Manifest-Block (manifest_len (u32 LE) | ciphertext, Nonce siehe
+// `crypto::manifest_nonce`). So lässt sich der Inhalt eines Pakets anzeigen, ohne
+// den gesamten Payload zu entschlüsseln.
+//
+// `volume_size` ist gesetzt, wenn das Paket in nummerierte Volumes aufgeteilt wurde
+// (siehe `volumes`); damit lässt sich beim Öffnen prüfen, ob Volumes fehlen.
+//
+// Der Inhalt ist mit einem zufälligen Schlüssel verschlüsselt, der für jeden
+// Empfänger (Passwort oder öffentlicher X25519-Schlüssel) verpackt in `recipients`
+// liegt (siehe `recipients`).
+//
+// Ohne Kennung: altes Format nonce | ciphertext (ZIP), Schlüssel = Passwort-Bytes.
+// Die Versionen 1 und 2 des Formats mit Kopf wurden nie veröffentlicht.
+
+/// Kennung am Anfang jedes .nilo-Pakets mit Kopf.
+pub const PACKAGE_MAGIC: &[u8; 8] = b"NILOSHFT";
+/// Formatversion, die dieser Build schreibt.
+pub const FORMAT_VERSION: u16 = 3;
+
+pub const KDF_ARGON2ID: &str = "argon2id";
+pub const CIPHER_AES_256_GCM_STREAM: &str = "aes-256-gcm-stream";
+
+/// Klartext pro Block im Stream-Modus.
+pub const DEFAULT_CHUNK_SIZE: u32 = 1024 * 1024;
+const MAX_CHUNK_SIZE: u32 = 64 * 1024 * 1024;
+
+const PREFIX_LEN: usize = PACKAGE_MAGIC.len() + 2 + 4;
+const MAX_HEADER_LEN: usize = 1024 * 1024;
+const MAX_MANIFEST_LEN: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfHeader {
+    pub id: String,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    pub salt: String,
+}
+
+impl KdfHeader {
+    pub fn from_params(kdf: &KdfParams) -> Self {
+        KdfHeader {
+            id: KDF_ARGON2ID.to_string(),
+            m_cost: kdf.m_cost,
+            t_cost: kdf.t_cost,
+            p_cost: kdf.p_cost,
+            salt: to_hex(&kdf.salt),
+        }
+    }
+
+    pub fn to_params(&self) -> Result<KdfParams, String> {
+        if self.id != KDF_ARGON2ID {
+            return Err(format!(
+                "Nicht unterstütztes Schlüsselverfahren im Paket: {}",
+                self.id
+            ));
+        }
+        let salt: [u8; SALT_LEN] = from_hex(&self.salt)?
+            .try_into()
+            .map_err(|_| "Ungültiger Salt im Paketkopf".to_string())?;
+        Ok(KdfParams {
+            m_cost: self.m_cost,
+            t_cost: self.t_cost,
+            p_cost: self.p_cost,
+            salt,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageHeader {
+    /// Verpackter Inhaltsschlüssel je Empfänger.
+    pub recipients: Vec<RecipientStanza>,
+    pub cipher: String,
+    /// Größe der Klartext-Blöcke.
+    pub chunk_size: u32,
+    pub nonce: String,
+    #[serde(default)]
+    pub has_manifest: bool,
+    /// Größe je Volume bei aufgeteilten Paketen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume_size: Option<u64>,
+}
+
+/// Inhaltsübersicht eines Pakets, separat vom Payload verschlüsselt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageManifest {
+    pub niloshift_version: String,
+    pub source_user: String,
+    pub machine_name: String,
+    /// Exportzeitpunkt in Sekunden seit 1970.
+    pub exported_at: u64,
+    pub categories: Vec<CategorySummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategorySummary {
+    pub id: String,
+    /// Pfad im Archiv (relativ zum Benutzerprofil).
+    pub path: String,
+    pub files: u64,
+    pub bytes: u64,
+    /// Herkunft und Ziel bei benutzerdefinierten Pfaden.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom: Option<CustomPath>,
+    /// Beim Export wirksame Filterregeln – zeigt, was ausgelassen wurde.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rules: Option<FilterRules>,
+}
+
+/// Benutzerdefinierter Ordner außerhalb der bekannten Ordner, z. B. `D:\Daten`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomPath {
+    /// Quellordner: absolut oder relativ zum Benutzerprofil.
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Zielordner beim Import: absolut oder relativ zum Benutzerprofil.
+    /// Ohne Angabe wird an den ursprünglichen Ort wiederhergestellt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restore: Option<String>,
+}
+
+/// Präfix benutzerdefinierter Pfade im Archiv; Eintrag `n` liegt unter `<Präfix>/<n>`.
+pub const CUSTOM_ARCHIVE_PREFIX: &str = "NiloShift-Custom";
+
+/// Geöffnetes Paket: Manifest (falls vorhanden) und entschlüsselter Inhalt.
+pub struct OpenedPackage {
+    pub manifest: Option<PackageManifest>,
+    /// Volume-Größe laut Kopf, falls das Paket aufgeteilt geschrieben wurde.
+    pub volume_size: Option<u64>,
+    pub content: Box<dyn Read + Send>,
+}
+
+impl PackageHeader {
+    /// Kopf für ein blockweise verschlüsseltes Paket.
+    pub fn new_stream(
+        recipients: Vec<RecipientStanza>,
+        nonce_prefix: &[u8; STREAM_NONCE_PREFIX_LEN],
+        chunk_size: u32,
+    ) -> Self {
+        PackageHeader {
+            recipients,
+            cipher: CIPHER_AES_256_GCM_STREAM.to_string(),
+            chunk_size,
+            nonce: to_hex(nonce_prefix),
+            has_manifest: true,
+            volume_size: None,
+        }
+    }
+
+    /// Ermittelt den Inhaltsschlüssel über die Empfänger.
+    pub fn content_key(&self, credentials: &Credentials) -> Result<[u8; KEY_LEN], String> {
+        recipients::unwrap_file_key(&self.recipients, credentials)
+    }
+
+    fn nonce_bytes<const N: usize>(&self) -> Result<[u8; N], String> {
+        from_hex(&self.nonce)?
+            .try_into()
+            .map_err(|_| "Ungültige Nonce im Paketkopf".to_string())
+    }
+
+    /// Serialisiert den vollständigen Kopf inkl. Kennung, Version und Länge.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let json = serde_json::to_vec(self)
+            .map_err(|e| format!("Fehler beim Erstellen des Paketkopfs: {}", e))?;
+        let mut out = Vec::with_capacity(PREFIX_LEN + json.len());
+        out.extend_from_slice(PACKAGE_MAGIC);
+        out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        out.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        out.extend_from_slice(&json);
+        Ok(out)
+    }
+}
+
+/// Legt ein neues, blockweise verschlüsseltes Paket an: schreibt Kopf und Manifest
+/// nach `out` und liefert den Writer für den Klartext. Abschließen mit `finish()`.
+/// Jeder der `recipients` kann das Paket öffnen. `volume_size` wird im Kopf
+/// vermerkt, wenn `out` in Volumes aufteilt.
+pub fn create_package_writer<W: Write>(
+    mut out: W,
+    recipients: &[Recipient],
+    manifest: &PackageManifest,
+    volume_size: Option<u64>,
+) -> Result<ChunkEncryptor<W>, String> {
+    let mut key = [0u8; KEY_LEN];
+    rand::thread_rng().fill_bytes(&mut key);
+    let stanzas = recipients::wrap_file_key(&key, recipients)?;
+    let mut nonce_prefix = [0u8; STREAM_NONCE_PREFIX_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce_prefix);
+    let mut header = PackageHeader::new_stream(stanzas, &nonce_prefix, DEFAULT_CHUNK_SIZE);
+    header.volume_size = volume_size;
+    let header = header.to_bytes()?;
+    out.write_all(&header)
+        .map_err(|e| format!("Fehler beim Schreiben des Paketkopfs: {}", e))?;
+    let manifest_json = serde_json::to_vec(manifest)
+        .map_err(|e| format!("Fehler beim Erstellen des Manifests: {}", e))?;
+    let sealed = new_cipher(&key)?
+        .encrypt(
+            Nonce::from_slice(&crypto::manifest_nonce(&nonce_prefix)),
+            Payload {
+                msg: &manifest_json,
+                aad: &header,
+            },
+        )
+        .map_err(|e| format!("Fehler beim Verschlüsseln des Manifests: {}", e))?;
+    out.write_all(&(sealed.len() as u32).to_le_bytes())
+        .and_then(|_| out.write_all(&sealed))
+        .map_err(|e| format!("Fehler beim Schreiben des Manifests: {}", e))?;
+    Ok(ChunkEncryptor::new(
+        out,
+        &key,
+        nonce_prefix,
+        DEFAULT_CHUNK_SIZE as usize,
+        header,
+    ))
+}
+
+/// Angefangenes Paket, das weitergeschrieben werden soll: Kopf gelesen, Schlüssel
+/// ermittelt. Die Blöcke beginnen bei `payload_offset`.
+pub struct ResumablePackage {
+    pub manifest: PackageManifest,
+    pub volume_size: Option<u64>,
+    pub chunk_size: u32,
+    pub payload_offset: u64,
+    key: [u8; KEY_LEN],
+    nonce_prefix: [u8; STREAM_NONCE_PREFIX_LEN],
+    header_bytes: Vec<u8>,
+}
+
+impl ResumablePackage {
+    /// Writer für den Klartext ab Block `chunks`; `segment` siehe
+    /// `crypto::ChunkEncryptor::resume`.
+    pub fn writer<W: Write>(
+        self,
+        out: W,
+        chunks: u32,
+        segment: u8,
+    ) -> Result<ChunkEncryptor<W>, String> {
+        ChunkEncryptor::resume(
+            out,
+            &self.key,
+            self.nonce_prefix,
+            self.chunk_size as usize,
+            self.header_bytes,
+            chunks,
+            segment,
+        )
+        .map_err(|e| e.to_string())
+    }
+}
+
+/// Liest Kopf und Manifest eines von diesem Build angefangenen Pakets, um es
+/// fortzusetzen. Dafür muss der Inhaltsschlüssel wieder entpackt werden können.
+pub fn open_for_resume<R: Read>(
+    mut input: R,
+    credentials: &Credentials,
+) -> Result<ResumablePackage, String> {
+    let mut data = Vec::new();
+    (&mut input)
+        .take(PREFIX_LEN as u64)
+        .read_to_end(&mut data)
+        .map_err(read_error)?;
+    if data.len() < PREFIX_LEN
+        || !data.starts_with(PACKAGE_MAGIC)
+        || version_of(&data) != FORMAT_VERSION
+    {
+        return Err("Angefangenes Paket ist beschädigt".to_string());
+    }
+    let header_len = header_len_of(&data)?;
+    (&mut input)
+        .take(header_len as u64)
+        .read_to_end(&mut data)
+        .map_err(read_error)?;
+    let (header, header_bytes, _) = read_header(&data)?;
+    if header.cipher != CIPHER_AES_256_GCM_STREAM
+        || header.chunk_size == 0
+        || header.chunk_size > MAX_CHUNK_SIZE
+    {
+        return Err("Angefangenes Paket ist beschädigt".to_string());
+    }
+    let key = header.content_key(credentials)?;
+    let nonce_prefix = header.nonce_bytes()?;
+    // Länge des Manifest-Blocks über das, was davon gelesen wurde
+    let limit = MAX_MANIFEST_LEN as u64 + 4;
+    let mut manifest_block = (&mut input).take(limit);
+    let manifest = read_manifest(&mut manifest_block, &key, &nonce_prefix, header_bytes)?;
+    let payload_offset = header_bytes.len() as u64 + limit - manifest_block.limit();
+    Ok(ResumablePackage {
+        manifest,
+        volume_size: header.volume_size,
+        chunk_size: header.chunk_size,
+        payload_offset,
+        key,
+        nonce_prefix,
+        header_bytes: header_bytes.to_vec(),
+    })
+}
+
+/// Öffnet ein Paket, entschlüsselt das Manifest und liefert den Inhalt als Stream.
+/// Die Blöcke werden erst beim Lesen entschlüsselt; alte Pakete ohne Kopf müssen
+/// dafür vollständig in den Speicher geladen werden.
+pub fn open_package_reader<R: Read + Send + 'static>(
+    mut input: R,
+    credentials: &Credentials,
+) -> Result<OpenedPackage, String> {
+    let mut data = Vec::new();
+    (&mut input)
+        .take(PREFIX_LEN as u64)
+        .read_to_end(&mut data)
+        .map_err(read_error)?;
+    if !data.starts_with(PACKAGE_MAGIC) {
+        // Altes Paket: komplett lesen und in einem Stück entschlüsseln
+        input.read_to_end(&mut data).map_err(read_error)?;
+        return Ok(OpenedPackage {
+            manifest: None,
+            volume_size: None,
+            content: Box::new(Cursor::new(open_legacy(&data, credentials)?)),
+        });
+    }
+    if data.len() < PREFIX_LEN {
+        return Err("Datei ist beschädigt".to_string());
+    }
+    let version = version_of(&data);
+    if version != FORMAT_VERSION {
+        return Err(unsupported_version(version));
+    }
+    let header_len = header_len_of(&data)?;
+    (&mut input)
+        .take(header_len as u64)
+        .read_to_end(&mut data)
+        .map_err(read_error)?;
+    let (header, header_bytes, _) = read_header(&data)?;
+    if header.cipher != CIPHER_AES_256_GCM_STREAM {
+        return Err(format!(
+            "Nicht unterstütztes Verschlüsselungsverfahren im Paket: {}",
+            header.cipher
+        ));
+    }
+    if header.chunk_size == 0 || header.chunk_size > MAX_CHUNK_SIZE {
+        return Err(format!(
+            "Nicht unterstützte Blockgröße im Paket: {}",
+            header.chunk_size
+        ));
+    }
+    let key = header.content_key(credentials)?;
+    let nonce_prefix = header.nonce_bytes()?;
+    let manifest = if header.has_manifest {
+        Some(read_manifest(
+            &mut input,
+            &key,
+            &nonce_prefix,
+            header_bytes,
+        )?)
+    } else {
+        None
+    };
+    Ok(OpenedPackage {
+        manifest,
+        volume_size: header.volume_size,
+        content: Box::new(ChunkDecryptor::new(
+            BufReader::new(input),
+            &key,
+            nonce_prefix,
+            header.chunk_size as usize,
+            header_bytes.to_vec(),
+        )),
+    })
+}
+
+fn read_manifest<R: Read>(
+    input: &mut R,
+    key: &[u8; crypto::KEY_LEN],
+    nonce_prefix: &[u8; STREAM_NONCE_PREFIX_LEN],
+    header_bytes: &[u8],
+) -> Result<PackageManifest, String> {
+    let mut len = [0u8; 4];
+    input.read_exact(&mut len).map_err(read_error)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_MANIFEST_LEN {
+        return Err("Paketmanifest ist beschädigt".to_string());
+    }
+    let mut sealed = vec![0u8; len];
+    input.read_exact(&mut sealed).map_err(read_error)?;
+    let json = new_cipher(key)?
+        .decrypt(
+            Nonce::from_slice(&crypto::manifest_nonce(nonce_prefix)),
+            Payload {
+                msg: &sealed,
+                aad: header_bytes,
+            },
+        )
+        .map_err(|_| "Entschlüsselung fehlgeschlagen (falsches Passwort?)".to_string())?;
+    serde_json::from_slice(&json).map_err(|e| format!("Paketmanifest ist beschädigt: {}", e))
+}
+
+fn read_error(e: io::Error) -> String {
+    format!("Konnte Paketdatei nicht lesen: {}", e)
+}
+
+fn version_of(data: &[u8]) -> u16 {
+    u16::from_le_bytes([data[PACKAGE_MAGIC.len()], data[PACKAGE_MAGIC.len() + 1]])
+}
+
+fn header_len_of(data: &[u8]) -> Result<usize, String> {
+    let len_pos = PACKAGE_MAGIC.len() + 2;
+    let header_len = u32::from_le_bytes([
+        data[len_pos],
+        data[len_pos + 1],
+        data[len_pos + 2],
+        data[len_pos + 3],
+    ]) as usize;
+    if header_len > MAX_HEADER_LEN {
+        return Err("Paketkopf ist beschädigt".to_string());
+    }
+    Ok(header_len)
+}
+
+/// Entschlüsselt ein altes Paket ohne Kopf (nonce || ciphertext) zum ZIP-Inhalt.
+fn open_legacy(data: &[u8], credentials: &Credentials) -> Result<Vec<u8>, String> {
+    if data.len() < NONCE_LEN {
+        return Err("Datei ist beschädigt".to_string());
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    new_cipher(&crypto::legacy_key(credentials.require_password()?))?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Entschlüsselung fehlgeschlagen (falsches Passwort?)".to_string())
+}
+
+/// Zerlegt ein Paket in Kopf, rohe Kopf-Bytes (AAD) und Nutzdaten.
+fn read_header(data: &[u8]) -> Result<(PackageHeader, &[u8], &[u8]), String> {
+    if data.len() < PREFIX_LEN {
+        return Err("Datei ist beschädigt".to_string());
+    }
+    let header_len = header_len_of(data)?;
+    if data.len() < PREFIX_LEN + header_len {
+        return Err("Paketkopf ist beschädigt".to_string());
+    }
+    let header: PackageHeader = serde_json::from_slice(&data[PREFIX_LEN..PREFIX_LEN + header_len])
+        .map_err(|e| format!("Paketkopf ist beschädigt: {}", e))?;
+    let (header_bytes, payload) = data.split_at(PREFIX_LEN + header_len);
+    Ok((header, header_bytes, payload))
+}
+
+fn unsupported_version(version: u16) -> String {
+    if version < FORMAT_VERSION {
+        return format!("Paketversion {} wird nicht unterstützt", version);
+    }
+    format!(
+        "Paketversion {} wird nicht unterstützt (unterstützt bis Version {}). Bitte NiloShift aktualisieren.",
+        version, FORMAT_VERSION
+    )
+}
+
+fn new_cipher(key: &[u8]) -> Result<Aes256Gcm, String> {
+    Aes256Gcm::new_from_slice(key).map_err(|e| format!("Cipher-Fehler: {}", e))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn from_hex(s: &str) -> Result<Vec<u8>, String> {
+    if !s.len().is_multiple_of(2) {
+        return Err("Ungültiger Hex-Wert im Paketkopf".to_string());
+    }
+    // Bytes statt Zeichen: Nicht-ASCII im Kopf ist ein Fehler, keine Panik beim Schneiden
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| "Ungültiger Hex-Wert im Paketkopf".to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_hex_rejects_non_ascii() {
+        assert_eq!(from_hex("00ff7a").unwrap(), vec![0x00, 0xff, 0x7a]);
+        assert!(from_hex("abc").is_err());
+        assert!(from_hex("zz").is_err());
+        // "ä" ist zwei Bytes lang; Schneiden nach Zeichen würde hier abbrechen
+        assert!(from_hex("aä0").is_err());
+        assert!(from_hex("0ä").is_err());
+    }
+
+    #[test]
+    fn unreleased_versions_are_unsupported() {
+        for version in [1u16, 2, FORMAT_VERSION + 1] {
+            let mut data = PACKAGE_MAGIC.to_vec();
+            data.extend_from_slice(&version.to_le_bytes());
+            data.extend_from_slice(&[0u8; 64]);
+            let credentials = Credentials::from_password("pw");
+            let err = open_package_reader(Cursor::new(data), &credentials)
+                .err()
+                .unwrap();
+            assert!(
+                err.contains(&format!("Paketversion {}", version)),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn legacy_package_is_read() {
+        let nonce = [3u8; NONCE_LEN];
+        let zip = b"PK\x03\x04 alter ZIP-Inhalt".to_vec();
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(
+            &new_cipher(&crypto::legacy_key("pw"))
+                .unwrap()
+                .encrypt(Nonce::from_slice(&nonce), &zip[..])
+                .unwrap(),
+        );
+        let mut opened =
+            open_package_reader(Cursor::new(data.clone()), &Credentials::from_password("pw"))
+                .unwrap();
+        assert!(opened.manifest.is_none());
+        let mut plain = Vec::new();
+        opened.content.read_to_end(&mut plain).unwrap();
+        assert_eq!(plain, zip);
+        let credentials = Credentials::from_password("falsch");
+        assert!(open_package_reader(Cursor::new(data), &credentials).is_err());
+    }
+}
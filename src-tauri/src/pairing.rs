@@ -0,0 +1,575 @@
+use aes_gcm::{
+    aead::{AeadInPlace, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use hkdf::Hkdf;
+use rand::{Rng, RngCore};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256, Sha512};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::time::Duration;
+
+use crate::crypto::{KEY_LEN, NONCE_LEN, TAG_LEN};
+
+// Gekoppelte Verbindung zweier NiloShift-Instanzen im LAN. Der Empfänger zeigt einen
+// kurzen Code, der Sender gibt ihn ein. Beide leiten daraus per SPAKE2 (Ristretto255)
+// einen gemeinsamen Schlüssel ab; der Code geht nie über das Netz und lässt sich aus
+// einem Mitschnitt nicht offline erraten, jeder falsche Versuch kostet eine Verbindung.
+//
+//   Sender    → MAGIC ‖ T,  T = x·G + w·M
+//   Empfänger → S,          S = y·G + w·N
+//   K = x·(S − w·N) = y·(T − w·M),  Schlüssel = HKDF-SHA256(K ‖ w, salt = T ‖ S)
+//   Sender    → Bestätigung des Senders, Empfänger → Bestätigung des Empfängers
+//
+// Danach laufen die Daten in Frames (Länge als u32 BE ‖ AES-256-GCM-Chiffretext) mit
+// einem Schlüssel je Richtung und fortlaufender Nonce. Ein leerer Frame beendet den
+// Datenstrom, damit ein abgeschnittener Strom nicht als vollständig gilt.
+
+const MAGIC: &[u8; 9] = b"NILOPAIR1";
+const POINT_LEN: usize = 32;
+const CONFIRM_LEN: usize = 32;
+/// Anzahl der Ziffern im Kopplungscode.
+const CODE_DIGITS: usize = 8;
+/// Klartext je Frame beim Senden.
+const FRAME_SIZE: usize = 1024 * 1024;
+/// Obergrenze beim Lesen, damit die Gegenseite nicht beliebig viel RAM anfordert.
+const MAX_FRAME_LEN: usize = FRAME_SIZE + TAG_LEN;
+/// Zeit für die Kopplung, damit eine stumme Verbindung den Empfänger nicht blockiert.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+const M_LABEL: &[u8] = b"niloshift spake2 M";
+const N_LABEL: &[u8] = b"niloshift spake2 N";
+const CODE_LABEL: &[u8] = b"niloshift pairing code";
+
+/// Neuer zufälliger Kopplungscode, z. B. `4821-7390`.
+pub fn generate_code() -> String {
+    let n: u32 = rand::thread_rng().gen_range(0..100_000_000);
+    format!("{:04}-{:04}", n / 10_000, n % 10_000)
+}
+
+/// Ziffern des Kopplungscodes; Leerzeichen und Bindestriche sind beliebig.
+fn normalize_code(code: &str) -> Result<String, String> {
+    let digits: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect();
+    if digits.len() != CODE_DIGITS || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!(
+            "Der Kopplungscode besteht aus {} Ziffern",
+            CODE_DIGITS
+        ));
+    }
+    Ok(digits)
+}
+
+/// Seite der Verbindung; Sender und Empfänger verwenden verschiedene Punkte und Schlüssel.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Sender,
+    Receiver,
+}
+
+/// Sender: koppelt sich über `stream` mit dem Empfänger, der `code` anzeigt.
+pub fn connect(stream: TcpStream, code: &str) -> Result<(ChannelReader, ChannelWriter), String> {
+    handshake(stream, code, Role::Sender)
+}
+
+/// Empfänger: prüft, ob die Gegenseite von `stream` denselben `code` kennt.
+pub fn accept(stream: TcpStream, code: &str) -> Result<(ChannelReader, ChannelWriter), String> {
+    handshake(stream, code, Role::Receiver)
+}
+
+fn handshake(
+    mut stream: TcpStream,
+    code: &str,
+    role: Role,
+) -> Result<(ChannelReader, ChannelWriter), String> {
+    let w = code_scalar(&normalize_code(code)?);
+    let (own_blind, peer_blind) = match role {
+        Role::Sender => (label_point(M_LABEL), label_point(N_LABEL)),
+        Role::Receiver => (label_point(N_LABEL), label_point(M_LABEL)),
+    };
+    let mut wide = [0u8; 64];
+    rand::thread_rng().fill_bytes(&mut wide);
+    let secret = Scalar::from_bytes_mod_order_wide(&wide);
+    let own = (RistrettoPoint::mul_base(&secret) + w * own_blind).compress();
+
+    let net = |e: io::Error| format!("Kopplung fehlgeschlagen: {}", e);
+    stream
+        .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+        .map_err(net)?;
+    stream.set_nodelay(true).map_err(net)?;
+    let mut peer = [0u8; POINT_LEN];
+    match role {
+        Role::Sender => {
+            let mut hello = MAGIC.to_vec();
+            hello.extend_from_slice(own.as_bytes());
+            stream.write_all(&hello).map_err(net)?;
+            stream.read_exact(&mut peer).map_err(net)?;
+        }
+        Role::Receiver => {
+            let mut magic = [0u8; MAGIC.len()];
+            stream.read_exact(&mut magic).map_err(net)?;
+            if &magic != MAGIC {
+                return Err("Gegenstelle ist kein NiloShift-Sender".to_string());
+            }
+            stream.read_exact(&mut peer).map_err(net)?;
+            stream.write_all(own.as_bytes()).map_err(net)?;
+        }
+    }
+    let peer_point = CompressedRistretto::from_slice(&peer)
+        .ok()
+        .and_then(|p| p.decompress())
+        .ok_or("Ungültige Antwort der Gegenstelle")?;
+    let shared = (secret * (peer_point - w * peer_blind)).compress();
+
+    // Transkript immer in der Reihenfolge Sender, Empfänger
+    let (t, s) = match role {
+        Role::Sender => (own.to_bytes(), peer),
+        Role::Receiver => (peer, own.to_bytes()),
+    };
+    let mut salt = t.to_vec();
+    salt.extend_from_slice(&s);
+    let mut ikm = shared.to_bytes().to_vec();
+    ikm.extend_from_slice(w.as_bytes());
+    let hkdf = Hkdf::<Sha256>::new(Some(&salt), &ikm);
+    let expand = |info: &[u8]| -> Result<[u8; KEY_LEN], String> {
+        let mut out = [0u8; KEY_LEN];
+        hkdf.expand(info, &mut out)
+            .map_err(|e| format!("Fehler bei der Schlüsselableitung: {}", e))?;
+        Ok(out)
+    };
+    let sender_confirm = expand(b"niloshift pairing confirm sender")?;
+    let receiver_confirm = expand(b"niloshift pairing confirm receiver")?;
+    let sender_key = expand(b"niloshift pairing key sender")?;
+    let receiver_key = expand(b"niloshift pairing key receiver")?;
+
+    // Gegenseitige Bestätigung: nur wer denselben Code kennt, kommt auf dieselben Werte
+    let (own_confirm, peer_confirm, send_key, recv_key) = match role {
+        Role::Sender => (sender_confirm, receiver_confirm, sender_key, receiver_key),
+        Role::Receiver => (receiver_confirm, sender_confirm, receiver_key, sender_key),
+    };
+    let mut confirm = [0u8; CONFIRM_LEN];
+    if role == Role::Receiver {
+        stream.read_exact(&mut confirm).map_err(net)?;
+        if !same_bytes(&confirm, &peer_confirm) {
+            return Err("Kopplungscode stimmt nicht".to_string());
+        }
+    }
+    stream.write_all(&own_confirm).map_err(net)?;
+    if role == Role::Sender {
+        match stream.read_exact(&mut confirm) {
+            Ok(()) if same_bytes(&confirm, &peer_confirm) => {}
+            // Der Empfänger trennt die Verbindung bei falschem Code
+            _ => return Err("Kopplungscode stimmt nicht".to_string()),
+        }
+    }
+    stream.set_read_timeout(None).map_err(net)?;
+
+    let reader = ChannelReader {
+        stream: stream.try_clone().map_err(net)?,
+        cipher: Aes256Gcm::new(&recv_key.into()),
+        counter: 0,
+        buf: Vec::new(),
+        pos: 0,
+        finished: false,
+    };
+    let writer = ChannelWriter {
+        stream,
+        cipher: Aes256Gcm::new(&send_key.into()),
+        counter: 0,
+        buf: Vec::with_capacity(FRAME_SIZE),
+    };
+    Ok((reader, writer))
+}
+
+/// Punkt ohne bekannten diskreten Logarithmus, abgeleitet aus einer festen Bezeichnung.
+fn label_point(label: &[u8]) -> RistrettoPoint {
+    RistrettoPoint::from_uniform_bytes(&Sha512::digest(label).into())
+}
+
+fn code_scalar(digits: &str) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(CODE_LABEL);
+    hasher.update(digits.as_bytes());
+    Scalar::from_bytes_mod_order_wide(&hasher.finalize().into())
+}
+
+/// Vergleich in konstanter Zeit.
+fn same_bytes(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn frame_nonce(counter: u64) -> [u8; NONCE_LEN] {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[NONCE_LEN - 8..].copy_from_slice(&counter.to_be_bytes());
+    nonce
+}
+
+/// Empfangsrichtung einer gekoppelten Verbindung.
+pub struct ChannelReader {
+    stream: TcpStream,
+    cipher: Aes256Gcm,
+    counter: u64,
+    buf: Vec<u8>,
+    pos: usize,
+    finished: bool,
+}
+
+impl ChannelReader {
+    /// Liest den nächsten Frame; `None` am Ende des Datenstroms.
+    fn read_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut len = [0u8; 4];
+        self.stream.read_exact(&mut len).map_err(|e| {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                io::Error::new(e.kind(), "Verbindung vorzeitig getrennt")
+            } else {
+                e
+            }
+        })?;
+        let len = u32::from_be_bytes(len) as usize;
+        if !(TAG_LEN..=MAX_FRAME_LEN).contains(&len) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Ungültige Framelänge {}", len),
+            ));
+        }
+        let mut frame = vec![0u8; len];
+        self.stream.read_exact(&mut frame)?;
+        let nonce = frame_nonce(self.counter);
+        self.counter += 1;
+        self.cipher
+            .decrypt_in_place(Nonce::from_slice(&nonce), b"", &mut frame)
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Frame konnte nicht entschlüsselt werden",
+                )
+            })?;
+        Ok((!frame.is_empty()).then_some(frame))
+    }
+
+    /// Liest eine Nachricht, die die Gegenseite mit `send_message` geschickt hat.
+    /// Nachrichten sind eigene Frames und gehören nicht in einen laufenden Datenstrom.
+    pub fn recv_message<T: DeserializeOwned>(&mut self) -> Result<T, String> {
+        let frame = self
+            .read_frame()
+            .map_err(|e| e.to_string())?
+            .ok_or("Verbindung ohne Antwort beendet")?;
+        serde_json::from_slice(&frame).map_err(|e| format!("Ungültige Nachricht: {}", e))
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.buf.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            match self.read_frame()? {
+                Some(frame) => {
+                    self.buf = frame;
+                    self.pos = 0;
+                }
+                None => self.finished = true,
+            }
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Senderichtung einer gekoppelten Verbindung.
+pub struct ChannelWriter {
+    stream: TcpStream,
+    cipher: Aes256Gcm,
+    counter: u64,
+    buf: Vec<u8>,
+}
+
+impl ChannelWriter {
+    fn write_frame(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut frame = data.to_vec();
+        let nonce = frame_nonce(self.counter);
+        self.counter += 1;
+        self.cipher
+            .encrypt_in_place(Nonce::from_slice(&nonce), b"", &mut frame)
+            .map_err(|_| io::Error::other("Frame konnte nicht verschlüsselt werden"))?;
+        self.stream.write_all(&(frame.len() as u32).to_be_bytes())?;
+        self.stream.write_all(&frame)
+    }
+
+    /// Schickt eine einzelne Nachricht als eigenen Frame (siehe `recv_message`).
+    pub fn send_message<T: Serialize>(&mut self, message: &T) -> Result<(), String> {
+        self.flush().map_err(|e| e.to_string())?;
+        let data = serde_json::to_vec(message).map_err(|e| e.to_string())?;
+        self.write_frame(&data).map_err(|e| e.to_string())
+    }
+
+    /// Schließt die Senderichtung ohne Ende-Frame; die Gegenseite erkennt den Abbruch.
+    pub fn shutdown(&self) {
+        let _ = self.stream.shutdown(Shutdown::Write);
+    }
+
+    /// Beendet den Datenstrom; die Gegenseite liest danach das Ende.
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.write_frame(&[])
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let n = data.len().min(FRAME_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&data[..n]);
+        if self.buf.len() == FRAME_SIZE {
+            self.flush()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            let buf = std::mem::take(&mut self.buf);
+            self.write_frame(&buf)?;
+            self.buf = buf;
+            self.buf.clear();
+        }
+        self.stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    const CODE: &str = "4821-7390";
+    /// Vom Sender bis zu seiner Bestätigung geschickte Bytes: MAGIC ‖ T ‖ Bestätigung.
+    const HELLO_LEN: usize = MAGIC.len() + POINT_LEN;
+
+    /// Nachricht und Daten, die beim Empfänger ankommen.
+    type Received = Result<(String, Vec<u8>), String>;
+
+    /// Zerlegt den Datenstrom nach der Kopplung in Frames (jeweils mit Längenfeld).
+    fn frames(mut data: &[u8]) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        while data.len() >= 4 {
+            let len = 4 + u32::from_be_bytes(data[..4].try_into().unwrap()) as usize;
+            let len = len.min(data.len());
+            frames.push(data[..len].to_vec());
+            data = &data[len..];
+        }
+        frames
+    }
+
+    /// Koppelt Sender und Empfänger über einen Zwischenhalt. `flip` kippt ein Byte in
+    /// dem, was der Sender bis einschließlich seiner Bestätigung schickt; `relay` kann
+    /// den danach gesendeten Datenstrom verändern. Der Sender schickt eine Nachricht und
+    /// drei Datenframes; geliefert werden die Ergebnisse beider Seiten.
+    fn exchange(
+        sender_code: &str,
+        flip: Option<usize>,
+        relay: impl FnOnce(Vec<u8>) -> Vec<u8> + Send + 'static,
+    ) -> (Result<(), String>, Received) {
+        let receiver_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let receiver_addr = receiver_listener.local_addr().unwrap();
+        let receiver = thread::spawn(move || {
+            let (stream, _) = receiver_listener.accept().unwrap();
+            let (mut reader, _writer) = accept(stream, CODE)?;
+            let message: String = reader.recv_message()?;
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).map_err(|e| e.to_string())?;
+            Ok((message, data))
+        });
+
+        let relay_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let relay_addr = relay_listener.local_addr().unwrap();
+        let relay = thread::spawn(move || {
+            let (mut from_sender, _) = relay_listener.accept().unwrap();
+            let mut to_receiver = TcpStream::connect(receiver_addr).unwrap();
+            let mut back_from = to_receiver.try_clone().unwrap();
+            let mut back_to = from_sender.try_clone().unwrap();
+            let back = thread::spawn(move || {
+                let _ = io::copy(&mut back_from, &mut back_to);
+                let _ = back_to.shutdown(Shutdown::Write);
+            });
+            // Der Sender schickt seine Bestätigung erst nach der Antwort des Empfängers
+            let mut handshake = [0u8; HELLO_LEN + CONFIRM_LEN];
+            for range in [0..HELLO_LEN, HELLO_LEN..HELLO_LEN + CONFIRM_LEN] {
+                if from_sender
+                    .read_exact(&mut handshake[range.clone()])
+                    .is_err()
+                {
+                    break;
+                }
+                if let Some(i) = flip.filter(|i| range.contains(i)) {
+                    handshake[i] ^= 1;
+                }
+                let _ = to_receiver.write_all(&handshake[range]);
+            }
+            let mut rest = Vec::new();
+            let _ = from_sender.read_to_end(&mut rest);
+            let _ = to_receiver.write_all(&relay(rest));
+            let _ = to_receiver.shutdown(Shutdown::Write);
+            let _ = back.join();
+        });
+
+        let sender = (|| {
+            let stream = TcpStream::connect(relay_addr).map_err(|e| e.to_string())?;
+            let (_reader, mut writer) = connect(stream, sender_code)?;
+            writer.send_message(&"hallo")?;
+            for part in [&b"eins"[..], b"zwei", b"drei"] {
+                writer.write_all(part).map_err(|e| e.to_string())?;
+                writer.flush().map_err(|e| e.to_string())?;
+            }
+            writer.finish().map_err(|e| e.to_string())?;
+            writer.shutdown();
+            Ok(())
+        })();
+        let received = receiver.join().unwrap();
+        relay.join().unwrap();
+        (sender, received)
+    }
+
+    /// Datenstrom mit den Frames in der Reihenfolge `order` (Indizes in `frames`).
+    fn reorder(order: &'static [usize]) -> impl FnOnce(Vec<u8>) -> Vec<u8> {
+        move |data| {
+            let frames = frames(&data);
+            order.iter().flat_map(|&i| frames[i].clone()).collect()
+        }
+    }
+
+    #[test]
+    fn matching_code_pairs() {
+        let (sender, received) = exchange("4821 7390", None, |data| data);
+        assert_eq!(sender, Ok(()));
+        let (message, data) = received.unwrap();
+        assert_eq!(message, "hallo");
+        assert_eq!(data, b"einszweidrei");
+    }
+
+    #[test]
+    fn stream_has_message_data_and_end_frames() {
+        let (_, received) = exchange(CODE, None, |data| {
+            // Nachricht, drei Datenframes, leerer Ende-Frame
+            let lens: Vec<usize> = frames(&data).iter().map(Vec::len).collect();
+            assert_eq!(
+                lens,
+                [
+                    4 + 7 + TAG_LEN,
+                    4 + 4 + TAG_LEN,
+                    4 + 4 + TAG_LEN,
+                    4 + 4 + TAG_LEN,
+                    4 + TAG_LEN
+                ]
+            );
+            data
+        });
+        assert!(received.is_ok());
+    }
+
+    #[test]
+    fn wrong_code_is_rejected() {
+        let (sender, received) = exchange("4821-7391", None, |data| data);
+        assert_eq!(sender, Err("Kopplungscode stimmt nicht".to_string()));
+        assert_eq!(received, Err("Kopplungscode stimmt nicht".to_string()));
+    }
+
+    #[test]
+    fn malformed_code_is_rejected_before_connecting() {
+        for code in ["1234", "1234-56789", "abcd-efgh", ""] {
+            assert!(normalize_code(code).is_err(), "{}", code);
+        }
+        assert_eq!(normalize_code(" 4821 - 7390 ").unwrap(), "48217390");
+        assert!(normalize_code(&generate_code()).is_ok());
+    }
+
+    #[test]
+    fn tampered_confirmation_is_rejected() {
+        let (sender, received) = exchange(CODE, Some(HELLO_LEN + 3), |data| data);
+        assert_eq!(received, Err("Kopplungscode stimmt nicht".to_string()));
+        assert!(sender.is_err());
+    }
+
+    #[test]
+    fn tampered_point_is_rejected() {
+        let (sender, received) = exchange(CODE, Some(MAGIC.len() + 7), |data| data);
+        assert!(received.is_err());
+        assert!(sender.is_err());
+    }
+
+    #[test]
+    fn foreign_client_is_rejected() {
+        let (sender, received) = exchange(CODE, Some(0), |data| data);
+        assert_eq!(
+            received,
+            Err("Gegenstelle ist kein NiloShift-Sender".to_string())
+        );
+        assert!(sender.is_err());
+    }
+
+    #[test]
+    fn reordered_frames_are_rejected() {
+        let (sender, received) = exchange(CODE, None, reorder(&[0, 2, 1, 3, 4]));
+        assert_eq!(sender, Ok(()));
+        assert_eq!(
+            received,
+            Err("Frame konnte nicht entschlüsselt werden".to_string())
+        );
+    }
+
+    #[test]
+    fn dropped_frame_is_rejected() {
+        let (_, received) = exchange(CODE, None, reorder(&[0, 1, 3, 4]));
+        assert_eq!(
+            received,
+            Err("Frame konnte nicht entschlüsselt werden".to_string())
+        );
+    }
+
+    #[test]
+    fn replayed_frame_is_rejected() {
+        let (_, received) = exchange(CODE, None, reorder(&[0, 1, 1, 2, 3, 4]));
+        assert_eq!(
+            received,
+            Err("Frame konnte nicht entschlüsselt werden".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_end_frame_is_rejected() {
+        let (_, received) = exchange(CODE, None, reorder(&[0, 1, 2, 3]));
+        assert_eq!(received, Err("Verbindung vorzeitig getrennt".to_string()));
+    }
+
+    #[test]
+    fn truncated_frame_is_rejected() {
+        let (_, received) = exchange(CODE, None, |mut data| {
+            let end = frames(&data)[..3].iter().map(Vec::len).sum::<usize>() - 5;
+            data.truncate(end);
+            data
+        });
+        assert!(received.is_err());
+    }
+
+    #[test]
+    fn oversized_frame_is_rejected() {
+        let (_, received) = exchange(CODE, None, |data| {
+            let mut frames = frames(&data);
+            frames[1][..4].copy_from_slice(&(MAX_FRAME_LEN as u32 + 1).to_be_bytes());
+            frames.concat()
+        });
+        assert_eq!(
+            received,
+            Err(format!("Ungültige Framelänge {}", MAX_FRAME_LEN + 1))
+        );
+    }
+}
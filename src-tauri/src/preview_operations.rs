@@ -0,0 +1,145 @@
+use serde::Serialize;
+use std::path::Path;
+
+use crate::compression::CompressionMethod;
+use crate::export_operations::{self, ExportOptions, FileSize, SkippedPath};
+use crate::known_folders;
+use crate::package_format::DEFAULT_CHUNK_SIZE;
+use crate::profiles;
+
+/// Geschätzter Aufwand je Datei im Archiv: Eintragskopf, Blockrahmen, Prüfsumme, Index.
+const PER_FILE_OVERHEAD: u64 = 256;
+/// Authentifizierungs-Tag je verschlüsseltem Block.
+const PER_CHUNK_OVERHEAD: u64 = 16;
+
+#[derive(Debug, Serialize)]
+pub struct CategoryPreview {
+    id: String,
+    label: String,
+    /// Quellordner auf diesem Rechner.
+    source: String,
+    files: u64,
+    bytes: u64,
+    largest_files: Vec<FileSize>,
+    skipped: Vec<SkippedPath>,
+    /// Gesamtzahl ausgelassener Pfade; `skipped` enthält nur die ersten.
+    skipped_count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportPreview {
+    categories: Vec<CategoryPreview>,
+    total_files: u64,
+    total_bytes: u64,
+    /// Grobe Schätzung der Paketgröße nach Kompression und Verschlüsselung.
+    estimated_size: u64,
+    /// Freier Speicher am Zielort, falls ermittelbar.
+    free_space: Option<u64>,
+    warnings: Vec<String>,
+}
+
+/// Zeigt, was ein Export mit diesen Optionen enthalten würde – mit derselben Auswahl
+/// und denselben Regeln wie `start_export_command`, aber ohne etwas zu schreiben.
+#[tauri::command]
+pub async fn preview_export(
+    options: ExportOptions,
+    export_path: Option<String>,
+    selected_user: String,
+    source_root: Option<String>,
+) -> Result<ExportPreview, String> {
+    let profile = profiles::source_profile(source_root.as_deref(), &selected_user)?;
+    let output_path = export_operations::output_path_for(export_path, &profile);
+
+    let ratio = match options.compression.method {
+        CompressionMethod::None => 1.0,
+        CompressionMethod::Deflate => 0.6,
+        CompressionMethod::Zstd => 0.5,
+    };
+    let mut categories = Vec::new();
+    let mut estimated_size = 0u64;
+    for category in export_operations::plan_categories(&profile, &options)? {
+        let stats = export_operations::scan_category(&profile, &category)?;
+        let compressible = stats.bytes - stats.compressed_bytes;
+        estimated_size += stats.compressed_bytes
+            + (compressible as f64 * ratio) as u64
+            + stats.files * PER_FILE_OVERHEAD;
+        let (label, source) = match &category.custom {
+            Some(custom) => (
+                custom
+                    .label
+                    .clone()
+                    .unwrap_or_else(|| custom.source.clone()),
+                custom.source.clone(),
+            ),
+            None => (
+                known_folders::by_id(&category.id)
+                    .map(|f| f.label.to_string())
+                    .unwrap_or_else(|| category.id.clone()),
+                export_operations::category_source(&profile, &category)
+                    .display()
+                    .to_string(),
+            ),
+        };
+        categories.push(CategoryPreview {
+            id: category.id,
+            label,
+            source,
+            files: stats.files,
+            bytes: stats.bytes,
+            largest_files: stats.largest,
+            skipped: stats.skipped,
+            skipped_count: stats.skipped_count,
+        });
+    }
+    estimated_size += estimated_size.div_ceil(DEFAULT_CHUNK_SIZE as u64) * PER_CHUNK_OVERHEAD;
+
+    let mut warnings = Vec::new();
+    let free_space = free_space(&output_path);
+    match free_space {
+        Some(free) if free < estimated_size => warnings.push(format!(
+            "Am Zielort sind nur {} frei, das Paket wird voraussichtlich {} groß",
+            format_size(free),
+            format_size(estimated_size)
+        )),
+        Some(_) => {}
+        None => warnings.push(format!(
+            "Freier Speicher am Zielort {} konnte nicht ermittelt werden",
+            output_path.display()
+        )),
+    }
+
+    Ok(ExportPreview {
+        total_files: categories.iter().map(|c| c.files).sum(),
+        total_bytes: categories.iter().map(|c| c.bytes).sum(),
+        categories,
+        estimated_size,
+        free_space,
+        warnings,
+    })
+}
+
+/// Freier Speicher auf dem Laufwerk des Zielpfads; der Pfad selbst muss noch nicht existieren.
+fn free_space(path: &Path) -> Option<u64> {
+    let dir = path
+        .ancestors()
+        .skip(1)
+        .map(|p| {
+            if p.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                p
+            }
+        })
+        .find(|p| p.is_dir())?;
+    fs2::available_space(dir).ok()
+}
+
+fn format_size(bytes: u64) -> String {
+    const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+    const MB: f64 = 1024.0 * 1024.0;
+    if bytes as f64 >= GB {
+        format!("{:.1} GB", bytes as f64 / GB)
+    } else {
+        format!("{:.1} MB", bytes as f64 / MB)
+    }
+}
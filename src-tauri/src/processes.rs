@@ -0,0 +1,346 @@
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::known_folders::{KnownFolder, KNOWN_FOLDERS};
+
+// Laufende Programme, die Dateien der ausgewählten Ordner geöffnet halten (Browser-
+// Datenbanken, Outlook). Welche Programme zu welchem Ordner gehören, steht in
+// `known_folders`; die Prozessliste selbst kommt über `ProcessList`, damit sich die
+// Erkennung auch ohne Windows mit einer festen Liste oder einem eigenen /proc prüfen lässt.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// Quelle der laufenden Prozesse.
+pub trait ProcessList {
+    fn processes(&self) -> Result<Vec<ProcessInfo>, String>;
+}
+
+/// Prozesse dieses Rechners: `tasklist` unter Windows, sonst `/proc`.
+pub struct SystemProcesses;
+
+impl ProcessList for SystemProcesses {
+    #[cfg(target_os = "windows")]
+    fn processes(&self) -> Result<Vec<ProcessInfo>, String> {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let output = Command::new("tasklist")
+            .args(["/FO", "CSV", "/NH"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map_err(|e| format!("Prozessliste konnte nicht gelesen werden: {}", e))?;
+        Ok(parse_tasklist(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn processes(&self) -> Result<Vec<ProcessInfo>, String> {
+        ProcFs {
+            root: PathBuf::from("/proc"),
+        }
+        .processes()
+    }
+}
+
+/// Prozesse aus einem /proc-Verzeichnis (`<pid>/comm`).
+pub struct ProcFs {
+    pub root: PathBuf,
+}
+
+impl ProcessList for ProcFs {
+    fn processes(&self) -> Result<Vec<ProcessInfo>, String> {
+        let entries = fs::read_dir(&self.root).map_err(|e| {
+            format!(
+                "Prozessliste konnte nicht gelesen werden ({}): {}",
+                self.root.display(),
+                e
+            )
+        })?;
+        let mut list = Vec::new();
+        for entry in entries.flatten() {
+            let Ok(pid) = entry.file_name().to_string_lossy().parse::<u32>() else {
+                continue;
+            };
+            // Prozesse können zwischen read_dir und dem Lesen schon beendet sein
+            if let Ok(comm) = fs::read_to_string(entry.path().join("comm")) {
+                list.push(ProcessInfo {
+                    pid,
+                    name: comm.trim().to_string(),
+                });
+            }
+        }
+        Ok(list)
+    }
+}
+
+/// Feste Prozessliste, z. B. für die Prüfung der Erkennung.
+impl ProcessList for Vec<ProcessInfo> {
+    fn processes(&self) -> Result<Vec<ProcessInfo>, String> {
+        Ok(self.clone())
+    }
+}
+
+/// Zeilen von `tasklist /FO CSV /NH`: `"chrome.exe","1234","Console","1","120.000 K"`.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn parse_tasklist(output: &str) -> Vec<ProcessInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split("\",\"").map(|f| f.trim().trim_matches('"'));
+            let name = fields.next()?.to_string();
+            let pid = fields.next()?.parse().ok()?;
+            Some(ProcessInfo { pid, name })
+        })
+        .collect()
+}
+
+/// Vergleichbarer Prozessname: ohne `.exe`, Kleinschreibung.
+fn normalize(name: &str) -> String {
+    let lower = name.to_lowercase();
+    lower
+        .strip_suffix(".exe")
+        .map(str::to_string)
+        .unwrap_or(lower)
+}
+
+/// Programm, das Dateien einer ausgewählten Kategorie geöffnet hält.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningApp {
+    /// Kennung des bekannten Ordners, z. B. `chrome`.
+    pub id: String,
+    pub label: String,
+    pub processes: Vec<ProcessInfo>,
+}
+
+/// Laufende Programme zu den angegebenen Ordnern; ohne Angabe zu allen.
+pub fn running_apps(
+    list: &dyn ProcessList,
+    ids: Option<&[String]>,
+) -> Result<Vec<RunningApp>, String> {
+    let processes = list.processes()?;
+    let folders = KNOWN_FOLDERS
+        .iter()
+        .filter(|f| !f.processes.is_empty())
+        .filter(|f| ids.is_none_or(|ids| ids.iter().any(|id| id == f.id)));
+    Ok(folders
+        .filter_map(|folder| {
+            let matching: Vec<ProcessInfo> = processes
+                .iter()
+                .filter(|p| belongs_to(folder, &p.name))
+                .cloned()
+                .collect();
+            (!matching.is_empty()).then(|| RunningApp {
+                id: folder.id.to_string(),
+                label: folder.label.to_string(),
+                processes: matching,
+            })
+        })
+        .collect())
+}
+
+fn belongs_to(folder: &KnownFolder, process: &str) -> bool {
+    let name = normalize(process);
+    folder.processes.iter().any(|p| *p == name)
+}
+
+/// Beendet die Programme der angegebenen Ordner und wartet bis zu `timeout` darauf.
+/// Ohne `force` werden sie nur zum Schließen aufgefordert, damit offene Daten gespeichert
+/// werden. Liefert die Programme, die danach noch laufen.
+pub fn close_apps(
+    list: &dyn ProcessList,
+    ids: &[String],
+    force: bool,
+    timeout: Duration,
+) -> Result<Vec<RunningApp>, String> {
+    for app in running_apps(list, Some(ids))? {
+        for process in &app.processes {
+            if let Err(e) = terminate(process.pid, force) {
+                eprintln!("⚠️ {} ({}) nicht beendet: {}", process.name, process.pid, e);
+            }
+        }
+    }
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = running_apps(list, Some(ids))?;
+        if remaining.is_empty() || Instant::now() >= deadline {
+            return Ok(remaining);
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn terminate(pid: u32, force: bool) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let pid = pid.to_string();
+    let mut args = vec!["/PID", pid.as_str(), "/T"];
+    if force {
+        args.push("/F");
+    }
+    let status = Command::new("taskkill")
+        .args(&args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("taskkill beendet mit {}", status))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn terminate(pid: u32, force: bool) -> Result<(), String> {
+    let signal = if force { "-KILL" } else { "-TERM" };
+    let status = Command::new("kill")
+        .args([signal, &pid.to_string()])
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("kill beendet mit {}", status))
+    }
+}
+
+/// Programme, die Dateien der ausgewählten Ordner geöffnet halten.
+#[tauri::command]
+pub async fn detect_running_apps(ids: Option<Vec<String>>) -> Result<Vec<RunningApp>, String> {
+    running_apps(&SystemProcesses, ids.as_deref())
+}
+
+/// Schließt die Programme der angegebenen Ordner; liefert die, die noch laufen.
+#[tauri::command]
+pub async fn close_running_apps(
+    ids: Vec<String>,
+    force: Option<bool>,
+) -> Result<Vec<RunningApp>, String> {
+    close_apps(
+        &SystemProcesses,
+        &ids,
+        force.unwrap_or(false),
+        Duration::from_secs(10),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(names: &[&str]) -> Vec<ProcessInfo> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| ProcessInfo {
+                pid: 100 + i as u32,
+                name: name.to_string(),
+            })
+            .collect()
+    }
+
+    fn ids(apps: &[RunningApp]) -> Vec<&str> {
+        apps.iter().map(|a| a.id.as_str()).collect()
+    }
+
+    #[test]
+    fn browsers_and_outlook_are_matched() {
+        let fixture = list(&[
+            "chrome.exe",
+            "msedge.exe",
+            "firefox.exe",
+            "OUTLOOK.EXE",
+            "explorer.exe",
+        ]);
+        let apps = running_apps(&fixture, None).unwrap();
+        assert_eq!(
+            ids(&apps),
+            ["chrome", "edge", "firefox", "outlook_signatures"]
+        );
+        assert_eq!(apps[3].label, "Outlook-Signaturen");
+        assert_eq!(
+            apps[3].processes,
+            [ProcessInfo {
+                pid: 103,
+                name: "OUTLOOK.EXE".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn all_processes_of_an_app_are_listed() {
+        let fixture = list(&["chrome.exe", "notepad.exe", "chrome.exe"]);
+        let apps = running_apps(&fixture, None).unwrap();
+        assert_eq!(ids(&apps), ["chrome"]);
+        let pids: Vec<u32> = apps[0].processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, [100, 102]);
+    }
+
+    #[test]
+    fn names_ignore_case_and_exe_suffix() {
+        for name in ["Chrome.exe", "CHROME.EXE", "chrome", "chrome.EXE"] {
+            assert_eq!(
+                ids(&running_apps(&list(&[name]), None).unwrap()),
+                ["chrome"],
+                "{}",
+                name
+            );
+        }
+        // Nur der ganze Name zählt
+        for name in [
+            "chromedriver.exe",
+            "googlechrome.exe",
+            "chrome.exe.bak",
+            "outlook_helper",
+        ] {
+            assert!(
+                running_apps(&list(&[name]), None).unwrap().is_empty(),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn not_running_or_not_selected() {
+        assert!(running_apps(&list(&[]), None).unwrap().is_empty());
+        assert!(running_apps(&list(&["explorer.exe", "svchost.exe"]), None)
+            .unwrap()
+            .is_empty());
+
+        let fixture = list(&["chrome.exe", "OUTLOOK.EXE"]);
+        let selected = ["outlook_signatures".to_string(), "desktop".to_string()];
+        assert_eq!(
+            ids(&running_apps(&fixture, Some(&selected)).unwrap()),
+            ["outlook_signatures"]
+        );
+        let firefox = ["firefox".to_string()];
+        assert!(running_apps(&fixture, Some(&firefox)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn tasklist_output_is_parsed() {
+        let output = "\"chrome.exe\",\"1234\",\"Console\",\"1\",\"120.000 K\"\r\n\
+                      \"System Idle Process\",\"0\",\"Services\",\"0\",\"8 K\"\r\n\
+                      INFO: Keine Aufgaben\r\n";
+        assert_eq!(
+            parse_tasklist(output),
+            [
+                ProcessInfo {
+                    pid: 1234,
+                    name: "chrome.exe".to_string()
+                },
+                ProcessInfo {
+                    pid: 0,
+                    name: "System Idle Process".to_string()
+                },
+            ]
+        );
+    }
+}
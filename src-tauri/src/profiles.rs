@@ -0,0 +1,696 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::known_folders::KnownFolder;
+
+// Wo die Benutzerprofile und ihre bekannten Ordner liegen. Export, Import und
+// Erkennung fragen den `ProfileLocator`, statt `C:\Users` fest anzunehmen:
+// - `WindowsLocator` liest die Profile aus der Registry (ProfileList) und findet
+//   umgeleitete Ordner, z. B. den Desktop in OneDrive.
+// - `DirectoryLocator` nimmt jeden Unterordner eines beliebigen Ordners als Profil,
+//   etwa `E:\Users` einer ausgebauten Festplatte oder einen Testordner unter Linux.
+// Ein eigener Profilordner wird mit `set_profile_root` oder über die
+// Umgebungsvariable `NILOSHIFT_PROFILE_ROOT` festgelegt. Für eine Offline-Migration
+// von einem angeschlossenen alten Datenträger nimmt `source_locator` auch eine
+// fremde Windows-Installation (`E:\`) oder ein einzelnes Profil (`E:\Users\alice`);
+// alle Ordner werden dann relativ dazu aufgelöst, nicht über diesen Rechner.
+
+/// Umgebungsvariable für einen eigenen Profilordner.
+pub const PROFILE_ROOT_ENV: &str = "NILOSHIFT_PROFILE_ROOT";
+
+/// Ordner im Profilordner, die keine Benutzerprofile sind.
+const SKIP_NAMES: &[&str] = &[
+    "All Users",
+    "Default",
+    "Default User",
+    "Public",
+    "DefaultAppPool",
+    "WDAGUtilityAccount",
+    "Administrator",
+];
+
+/// Profilordner aus `set_profile_root`; hat Vorrang vor der Umgebungsvariable.
+static PROFILE_ROOT: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Tests, die den Profilordner umstellen, laufen nacheinander.
+#[cfg(test)]
+pub(crate) static PROFILE_ROOT_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Ein Benutzerprofil mit den Orten seiner bekannten Ordner.
+#[derive(Debug, Clone)]
+pub struct UserProfile {
+    pub name: String,
+    /// Profilordner, z. B. `C:\Users\max`.
+    pub base: PathBuf,
+    /// Bekannte Ordner, die nicht an ihrem Standardort im Profil liegen (Kennung → Ort).
+    pub redirected: BTreeMap<&'static str, PathBuf>,
+    /// Profil auf einem angeschlossenen Datenträger statt eines Kontos dieses Rechners.
+    pub offline: bool,
+}
+
+impl UserProfile {
+    /// Ort eines bekannten Ordners; umgeleitete Ordner können außerhalb des Profils liegen.
+    pub fn folder(&self, folder: &KnownFolder) -> PathBuf {
+        self.redirected
+            .get(folder.id)
+            .cloned()
+            .unwrap_or_else(|| self.base.join(folder.source))
+    }
+}
+
+pub trait ProfileLocator {
+    /// Namen der Benutzerprofile, sortiert.
+    fn list_profiles(&self) -> Result<Vec<String>, String>;
+
+    /// Profil eines Benutzers.
+    fn profile(&self, name: &str) -> Result<UserProfile, String>;
+}
+
+/// Jeder Unterordner von `root` ist ein Profil; bekannte Ordner liegen an ihrem
+/// Standardort.
+pub struct DirectoryLocator {
+    root: PathBuf,
+    offline: bool,
+}
+
+impl DirectoryLocator {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        DirectoryLocator {
+            root: root.into(),
+            offline: false,
+        }
+    }
+
+    /// Profile auf einem angeschlossenen Datenträger.
+    pub fn offline(root: impl Into<PathBuf>) -> Self {
+        DirectoryLocator {
+            root: root.into(),
+            offline: true,
+        }
+    }
+}
+
+impl ProfileLocator for DirectoryLocator {
+    fn list_profiles(&self) -> Result<Vec<String>, String> {
+        let entries = fs::read_dir(&self.root)
+            .map_err(|e| format!("Profilordner {} nicht lesbar: {}", self.root.display(), e))?;
+        let mut profiles: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| is_profile_name(name))
+            .collect();
+        profiles.sort();
+        Ok(profiles)
+    }
+
+    fn profile(&self, name: &str) -> Result<UserProfile, String> {
+        let mut components = Path::new(name).components();
+        let valid = matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        );
+        let base = self.root.join(name);
+        if !valid || !base.is_dir() {
+            return Err(format!(
+                "Benutzerprofil {} nicht gefunden in {}",
+                name,
+                self.root.display()
+            ));
+        }
+        Ok(UserProfile {
+            name: name.to_string(),
+            base,
+            redirected: BTreeMap::new(),
+            offline: self.offline,
+        })
+    }
+}
+
+/// Ein einzelnes Profil auf einem angeschlossenen Datenträger, z. B. `E:\Users\alice`.
+pub struct SingleProfileLocator {
+    base: PathBuf,
+}
+
+impl SingleProfileLocator {
+    fn name(&self) -> String {
+        self.base
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.base.display().to_string())
+    }
+}
+
+impl ProfileLocator for SingleProfileLocator {
+    fn list_profiles(&self) -> Result<Vec<String>, String> {
+        Ok(vec![self.name()])
+    }
+
+    fn profile(&self, name: &str) -> Result<UserProfile, String> {
+        let own = self.name();
+        if !name.is_empty() && !name.eq_ignore_ascii_case(&own) {
+            return Err(format!(
+                "Benutzerprofil {} nicht gefunden in {}",
+                name,
+                self.base.display()
+            ));
+        }
+        Ok(UserProfile {
+            name: own,
+            base: self.base.clone(),
+            redirected: BTreeMap::new(),
+            offline: true,
+        })
+    }
+}
+
+fn is_profile_name(name: &str) -> bool {
+    !SKIP_NAMES.iter().any(|s| s.eq_ignore_ascii_case(name))
+}
+
+/// Eigener Profilordner, falls festgelegt.
+fn configured_root() -> Option<PathBuf> {
+    let root = PROFILE_ROOT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    root.or_else(|| {
+        std::env::var_os(PROFILE_ROOT_ENV)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// Locator für diesen Rechner oder den eingestellten Profilordner.
+pub fn locator() -> Box<dyn ProfileLocator> {
+    if let Some(root) = configured_root() {
+        return Box::new(DirectoryLocator::offline(root));
+    }
+    #[cfg(target_os = "windows")]
+    {
+        Box::new(WindowsLocator)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        // Geschwister des eigenen Home-Ordners, meist `/home`
+        let root = dirs::home_dir()
+            .and_then(|home| home.parent().map(Path::to_path_buf))
+            .unwrap_or_else(|| PathBuf::from("/home"));
+        Box::new(DirectoryLocator::new(root))
+    }
+}
+
+pub fn user_profile(name: &str) -> Result<UserProfile, String> {
+    locator().profile(name)
+}
+
+/// Locator für einen Quellordner außerhalb dieses Rechners: eine Windows-Installation
+/// (`E:\`, Profile unter `Users`), ein Profilordner (`E:\Users`) oder ein einzelnes
+/// Profil (`E:\Users\alice`, erkennbar an `NTUSER.DAT` oder `AppData`).
+pub fn offline_locator(root: &Path) -> Result<Box<dyn ProfileLocator>, String> {
+    if !root.is_dir() {
+        return Err(format!("Quellordner {} nicht gefunden", root.display()));
+    }
+    if child(root, "NTUSER.DAT").is_some_and(|p| p.is_file())
+        || child(root, "AppData").is_some_and(|p| p.is_dir())
+    {
+        return Ok(Box::new(SingleProfileLocator {
+            base: root.to_path_buf(),
+        }));
+    }
+    let users = ["Users", "Documents and Settings"]
+        .iter()
+        .filter_map(|name| child(root, name))
+        .find(|p| p.is_dir());
+    Ok(Box::new(DirectoryLocator::offline(
+        users.unwrap_or_else(|| root.to_path_buf()),
+    )))
+}
+
+/// Leere Angaben bedeuten: kein eigener Quellordner.
+pub fn source_root(root: Option<String>) -> Option<String> {
+    root.map(|r| r.trim().to_string()).filter(|r| !r.is_empty())
+}
+
+/// Locator für `source_root`; ohne Angabe die Profile dieses Rechners.
+pub fn source_locator(source_root: Option<&str>) -> Result<Box<dyn ProfileLocator>, String> {
+    match source_root.map(str::trim).filter(|r| !r.is_empty()) {
+        Some(root) => offline_locator(Path::new(root)),
+        None => Ok(locator()),
+    }
+}
+
+/// Profil `name` aus `source_root` oder von diesem Rechner.
+pub fn source_profile(source_root: Option<&str>, name: &str) -> Result<UserProfile, String> {
+    source_locator(source_root)?.profile(name)
+}
+
+/// Eintrag `name` in `dir`; Groß-/Kleinschreibung egal, da NTFS-Datenträger unter
+/// Linux oft mit exakter Schreibweise eingebunden sind.
+fn child(dir: &Path, name: &str) -> Option<PathBuf> {
+    let exact = dir.join(name);
+    if exact.exists() {
+        return Some(exact);
+    }
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .find(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .eq_ignore_ascii_case(name)
+        })
+        .map(|entry| entry.path())
+}
+
+/// Profilordner eines Benutzers für die Anzeige.
+#[tauri::command]
+pub async fn get_profile_path(
+    selected_user: String,
+    source_root: Option<String>,
+) -> Result<String, String> {
+    Ok(source_profile(source_root.as_deref(), &selected_user)?
+        .base
+        .display()
+        .to_string())
+}
+
+/// Eingestellter Profilordner; `None` für die Profile dieses Rechners.
+#[tauri::command]
+pub async fn get_profile_root() -> Option<String> {
+    configured_root().map(|root| root.display().to_string())
+}
+
+/// Legt fest, aus welchem Ordner Profile gelesen werden, z. B. `E:\Users` einer
+/// angeschlossenen alten Festplatte. Ohne Angabe gelten wieder die Profile dieses
+/// Rechners.
+#[tauri::command]
+pub async fn set_profile_root(root: Option<String>) -> Result<(), String> {
+    let root = root
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+        .map(PathBuf::from);
+    if let Some(root) = &root {
+        if !root.is_dir() {
+            return Err(format!("Profilordner {} nicht gefunden", root.display()));
+        }
+    }
+    *PROFILE_ROOT.lock().unwrap_or_else(|e| e.into_inner()) = root;
+    Ok(())
+}
+
+/// Profile der lokalen Benutzerkonten laut Registry.
+#[cfg(target_os = "windows")]
+pub struct WindowsLocator;
+
+#[cfg(target_os = "windows")]
+const PROFILE_LIST: &str = r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\ProfileList";
+
+#[cfg(target_os = "windows")]
+const USER_SHELL_FOLDERS: &str =
+    r"Software\Microsoft\Windows\CurrentVersion\Explorer\User Shell Folders";
+
+/// Umleitbare bekannte Ordner: Kennung, KNOWNFOLDERID und Wert unter `User Shell Folders`.
+#[cfg(target_os = "windows")]
+const SHELL_FOLDERS: &[(&str, windows_sys::core::GUID, &str)] = {
+    use windows_sys::Win32::UI::Shell::*;
+    &[
+        ("desktop", FOLDERID_Desktop, "Desktop"),
+        ("documents", FOLDERID_Documents, "Personal"),
+        ("pictures", FOLDERID_Pictures, "My Pictures"),
+        ("videos", FOLDERID_Videos, "My Video"),
+        ("music", FOLDERID_Music, "My Music"),
+        (
+            "downloads",
+            FOLDERID_Downloads,
+            "{374DE290-123F-4565-9164-39C4925E467B}",
+        ),
+        ("favorites", FOLDERID_Favorites, "Favorites"),
+        (
+            "contacts",
+            FOLDERID_Contacts,
+            "{56784854-C6CB-462B-8169-88E350ACB882}",
+        ),
+        (
+            "saved_games",
+            FOLDERID_SavedGames,
+            "{4C5C32FF-BB9D-43B0-B5B4-2D72E54EAAA4}",
+        ),
+        (
+            "links",
+            FOLDERID_Links,
+            "{BFB9D5E0-C6A9-404C-B2B2-AE6DB6AF4968}",
+        ),
+        (
+            "objects_3d",
+            FOLDERID_Objects3D,
+            "{31C0DD25-9439-4F12-BF41-7FF4EDA38722}",
+        ),
+    ]
+};
+
+#[cfg(target_os = "windows")]
+impl WindowsLocator {
+    /// Lokale Benutzerkonten (SID, Profilordner) mit vorhandenem Profilordner.
+    fn accounts(&self) -> Vec<(String, PathBuf)> {
+        use windows_sys::Win32::System::Registry::HKEY_LOCAL_MACHINE;
+        registry::subkeys(HKEY_LOCAL_MACHINE, PROFILE_LIST)
+            .into_iter()
+            // Dienstkonten wie SYSTEM haben kürzere SIDs
+            .filter(|sid| sid.starts_with("S-1-5-21-"))
+            .filter_map(|sid| {
+                let key = format!(r"{}\{}", PROFILE_LIST, sid);
+                let path = registry::string(HKEY_LOCAL_MACHINE, &key, "ProfileImagePath", true)?;
+                Some((sid, PathBuf::from(path)))
+            })
+            .filter(|(_, path)| path.is_dir())
+            .collect()
+    }
+
+    /// Ohne Registry-Einträge: Unterordner von ProfilesDirectory, meist `C:\Users`.
+    fn fallback(&self) -> DirectoryLocator {
+        use windows_sys::Win32::System::Registry::HKEY_LOCAL_MACHINE;
+        let root = registry::string(HKEY_LOCAL_MACHINE, PROFILE_LIST, "ProfilesDirectory", true)
+            .unwrap_or_else(|| r"C:\Users".to_string());
+        DirectoryLocator::new(root)
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl ProfileLocator for WindowsLocator {
+    fn list_profiles(&self) -> Result<Vec<String>, String> {
+        let accounts = self.accounts();
+        if accounts.is_empty() {
+            return self.fallback().list_profiles();
+        }
+        let mut profiles: Vec<String> = accounts
+            .iter()
+            .filter_map(|(_, path)| path.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .filter(|name| is_profile_name(name))
+            .collect();
+        profiles.sort();
+        profiles.dedup();
+        Ok(profiles)
+    }
+
+    fn profile(&self, name: &str) -> Result<UserProfile, String> {
+        use windows_sys::Win32::System::Registry::HKEY_USERS;
+        use windows_sys::Win32::UI::Shell::FOLDERID_Profile;
+        let account = self.accounts().into_iter().find(|(_, path)| {
+            path.file_name()
+                .is_some_and(|n| n.to_string_lossy().eq_ignore_ascii_case(name))
+        });
+        let Some((sid, base)) = account else {
+            return self.fallback().profile(name);
+        };
+        // Für das eigene Konto liefert die Shell die Orte; für andere Konten stehen sie
+        // in deren Registry-Zweig, solange der Benutzer angemeldet ist
+        let own = known_folder_path(&FOLDERID_Profile).is_some_and(|p| same_path(&p, &base));
+        let user_key = format!(r"{}\{}", sid, USER_SHELL_FOLDERS);
+        let mut redirected = BTreeMap::new();
+        for (id, guid, value) in SHELL_FOLDERS {
+            let path = if own {
+                known_folder_path(guid)
+            } else {
+                registry::string(HKEY_USERS, &user_key, value, false)
+                    .and_then(|p| expand_profile_path(&p, &base))
+            };
+            let Some(path) = path else {
+                continue;
+            };
+            let Some(folder) = crate::known_folders::by_id(id) else {
+                continue;
+            };
+            if !same_path(&path, &base.join(folder.source)) {
+                redirected.insert(folder.id, path);
+            }
+        }
+        Ok(UserProfile {
+            name: name.to_string(),
+            base,
+            redirected,
+            offline: false,
+        })
+    }
+}
+
+/// Pfad eines bekannten Ordners des angemeldeten Benutzers.
+#[cfg(target_os = "windows")]
+fn known_folder_path(id: &windows_sys::core::GUID) -> Option<PathBuf> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+    use windows_sys::Win32::System::Com::CoTaskMemFree;
+    use windows_sys::Win32::UI::Shell::{SHGetKnownFolderPath, KF_FLAG_DEFAULT};
+
+    let mut raw: *mut u16 = std::ptr::null_mut();
+    let result =
+        unsafe { SHGetKnownFolderPath(id, KF_FLAG_DEFAULT as _, std::ptr::null_mut(), &mut raw) };
+    let path = (result >= 0 && !raw.is_null()).then(|| {
+        let len = (0..).take_while(|&i| unsafe { *raw.add(i) } != 0).count();
+        PathBuf::from(OsString::from_wide(unsafe {
+            std::slice::from_raw_parts(raw, len)
+        }))
+    });
+    unsafe { CoTaskMemFree(raw as *const _) };
+    path
+}
+
+/// Löst `%USERPROFILE%` mit dem Profil des Kontos auf; andere Variablen hängen vom
+/// Konto ab und werden nicht geraten.
+#[cfg(target_os = "windows")]
+fn expand_profile_path(value: &str, base: &Path) -> Option<PathBuf> {
+    const VAR: &str = "%USERPROFILE%";
+    match value.get(..VAR.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(VAR) => {
+            Some(base.join(value[VAR.len()..].trim_start_matches('\\')))
+        }
+        _ if value.contains('%') => None,
+        _ => Some(PathBuf::from(value)),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn same_path(a: &Path, b: &Path) -> bool {
+    let normalize = |p: &Path| {
+        p.to_string_lossy()
+            .replace('/', "\\")
+            .trim_end_matches('\\')
+            .to_lowercase()
+    };
+    normalize(a) == normalize(b)
+}
+
+#[cfg(target_os = "windows")]
+mod registry {
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{
+        RegCloseKey, RegEnumKeyExW, RegGetValueW, RegOpenKeyExW, HKEY, KEY_READ, RRF_NOEXPAND,
+        RRF_RT_REG_EXPAND_SZ, RRF_RT_REG_SZ,
+    };
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(Some(0)).collect()
+    }
+
+    /// Namen der Unterschlüssel von `path`.
+    pub fn subkeys(root: HKEY, path: &str) -> Vec<String> {
+        let path = wide(path);
+        let mut key: HKEY = std::ptr::null_mut();
+        if unsafe { RegOpenKeyExW(root, path.as_ptr(), 0, KEY_READ, &mut key) } != ERROR_SUCCESS {
+            return Vec::new();
+        }
+        let mut names = Vec::new();
+        let mut name = [0u16; 256];
+        for index in 0.. {
+            let mut len = name.len() as u32;
+            let status = unsafe {
+                RegEnumKeyExW(
+                    key,
+                    index,
+                    name.as_mut_ptr(),
+                    &mut len,
+                    std::ptr::null(),
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                )
+            };
+            if status != ERROR_SUCCESS {
+                break;
+            }
+            names.push(String::from_utf16_lossy(&name[..len as usize]));
+        }
+        unsafe { RegCloseKey(key) };
+        names
+    }
+
+    /// Zeichenkette `value` unter `path`. Mit `expand` werden Umgebungsvariablen mit
+    /// denen dieses Prozesses aufgelöst, sonst bleiben sie stehen.
+    pub fn string(root: HKEY, path: &str, value: &str, expand: bool) -> Option<String> {
+        let (path, value) = (wide(path), wide(value));
+        let flags = if expand {
+            RRF_RT_REG_SZ
+        } else {
+            RRF_RT_REG_SZ | RRF_RT_REG_EXPAND_SZ | RRF_NOEXPAND
+        };
+        let mut len = 0u32;
+        let status = unsafe {
+            RegGetValueW(
+                root,
+                path.as_ptr(),
+                value.as_ptr(),
+                flags,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut len,
+            )
+        };
+        if status != ERROR_SUCCESS || len == 0 {
+            return None;
+        }
+        let mut data = vec![0u16; (len as usize).div_ceil(2)];
+        let status = unsafe {
+            RegGetValueW(
+                root,
+                path.as_ptr(),
+                value.as_ptr(),
+                flags,
+                std::ptr::null_mut(),
+                data.as_mut_ptr().cast(),
+                &mut len,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return None;
+        }
+        let end = data.iter().position(|&c| c == 0).unwrap_or(data.len());
+        Some(String::from_utf16_lossy(&data[..end]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::known_folders;
+
+    /// Windows-Installation unter `<temp>/<name>` mit `Users/alice`, `Users/bob` und
+    /// den Systemordnern, die keine Profile sind.
+    fn windows_tree(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("nilo-profiles-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let users = root.join("Users");
+        fs::create_dir_all(users.join("alice/Desktop")).unwrap();
+        fs::create_dir_all(users.join("alice/AppData/Roaming")).unwrap();
+        fs::write(users.join("alice/NTUSER.DAT"), b"").unwrap();
+        fs::create_dir_all(users.join("bob/Documents")).unwrap();
+        fs::create_dir_all(users.join("Public")).unwrap();
+        fs::create_dir_all(users.join("Default User")).unwrap();
+        fs::write(users.join("desktop.ini"), b"").unwrap();
+        root
+    }
+
+    #[test]
+    fn directory_locator_lists_profiles() {
+        let root = windows_tree("list");
+        let locator = DirectoryLocator::new(root.join("Users"));
+        assert_eq!(locator.list_profiles().unwrap(), ["alice", "bob"]);
+
+        let alice = locator.profile("alice").unwrap();
+        assert_eq!(alice.base, root.join("Users/alice"));
+        assert!(!alice.offline);
+        assert!(alice.redirected.is_empty());
+        let desktop = known_folders::by_id("desktop").unwrap();
+        assert_eq!(alice.folder(desktop), root.join("Users/alice/Desktop"));
+
+        for name in ["carol", "../Users/alice", "alice/Desktop", ""] {
+            assert!(locator.profile(name).is_err(), "{}", name);
+        }
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn redirected_folder_replaces_default_location() {
+        let mut profile = UserProfile {
+            name: "alice".to_string(),
+            base: PathBuf::from("/home/alice"),
+            redirected: BTreeMap::new(),
+            offline: false,
+        };
+        profile
+            .redirected
+            .insert("desktop", PathBuf::from("/mnt/onedrive/Desktop"));
+        let desktop = known_folders::by_id("desktop").unwrap();
+        let documents = known_folders::by_id("documents").unwrap();
+        assert_eq!(
+            profile.folder(desktop),
+            PathBuf::from("/mnt/onedrive/Desktop")
+        );
+        assert_eq!(
+            profile.folder(documents),
+            PathBuf::from("/home/alice/Documents")
+        );
+    }
+
+    #[test]
+    fn offline_locator_finds_installation_and_single_profile() {
+        let root = windows_tree("offline");
+
+        // Laufwerk einer Windows-Installation: Profile unter `Users`
+        let drive = offline_locator(&root).unwrap();
+        assert_eq!(drive.list_profiles().unwrap(), ["alice", "bob"]);
+        let bob = drive.profile("bob").unwrap();
+        assert_eq!(bob.base, root.join("Users/bob"));
+        assert!(bob.offline);
+
+        // Profilordner selbst
+        let users = offline_locator(&root.join("Users")).unwrap();
+        assert_eq!(users.list_profiles().unwrap(), ["alice", "bob"]);
+
+        // Einzelnes Profil, erkannt an NTUSER.DAT
+        let single = offline_locator(&root.join("Users/alice")).unwrap();
+        assert_eq!(single.list_profiles().unwrap(), ["alice"]);
+        let alice = single.profile("ALICE").unwrap();
+        assert_eq!(alice.name, "alice");
+        assert_eq!(alice.base, root.join("Users/alice"));
+        assert!(alice.offline);
+        assert!(single.profile("").is_ok());
+        assert!(single.profile("bob").is_err());
+
+        assert!(offline_locator(&root.join("fehlt")).is_err());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn users_folder_is_found_in_any_case() {
+        let root = std::env::temp_dir().join(format!("nilo-profiles-case-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("users/carol/Desktop")).unwrap();
+        let locator = offline_locator(&root).unwrap();
+        assert_eq!(locator.list_profiles().unwrap(), ["carol"]);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn source_profile_resolves_from_source_root() {
+        let root = windows_tree("source");
+        let text = root.display().to_string();
+        let alice = source_profile(Some(&text), "alice").unwrap();
+        assert_eq!(alice.base, root.join("Users/alice"));
+        assert!(alice.offline);
+        let documents = known_folders::by_id("documents").unwrap();
+        let bob = source_profile(Some(&format!("  {}  ", text)), "bob").unwrap();
+        assert_eq!(bob.folder(documents), root.join("Users/bob/Documents"));
+
+        assert_eq!(source_root(Some("   ".to_string())), None);
+        assert_eq!(
+            source_root(Some(" E:\\ ".to_string())),
+            Some("E:\\".to_string())
+        );
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,155 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::Emitter;
+
+// Export, Import und Prüfung melden ihren Fortschritt entweder als Ereignis an das
+// Fenster der App oder, in `niloshift-cli`, auf der Konsole.
+
+/// Ausgabeformat von `niloshift-cli`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Lesbarer Text; der Fortschritt geht nach stderr, das Ergebnis nach stdout.
+    Text,
+    /// Ein JSON-Objekt je Zeile auf stdout, der Ereignisname steht unter `event`.
+    Json,
+}
+
+/// Empfänger der Fortschrittsmeldungen eines Vorgangs.
+pub enum ProgressSink {
+    App(tauri::AppHandle),
+    Console {
+        format: OutputFormat,
+        /// Zuletzt ausgegebene Phase und ganze Prozentzahl. Meldungen je Datei werden
+        /// erst ausgegeben, wenn sich eines davon ändert.
+        last: Mutex<Option<(String, u32)>>,
+    },
+}
+
+impl From<tauri::AppHandle> for ProgressSink {
+    fn from(app: tauri::AppHandle) -> Self {
+        ProgressSink::App(app)
+    }
+}
+
+impl ProgressSink {
+    pub fn console(format: OutputFormat) -> Self {
+        ProgressSink::Console {
+            format,
+            last: Mutex::new(None),
+        }
+    }
+
+    pub fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        match self {
+            ProgressSink::App(app) => {
+                let _ = app.emit(event, payload);
+            }
+            ProgressSink::Console { format, last } => {
+                let Ok(value) = serde_json::to_value(payload) else {
+                    return;
+                };
+                if let Some((phase, percent)) = progress_of(&value) {
+                    let key = (phase.to_string(), percent as u32);
+                    let mut last = last.lock().unwrap_or_else(|e| e.into_inner());
+                    if last.as_ref() == Some(&key) {
+                        return;
+                    }
+                    *last = Some(key);
+                }
+                print_event(*format, event, value);
+            }
+        }
+    }
+}
+
+/// Fortschrittsmeldung, wie sie alle Vorgänge senden.
+#[derive(Serialize, Clone)]
+pub struct ProgressPayload {
+    pub percent: f32,
+    /// Geschätzte Restzeit aus dem bisherigen Tempo.
+    pub eta_ms: u64,
+    pub phase: &'static str,
+    pub processed: u64,
+    pub total: u64,
+}
+
+/// Meldet `processed` von `total` als Ereignis `event`; die Restzeit wird aus der
+/// seit `start` vergangenen Zeit geschätzt.
+pub fn emit_progress(
+    sink: &ProgressSink,
+    event: &str,
+    start: Instant,
+    processed: u64,
+    total: u64,
+    phase: &'static str,
+) {
+    let percent = if total == 0 {
+        0.0
+    } else {
+        (processed as f32 / total as f32) * 100.0
+    };
+    let elapsed = start.elapsed().as_millis() as u64;
+    let eta_ms = if processed == 0 {
+        0
+    } else {
+        ((elapsed as f64) * ((total as f64 / processed as f64) - 1.0)).max(0.0) as u64
+    };
+    sink.emit(
+        event,
+        ProgressPayload {
+            percent,
+            eta_ms,
+            phase,
+            processed,
+            total,
+        },
+    );
+}
+
+/// Phase und Prozent, wenn das Ereignis eine Fortschrittsmeldung ist.
+fn progress_of(value: &Value) -> Option<(&str, f64)> {
+    let phase = value.get("phase")?.as_str()?;
+    let percent = value.get("percent")?.as_f64()?;
+    Some((phase, percent))
+}
+
+/// Gibt ein Ereignis auf der Konsole aus.
+pub fn print_event(format: OutputFormat, event: &str, value: Value) {
+    match format {
+        OutputFormat::Json => {
+            let mut line = Map::new();
+            line.insert("event".to_string(), Value::String(event.to_string()));
+            match value {
+                Value::Object(fields) => line.extend(fields),
+                other => {
+                    line.insert("data".to_string(), other);
+                }
+            }
+            println!("{}", Value::Object(line));
+        }
+        OutputFormat::Text => match progress_of(&value) {
+            Some((phase, percent)) => {
+                let count = |key: &str| value.get(key).and_then(Value::as_u64).unwrap_or(0);
+                let eta_secs = count("eta_ms") / 1000;
+                if eta_secs > 0 {
+                    eprintln!(
+                        "{:>3.0} % {} ({}/{}), noch ca. {} s",
+                        percent,
+                        phase,
+                        count("processed"),
+                        count("total"),
+                        eta_secs
+                    );
+                } else {
+                    eprintln!("{:>3.0} % {}", percent, phase);
+                }
+            }
+            None => match serde_json::to_string_pretty(&value) {
+                Ok(text) => println!("{}", text),
+                Err(_) => println!("{}", value),
+            },
+        },
+    }
+}
@@ -0,0 +1,499 @@
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs;
+use std::path::Path;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+
+use crate::crypto::{self, KdfParams, KEY_LEN, NONCE_LEN};
+use crate::package_format::{from_hex, to_hex, KdfHeader};
+
+// Empfänger eines Pakets (ähnlich wie bei age): Der Inhalt wird mit einem zufälligen
+// Inhaltsschlüssel verschlüsselt, der für jeden Empfänger einzeln verpackt im
+// Paketkopf liegt. Jeder Empfänger kann das Paket allein öffnen.
+//
+//   password : Schlüssel per Argon2id aus dem Passwort (eigener Salt je Paket)
+//   x25519   : ephemerer X25519-Schlüsselaustausch mit dem öffentlichen Schlüssel,
+//              Verpackungsschlüssel = HKDF-SHA256(shared, salt = epk || pk)
+//
+// Jeder Verpackungsschlüssel wird nur einmal verwendet, daher ist die Nonce null.
+
+pub const RECIPIENT_PASSWORD: &str = "password";
+pub const RECIPIENT_X25519: &str = "x25519";
+
+/// Präfix öffentlicher Schlüssel, z. B. für die Eingabe im Export.
+pub const PUBLIC_KEY_PREFIX: &str = "nilo-pub-";
+/// Präfix der Zeile mit dem privaten Schlüssel in einer Schlüsseldatei.
+pub const SECRET_KEY_PREFIX: &str = "NILO-SECRET-KEY-";
+
+const WRAP_INFO: &[u8] = b"niloshift x25519 key wrap";
+
+/// Verpackter Inhaltsschlüssel im Paketkopf.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipientStanza {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// KDF-Parameter bei Passwort-Empfängern.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<KdfHeader>,
+    /// Ephemerer öffentlicher Schlüssel bei X25519-Empfängern (hex).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ephemeral: Option<String>,
+    /// Verschlüsselter Inhaltsschlüssel (hex).
+    pub wrapped_key: String,
+}
+
+/// Empfänger beim Erstellen eines Pakets.
+pub enum Recipient {
+    Password(String),
+    PublicKey(PublicKey),
+}
+
+/// Privater Schlüssel eines Empfängers, geladen aus einer Schlüsseldatei.
+pub struct Identity {
+    secret: StaticSecret,
+}
+
+impl Identity {
+    pub fn generate() -> Self {
+        Identity {
+            secret: StaticSecret::random_from_rng(rand::rngs::OsRng),
+        }
+    }
+
+    /// Liest eine Schlüsseldatei; Zeilen mit `#` sind Kommentare.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Konnte Schlüsseldatei nicht lesen: {}", e))?;
+        let line = text
+            .lines()
+            .map(str::trim)
+            .find(|l| l.starts_with(SECRET_KEY_PREFIX))
+            .ok_or_else(|| "Schlüsseldatei enthält keinen privaten Schlüssel".to_string())?;
+        let bytes: [u8; 32] = from_hex(&line[SECRET_KEY_PREFIX.len()..])
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| "Privater Schlüssel in der Schlüsseldatei ist ungültig".to_string())?;
+        Ok(Identity {
+            secret: StaticSecret::from(bytes),
+        })
+    }
+
+    pub fn public_key(&self) -> String {
+        format!(
+            "{}{}",
+            PUBLIC_KEY_PREFIX,
+            to_hex(PublicKey::from(&self.secret).as_bytes())
+        )
+    }
+
+    /// Inhalt der Schlüsseldatei mit dem öffentlichen Schlüssel als Kommentar.
+    pub fn to_file_contents(&self) -> String {
+        format!(
+            "# NiloShift-Schlüssel – geheim halten!\n# Öffentlicher Schlüssel: {}\n{}{}\n",
+            self.public_key(),
+            SECRET_KEY_PREFIX,
+            to_hex(self.secret.as_bytes())
+        )
+    }
+}
+
+/// Zugangsdaten zum Öffnen eines Pakets: Passwort, Schlüsseldatei oder beides.
+#[derive(Default)]
+pub struct Credentials {
+    pub password: Option<String>,
+    pub identity: Option<Identity>,
+}
+
+impl Credentials {
+    /// Leeres Passwort gilt als nicht angegeben.
+    pub fn new(password: &str, identity_path: Option<&str>) -> Result<Self, String> {
+        let identity = match identity_path.filter(|p| !p.is_empty()) {
+            Some(p) => Some(Identity::load(Path::new(p))?),
+            None => None,
+        };
+        let password = Some(password.to_string()).filter(|p| !p.is_empty());
+        if password.is_none() && identity.is_none() {
+            return Err("Passwort oder Schlüsseldatei erforderlich".to_string());
+        }
+        Ok(Credentials { password, identity })
+    }
+
+    pub fn from_password(password: &str) -> Self {
+        Credentials {
+            password: Some(password.to_string()),
+            identity: None,
+        }
+    }
+
+    /// Passwort für Pakete, die nur mit Passwort verschlüsselt sein können.
+    pub fn require_password(&self) -> Result<&str, String> {
+        self.password
+            .as_deref()
+            .ok_or_else(|| "Dieses Paket kann nur mit Passwort geöffnet werden".to_string())
+    }
+}
+
+/// Liest einen öffentlichen Schlüssel im Format `nilo-pub-<hex>`.
+pub fn parse_public_key(text: &str) -> Result<PublicKey, String> {
+    let text = text.trim();
+    let bytes: [u8; 32] = text
+        .strip_prefix(PUBLIC_KEY_PREFIX)
+        .and_then(|hex| from_hex(hex).ok())
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| format!("Ungültiger öffentlicher Schlüssel: {}", text))?;
+    Ok(PublicKey::from(bytes))
+}
+
+/// Verpackt den Inhaltsschlüssel für alle Empfänger.
+pub fn wrap_file_key(
+    file_key: &[u8; KEY_LEN],
+    recipients: &[Recipient],
+) -> Result<Vec<RecipientStanza>, String> {
+    if recipients.is_empty() {
+        return Err("Passwort oder öffentlicher Schlüssel erforderlich".to_string());
+    }
+    recipients
+        .iter()
+        .map(|recipient| match recipient {
+            Recipient::Password(password) => {
+                let kdf = KdfParams::generate();
+                let key = crypto::derive_key(password, &kdf)?;
+                Ok(RecipientStanza {
+                    kind: RECIPIENT_PASSWORD.to_string(),
+                    kdf: Some(KdfHeader::from_params(&kdf)),
+                    ephemeral: None,
+                    wrapped_key: to_hex(&seal(&key, file_key)?),
+                })
+            }
+            Recipient::PublicKey(public) => {
+                let ephemeral = EphemeralSecret::random_from_rng(rand::rngs::OsRng);
+                let ephemeral_public = PublicKey::from(&ephemeral);
+                let shared = ephemeral.diffie_hellman(public);
+                if !shared.was_contributory() {
+                    return Err("Ungültiger öffentlicher Schlüssel".to_string());
+                }
+                let key = wrap_key(shared.as_bytes(), &ephemeral_public, public)?;
+                Ok(RecipientStanza {
+                    kind: RECIPIENT_X25519.to_string(),
+                    kdf: None,
+                    ephemeral: Some(to_hex(ephemeral_public.as_bytes())),
+                    wrapped_key: to_hex(&seal(&key, file_key)?),
+                })
+            }
+        })
+        .collect()
+}
+
+/// Entpackt den Inhaltsschlüssel mit Passwort oder Schlüsseldatei.
+pub fn unwrap_file_key(
+    stanzas: &[RecipientStanza],
+    credentials: &Credentials,
+) -> Result<[u8; KEY_LEN], String> {
+    let mut tried_password = false;
+    let mut tried_identity = false;
+    for stanza in stanzas {
+        let key = match (
+            stanza.kind.as_str(),
+            &credentials.password,
+            &credentials.identity,
+        ) {
+            (RECIPIENT_PASSWORD, Some(password), _) => {
+                let Some(kdf) = &stanza.kdf else {
+                    continue;
+                };
+                tried_password = true;
+                crypto::derive_key(password, &kdf.to_params()?)?
+            }
+            (RECIPIENT_X25519, _, Some(identity)) => {
+                let Some(ephemeral) = stanza.ephemeral.as_deref() else {
+                    continue;
+                };
+                tried_identity = true;
+                let ephemeral: [u8; 32] = from_hex(ephemeral)?
+                    .try_into()
+                    .map_err(|_| "Ungültiger Empfänger im Paketkopf".to_string())?;
+                let ephemeral = PublicKey::from(ephemeral);
+                let shared = identity.secret.diffie_hellman(&ephemeral);
+                if !shared.was_contributory() {
+                    continue;
+                }
+                let own_public = PublicKey::from(&identity.secret);
+                wrap_key(shared.as_bytes(), &ephemeral, &own_public)?
+            }
+            _ => continue,
+        };
+        if let Some(file_key) = open(&key, &from_hex(&stanza.wrapped_key)?) {
+            return Ok(file_key);
+        }
+    }
+    if tried_password || tried_identity {
+        return Err(match (tried_password, tried_identity) {
+            (true, false) => "Entschlüsselung fehlgeschlagen (falsches Passwort?)",
+            (false, _) => "Die Schlüsseldatei passt zu keinem Empfänger des Pakets",
+            _ => "Entschlüsselung fehlgeschlagen (falsches Passwort oder falsche Schlüsseldatei?)",
+        }
+        .to_string());
+    }
+    let has_password = stanzas.iter().any(|s| s.kind == RECIPIENT_PASSWORD);
+    let has_key = stanzas.iter().any(|s| s.kind == RECIPIENT_X25519);
+    Err(match (has_password, has_key) {
+        (true, false) => "Dieses Paket kann nur mit Passwort geöffnet werden",
+        (false, true) => "Dieses Paket kann nur mit einer Schlüsseldatei geöffnet werden",
+        _ => "Passwort oder Schlüsseldatei erforderlich",
+    }
+    .to_string())
+}
+
+fn wrap_key(
+    shared: &[u8; 32],
+    ephemeral: &PublicKey,
+    recipient: &PublicKey,
+) -> Result<[u8; KEY_LEN], String> {
+    let mut salt = Vec::with_capacity(64);
+    salt.extend_from_slice(ephemeral.as_bytes());
+    salt.extend_from_slice(recipient.as_bytes());
+    let mut key = [0u8; KEY_LEN];
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(WRAP_INFO, &mut key)
+        .map_err(|e| format!("Fehler bei der Schlüsselableitung: {}", e))?;
+    Ok(key)
+}
+
+fn seal(key: &[u8; KEY_LEN], file_key: &[u8; KEY_LEN]) -> Result<Vec<u8>, String> {
+    Aes256Gcm::new(key.into())
+        .encrypt(Nonce::from_slice(&[0u8; NONCE_LEN]), &file_key[..])
+        .map_err(|e| format!("Fehler beim Verschlüsseln des Schlüssels: {}", e))
+}
+
+fn open(key: &[u8; KEY_LEN], wrapped: &[u8]) -> Option<[u8; KEY_LEN]> {
+    Aes256Gcm::new(key.into())
+        .decrypt(Nonce::from_slice(&[0u8; NONCE_LEN]), wrapped)
+        .ok()
+        .and_then(|k| k.try_into().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_format::{self, PackageManifest, PACKAGE_MAGIC};
+    use std::io::{Cursor, Read, Write};
+
+    const FILE_KEY: [u8; KEY_LEN] = [42; KEY_LEN];
+
+    fn with_identity(identity: Identity) -> Credentials {
+        Credentials {
+            password: None,
+            identity: Some(identity),
+        }
+    }
+
+    /// Zweites Identity-Objekt mit demselben privaten Schlüssel.
+    fn same_identity(identity: &Identity) -> Identity {
+        Identity {
+            secret: StaticSecret::from(identity.secret.to_bytes()),
+        }
+    }
+
+    #[test]
+    fn password_only() {
+        let stanzas = wrap_file_key(&FILE_KEY, &[Recipient::Password("geheim".into())]).unwrap();
+        assert_eq!(stanzas.len(), 1);
+        assert_eq!(stanzas[0].kind, RECIPIENT_PASSWORD);
+        let key = unwrap_file_key(&stanzas, &Credentials::from_password("geheim")).unwrap();
+        assert_eq!(key, FILE_KEY);
+
+        let err = unwrap_file_key(&stanzas, &Credentials::from_password("falsch")).unwrap_err();
+        assert!(err.contains("falsches Passwort"), "{}", err);
+        let err = unwrap_file_key(&stanzas, &with_identity(Identity::generate())).unwrap_err();
+        assert!(err.contains("nur mit Passwort"), "{}", err);
+    }
+
+    #[test]
+    fn public_key_only() {
+        let identity = Identity::generate();
+        let public = parse_public_key(&identity.public_key()).unwrap();
+        let stanzas = wrap_file_key(&FILE_KEY, &[Recipient::PublicKey(public)]).unwrap();
+        assert_eq!(stanzas[0].kind, RECIPIENT_X25519);
+        assert!(stanzas[0].kdf.is_none());
+        let key = unwrap_file_key(&stanzas, &with_identity(identity)).unwrap();
+        assert_eq!(key, FILE_KEY);
+
+        let err = unwrap_file_key(&stanzas, &with_identity(Identity::generate())).unwrap_err();
+        assert!(err.contains("passt zu keinem Empfänger"), "{}", err);
+        let err = unwrap_file_key(&stanzas, &Credentials::from_password("geheim")).unwrap_err();
+        assert!(err.contains("nur mit einer Schlüsseldatei"), "{}", err);
+    }
+
+    #[test]
+    fn password_and_public_key_each_open_alone() {
+        let identity = Identity::generate();
+        let recipients = [
+            Recipient::Password("geheim".into()),
+            Recipient::PublicKey(parse_public_key(&identity.public_key()).unwrap()),
+        ];
+        let stanzas = wrap_file_key(&FILE_KEY, &recipients).unwrap();
+        assert_eq!(stanzas.len(), 2);
+
+        let by_password = Credentials::from_password("geheim");
+        assert_eq!(unwrap_file_key(&stanzas, &by_password).unwrap(), FILE_KEY);
+        let by_key = with_identity(same_identity(&identity));
+        assert_eq!(unwrap_file_key(&stanzas, &by_key).unwrap(), FILE_KEY);
+        // Falsches Passwort, aber passende Schlüsseldatei
+        let mixed = Credentials {
+            password: Some("falsch".into()),
+            identity: Some(identity),
+        };
+        assert_eq!(unwrap_file_key(&stanzas, &mixed).unwrap(), FILE_KEY);
+
+        let wrong = Credentials {
+            password: Some("falsch".into()),
+            identity: Some(Identity::generate()),
+        };
+        let err = unwrap_file_key(&stanzas, &wrong).unwrap_err();
+        assert!(
+            err.contains("falsches Passwort oder falsche Schlüsseldatei"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn no_recipients() {
+        assert!(wrap_file_key(&FILE_KEY, &[]).is_err());
+        assert!(Credentials::new("", None).is_err());
+        assert!(Credentials::new("", Some("")).is_err());
+    }
+
+    #[test]
+    fn malformed_public_keys() {
+        let identity = Identity::generate();
+        let valid = identity.public_key();
+        assert_eq!(
+            parse_public_key(&format!("  {}\n", valid))
+                .unwrap()
+                .as_bytes(),
+            PublicKey::from(&identity.secret).as_bytes()
+        );
+        let hex = &valid[PUBLIC_KEY_PREFIX.len()..];
+        for text in [
+            "",
+            hex,
+            &format!("nilo-key-{}", hex),
+            &valid[..valid.len() - 2],
+            &format!("{}00", valid),
+            &format!("{}zz", &valid[..valid.len() - 2]),
+        ] {
+            let err = parse_public_key(text).unwrap_err();
+            assert!(
+                err.starts_with("Ungültiger öffentlicher Schlüssel"),
+                "{}",
+                err
+            );
+        }
+        // Kleine Untergruppe: kein gemeinsames Geheimnis möglich
+        let zero = parse_public_key(&format!("{}{}", PUBLIC_KEY_PREFIX, "00".repeat(32))).unwrap();
+        assert!(wrap_file_key(&FILE_KEY, &[Recipient::PublicKey(zero)]).is_err());
+    }
+
+    #[test]
+    fn identity_file_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("nilo-recipients-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let identity = Identity::generate();
+        let path = dir.join("schluessel.txt");
+        fs::write(&path, identity.to_file_contents()).unwrap();
+        let loaded = Identity::load(&path).unwrap();
+        assert_eq!(loaded.public_key(), identity.public_key());
+
+        let broken = dir.join("kaputt.txt");
+        fs::write(&broken, "# nur ein Kommentar\n").unwrap();
+        assert!(Identity::load(&broken).is_err());
+        fs::write(&broken, format!("{}abcd\n", SECRET_KEY_PREFIX)).unwrap();
+        assert!(Identity::load(&broken).is_err());
+        assert!(Identity::load(&dir.join("fehlt.txt")).is_err());
+        assert!(Credentials::new("", Some(path.to_str().unwrap())).is_ok());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// Schreibt ein kleines Paket und liefert die Bytes.
+    fn package(recipients: &[Recipient]) -> Vec<u8> {
+        let manifest = PackageManifest {
+            niloshift_version: "test".into(),
+            source_user: "nutzer".into(),
+            machine_name: "rechner".into(),
+            exported_at: 0,
+            categories: Vec::new(),
+        };
+        let mut writer =
+            package_format::create_package_writer(Vec::new(), recipients, &manifest, None).unwrap();
+        writer.write_all(b"inhalt").unwrap();
+        writer.finish().unwrap()
+    }
+
+    /// Ersetzt den JSON-Kopf eines Pakets und passt die Kopflänge an.
+    fn rewrite_header(data: &[u8], edit: impl Fn(&mut serde_json::Value)) -> Vec<u8> {
+        let prefix = PACKAGE_MAGIC.len() + 2;
+        let len = u32::from_le_bytes(data[prefix..prefix + 4].try_into().unwrap()) as usize;
+        let mut header: serde_json::Value =
+            serde_json::from_slice(&data[prefix + 4..prefix + 4 + len]).unwrap();
+        edit(&mut header);
+        let json = serde_json::to_vec(&header).unwrap();
+        let mut out = data[..prefix].to_vec();
+        out.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        out.extend_from_slice(&json);
+        out.extend_from_slice(&data[prefix + 4 + len..]);
+        out
+    }
+
+    fn open_package(data: Vec<u8>, credentials: &Credentials) -> Result<Vec<u8>, String> {
+        let mut opened = package_format::open_package_reader(Cursor::new(data), credentials)?;
+        let mut plain = Vec::new();
+        opened
+            .content
+            .read_to_end(&mut plain)
+            .map_err(|e| e.to_string())?;
+        Ok(plain)
+    }
+
+    #[test]
+    fn tampered_stanza_is_rejected() {
+        let identity = Identity::generate();
+        let data = package(&[
+            Recipient::Password("geheim".into()),
+            Recipient::PublicKey(parse_public_key(&identity.public_key()).unwrap()),
+        ]);
+        let password = Credentials::from_password("geheim");
+        assert_eq!(open_package(data.clone(), &password).unwrap(), b"inhalt");
+
+        // Empfänger der Schlüsseldatei entfernt: der Passwort-Empfänger entpackt den
+        // Schlüssel noch, aber der Kopf passt nicht mehr zur AAD
+        let removed = rewrite_header(&data, |h| {
+            h["recipients"].as_array_mut().unwrap().truncate(1);
+        });
+        let err = open_package(removed, &password).unwrap_err();
+        assert!(err.contains("Entschlüsselung fehlgeschlagen"), "{}", err);
+
+        // Ephemerer Schlüssel durch einen anderen ersetzt
+        let other = PublicKey::from(&StaticSecret::random_from_rng(rand::rngs::OsRng));
+        let swapped = rewrite_header(&data, |h| {
+            h["recipients"][1]["ephemeral"] = to_hex(other.as_bytes()).into();
+        });
+        assert!(open_package(swapped.clone(), &password).is_err());
+        assert!(open_package(swapped, &with_identity(identity)).is_err());
+
+        // Verpackter Schlüssel verändert
+        let flipped = rewrite_header(&data, |h| {
+            let wrapped = h["recipients"][0]["wrapped_key"].as_str().unwrap();
+            let flipped = if wrapped.starts_with('0') { "1" } else { "0" };
+            h["recipients"][0]["wrapped_key"] = format!("{}{}", flipped, &wrapped[1..]).into();
+        });
+        assert!(open_package(flipped, &password).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Bericht über einen Export oder Import: welche Dateien übernommen, ausgelassen oder
+// wegen eines Fehlers nicht übernommen wurden. Der Bericht wird als JSON neben dem
+// Paket (Export) bzw. im Zielprofil (Import) gespeichert, die Zahlen gehen an die UI.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportEntry {
+    /// Pfad im Archiv.
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    /// Grund beim Auslassen bzw. Fehlermeldung des Betriebssystems.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferReport {
+    /// "export" oder "import".
+    pub operation: String,
+    pub package: String,
+    /// Sekunden seit 1970.
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub copied: Vec<ReportEntry>,
+    pub skipped: Vec<ReportEntry>,
+    pub failed: Vec<ReportEntry>,
+    /// Beim Import am Zielort schon vorhandene Dateien und wie der Konflikt gelöst wurde.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<ReportEntry>,
+}
+
+/// Ergebnis eines Exports oder Imports für die Oberfläche.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferSummary {
+    pub message: String,
+    pub copied: u64,
+    pub copied_bytes: u64,
+    pub skipped: u64,
+    pub failed: u64,
+    pub conflicts: u64,
+    /// Ort des gespeicherten Berichts, falls er geschrieben werden konnte.
+    pub report_path: Option<String>,
+    /// Kennung des Importprotokolls für `rollback_import`.
+    pub journal_id: Option<String>,
+}
+
+impl TransferReport {
+    pub fn new(operation: &str, package: &Path) -> Self {
+        TransferReport {
+            operation: operation.to_string(),
+            package: package.display().to_string(),
+            started_at: now_secs(),
+            finished_at: 0,
+            copied: Vec::new(),
+            skipped: Vec::new(),
+            failed: Vec::new(),
+            conflicts: Vec::new(),
+        }
+    }
+
+    pub fn copied(&mut self, path: &str, bytes: u64) {
+        self.copied.push(ReportEntry {
+            path: path.to_string(),
+            bytes: Some(bytes),
+            reason: None,
+        });
+    }
+
+    pub fn skipped(&mut self, path: &str, reason: impl ToString) {
+        self.skipped.push(ReportEntry {
+            path: path.to_string(),
+            bytes: None,
+            reason: Some(reason.to_string()),
+        });
+    }
+
+    pub fn failed(&mut self, path: &str, error: impl ToString) {
+        self.failed.push(ReportEntry {
+            path: path.to_string(),
+            bytes: None,
+            reason: Some(error.to_string()),
+        });
+    }
+
+    pub fn conflict(&mut self, path: &str, resolution: impl ToString) {
+        self.conflicts.push(ReportEntry {
+            path: path.to_string(),
+            bytes: None,
+            reason: Some(resolution.to_string()),
+        });
+    }
+
+    /// Speichert den Bericht und liefert die Zusammenfassung. Kann der Bericht nicht
+    /// geschrieben werden, fehlt nur `report_path` – der Vorgang selbst gilt trotzdem.
+    pub fn finish(mut self, message: String, path: &Path) -> TransferSummary {
+        self.finished_at = now_secs();
+        let report_path = match self.save(path) {
+            Ok(()) => Some(path.display().to_string()),
+            Err(e) => {
+                eprintln!("⚠️ {}", e);
+                None
+            }
+        };
+        TransferSummary {
+            message,
+            copied: self.copied.len() as u64,
+            copied_bytes: self.copied.iter().filter_map(|e| e.bytes).sum(),
+            skipped: self.skipped.len() as u64,
+            failed: self.failed.len() as u64,
+            conflicts: self.conflicts.len() as u64,
+            report_path,
+            journal_id: None,
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| format!("Bericht konnte nicht erstellt werden: {}", e))?;
+        fs::write(path, json).map_err(|e| {
+            format!(
+                "Bericht konnte nicht gespeichert werden ({}): {}",
+                path.display(),
+                e
+            )
+        })
+    }
+}
+
+/// Bericht eines Exports: `<Paket>.report.json` neben dem Paket.
+pub fn export_report_path(package: &Path) -> PathBuf {
+    let mut name = package.file_name().unwrap_or_default().to_os_string();
+    name.push(".report.json");
+    package.with_file_name(name)
+}
+
+/// Bericht eines Imports im Zielprofil unter `AppData/Local/NiloShift/reports`.
+pub fn import_report_path(user_base: &Path) -> PathBuf {
+    user_base
+        .join("AppData/Local/NiloShift/reports")
+        .join(format!("import-{}.json", now_secs()))
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
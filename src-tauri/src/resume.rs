@@ -0,0 +1,201 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::archive::IndexEntry;
+use crate::export_operations;
+use crate::journal;
+use crate::profiles;
+use crate::report::TransferReport;
+
+// Zwischenstände, damit ein unterbrochener Export oder Import (Ruhezustand, Stick
+// abgezogen, Absturz) dort weitermachen kann, wo er stehen geblieben ist.
+//
+// Export: `<Paket>.resume.json` neben dem Paket. Für einen Zwischenstand wird das
+// Archiv mit einer Füllung bis zur nächsten Blockgrenze aufgefüllt, der volle Block
+// versiegelt und das Paket auf den Datenträger geschrieben – erst danach die Datei.
+// Beim Fortsetzen wird das Paket auf die gespeicherte Länge gekürzt und ab dem
+// gespeicherten Block in einem neuen Abschnitt weiterverschlüsselt (siehe
+// `crypto::MAX_STREAM_SEGMENT`). Der Inhaltsschlüssel wird nicht gespeichert, sondern
+// mit dem Passwort erneut aus dem Paketkopf entpackt; Exporte nur an öffentliche
+// Schlüssel lassen sich daher nicht fortsetzen.
+//
+// Import: `resume.json` im Ordner des Importprotokolls (siehe `journal`). Gespeichert
+// wird, wie viele Einträge des Pakets verarbeitet sind; beim Fortsetzen werden sie
+// im Stream übersprungen und das Protokoll weitergeführt. Einträge nach dem letzten
+// Zwischenstand werden erneut verarbeitet und dann als identisch erkannt.
+
+/// Abstand zwischen zwei Zwischenständen.
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+const IMPORT_STATE_FILE: &str = "resume.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportState {
+    pub package: String,
+    pub selected_user: String,
+    /// Quellordner einer Offline-Migration (siehe `profiles::source_locator`).
+    #[serde(default)]
+    pub source_root: Option<String>,
+    pub volume_size: Option<u64>,
+    /// Geschriebene Bytes über alle Volumes.
+    pub length: u64,
+    /// Versiegelte Blöcke; der Klartext bis dahin endet an einer Eintragsgrenze.
+    pub chunks: u32,
+    /// Zuletzt verwendeter Abschnitt der Nonce.
+    pub segment: u8,
+    /// Vollständig im Paket enthaltene Dateien.
+    pub completed: Vec<IndexEntry>,
+    pub saved_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportState {
+    pub package: String,
+    pub user_base: PathBuf,
+    /// Vollständig verarbeitete Einträge des Pakets in Archivreihenfolge.
+    pub entries: u64,
+    pub restored: u64,
+    pub corrupt: u64,
+    pub failed: u64,
+    pub conflicts: u64,
+    /// Bericht bis zum Zwischenstand.
+    pub report: TransferReport,
+    pub saved_at: u64,
+}
+
+/// Zwischenstand eines Exports: `<Paket>.resume.json`.
+pub fn export_state_path(package: &Path) -> PathBuf {
+    let mut name = package.file_name().unwrap_or_default().to_os_string();
+    name.push(".resume.json");
+    package.with_file_name(name)
+}
+
+/// Zwischenstand eines Imports im Ordner seines Protokolls.
+pub fn import_state_path(journal_dir: &Path) -> PathBuf {
+    journal_dir.join(IMPORT_STATE_FILE)
+}
+
+/// Jüngstes Protokoll eines unterbrochenen Imports von `package` nach `user_base`.
+pub fn find_import(user_base: &Path, package: &Path) -> Option<String> {
+    let package = package.display().to_string();
+    journal::headers()
+        .into_iter()
+        .filter(|(header, dir)| {
+            header.user_base == user_base
+                && header.package == package
+                && import_state_path(dir).exists()
+        })
+        .max_by_key(|(header, _)| header.started_at)
+        .map(|(header, _)| header.id)
+}
+
+/// Verwirft die Zwischenstände früherer Importe von `package` nach `user_base`, wenn
+/// neu begonnen wird. Die Protokolle bleiben für das Zurücksetzen erhalten.
+pub fn discard_imports(user_base: &Path, package: &Path) {
+    let package = package.display().to_string();
+    for (header, dir) in journal::headers() {
+        if header.user_base == user_base && header.package == package {
+            remove(&import_state_path(&dir));
+        }
+    }
+}
+
+/// Speichert einen Zwischenstand. Die alte Datei wird erst ersetzt, wenn die neue
+/// vollständig geschrieben ist.
+pub fn save<T: Serialize>(path: &Path, state: &T) -> Result<(), String> {
+    let json = serde_json::to_vec(state)
+        .map_err(|e| format!("Zwischenstand konnte nicht erstellt werden: {}", e))?;
+    let mut tmp = path.as_os_str().to_os_string();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::File::create(&tmp)
+        .and_then(|mut file| {
+            file.write_all(&json)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp, path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            format!(
+                "Zwischenstand konnte nicht gespeichert werden ({}): {}",
+                path.display(),
+                e
+            )
+        })
+}
+
+/// Liest einen Zwischenstand; fehlende oder unlesbare Dateien ergeben `None`.
+pub fn load<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let data = fs::read(path).ok()?;
+    match serde_json::from_slice(&data) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            eprintln!("⚠️ Zwischenstand {} unlesbar: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+pub fn remove(path: &Path) {
+    let _ = fs::remove_file(path);
+}
+
+/// Unterbrochener Vorgang für die Oberfläche.
+#[derive(Debug, Serialize)]
+pub struct InterruptedJob {
+    pub package: String,
+    /// Bereits übernommene Dateien.
+    pub files: u64,
+    pub bytes: u64,
+    /// Zeitpunkt des letzten Zwischenstands (Sekunden seit 1970).
+    pub saved_at: u64,
+    /// Protokoll eines unterbrochenen Imports.
+    pub journal_id: Option<String>,
+}
+
+/// Unterbrochener Export an diesem Zielpfad, der mit `resume` fortgesetzt werden kann.
+#[tauri::command]
+pub async fn find_interrupted_export(
+    export_path: Option<String>,
+    selected_user: String,
+    source_root: Option<String>,
+) -> Result<Option<InterruptedJob>, String> {
+    let source_root = profiles::source_root(source_root);
+    let profile = profiles::source_profile(source_root.as_deref(), &selected_user)?;
+    let package = export_operations::output_path_for(export_path, &profile);
+    let state: Option<ExportState> = load(&export_state_path(&package));
+    Ok(state
+        .filter(|s| s.selected_user == selected_user && s.source_root == source_root)
+        .map(|s| InterruptedJob {
+            package: s.package,
+            files: s.completed.len() as u64,
+            bytes: s.completed.iter().map(|e| e.size).sum(),
+            saved_at: s.saved_at,
+            journal_id: None,
+        }))
+}
+
+/// Unterbrochener Import dieses Pakets in das Profil, der mit `resume` fortgesetzt
+/// werden kann.
+#[tauri::command]
+pub async fn find_interrupted_import(
+    package_path: String,
+    selected_user: String,
+) -> Result<Option<InterruptedJob>, String> {
+    let user_base = profiles::user_profile(&selected_user)?.base;
+    let Some(id) = find_import(&user_base, Path::new(&package_path)) else {
+        return Ok(None);
+    };
+    let state: Option<ImportState> = load(&import_state_path(&journal::journal_dir(&id)?));
+    Ok(state.map(|s| InterruptedJob {
+        package: s.package,
+        files: s.restored,
+        bytes: s.report.copied.iter().filter_map(|e| e.bytes).sum(),
+        saved_at: s.saved_at,
+        journal_id: Some(id),
+    }))
+}
@@ -0,0 +1,113 @@
+use std::path::{Component, Path, PathBuf};
+
+// Schattenkopie (Volume Shadow Copy) eines Laufwerks. Laufende Browser und Outlook
+// halten ihre Datenbanken offen – aus der Schattenkopie lassen sie sich trotzdem
+// lesen, und zwar in einem Zustand, der zu einem einzigen Zeitpunkt gehört.
+// Das Anlegen braucht Administratorrechte. Die Kopie wird beim Drop wieder gelöscht.
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub struct Snapshot {
+    id: String,
+    /// Wurzel des Laufwerks, z. B. `C:\`.
+    volume: PathBuf,
+    /// Gerätepfad der Kopie, z. B. `\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy5`.
+    device: PathBuf,
+}
+
+impl Snapshot {
+    /// Legt eine Schattenkopie des Laufwerks an, auf dem `path` liegt.
+    #[cfg(target_os = "windows")]
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let volume = volume_root(path)
+            .ok_or_else(|| format!("Kein Laufwerk für {} gefunden", path.display()))?;
+        let script = format!(
+            "$r = Invoke-CimMethod -ClassName Win32_ShadowCopy -MethodName Create \
+             -Arguments @{{Volume='{}'; Context='ClientAccessible'}}; \
+             if ($r.ReturnValue -ne 0) {{ Write-Error \"Fehlercode $($r.ReturnValue)\"; exit 1 }}; \
+             $s = Get-CimInstance Win32_ShadowCopy -Filter \"ID='$($r.ShadowID)'\"; \
+             Write-Output $r.ShadowID; Write-Output $s.DeviceObject",
+            volume.display()
+        );
+        let output = powershell(&script)?;
+        let mut lines = output.lines().map(str::trim).filter(|l| !l.is_empty());
+        match (lines.next(), lines.next()) {
+            (Some(id), Some(device)) => Ok(Snapshot {
+                id: id.to_string(),
+                volume,
+                device: PathBuf::from(device),
+            }),
+            _ => Err(format!(
+                "Schattenkopie konnte nicht angelegt werden: unerwartete Antwort {:?}",
+                output
+            )),
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn create(_path: &Path) -> Result<Self, String> {
+        Err("Schattenkopien sind nur unter Windows verfügbar".to_string())
+    }
+
+    /// Pfad derselben Datei in der Schattenkopie. Pfade auf anderen Laufwerken
+    /// werden unverändert zurückgegeben.
+    pub fn map(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(&self.volume) {
+            // Gerätepfade werden nicht normalisiert: jede Komponente einzeln anhängen,
+            // damit nur `\` als Trenner entsteht
+            Ok(rest) => rest.components().fold(self.device.clone(), |mut p, c| {
+                if let Component::Normal(name) = c {
+                    p.push(name);
+                }
+                p
+            }),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        #[cfg(target_os = "windows")]
+        {
+            let script = format!(
+                "Get-CimInstance Win32_ShadowCopy -Filter \"ID='{}'\" | Remove-CimInstance",
+                self.id
+            );
+            if let Err(e) = powershell(&script) {
+                eprintln!("⚠️ Schattenkopie {} nicht gelöscht: {}", self.id, e);
+            }
+        }
+    }
+}
+
+/// Laufwerkswurzel eines Pfads, z. B. `C:\` für `C:\Users\max`.
+#[cfg(target_os = "windows")]
+fn volume_root(path: &Path) -> Option<PathBuf> {
+    match path.components().next()? {
+        Component::Prefix(prefix) => {
+            let mut root = PathBuf::from(prefix.as_os_str());
+            root.push(Component::RootDir.as_os_str());
+            Some(root)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn powershell(script: &str) -> Result<String, String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("PowerShell konnte nicht gestartet werden: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(format!(
+            "Schattenkopie fehlgeschlagen (Administratorrechte?): {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
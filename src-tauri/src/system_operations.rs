@@ -1,42 +1,13 @@
 use std::fs;
-use std::path::{Path, PathBuf};
 
-#[tauri::command]
-pub async fn list_windows_profiles() -> Result<Vec<String>, String> {
-    #[cfg(not(target_os = "windows"))]
-    {
-        return Err("Nur auf Windows verfügbar".to_string());
-    }
+use crate::known_folders;
+use crate::profiles;
 
-    #[cfg(target_os = "windows")]
-    {
-        let users_dir = PathBuf::from(r"C:\\Users");
-        if !users_dir.exists() {
-            return Err("C\\Users nicht gefunden".to_string());
-        }
-        let mut profiles: Vec<String> = Vec::new();
-        let skip_names = [
-            "All Users",
-            "Default",
-            "Default User",
-            "Public",
-            "DefaultAppPool",
-            "WDAGUtilityAccount",
-            "Administrator",
-        ];
-        for entry in fs::read_dir(&users_dir).map_err(|e| e.to_string())? {
-            let entry = entry.map_err(|e| e.to_string())?;
-            let meta = entry.metadata().map_err(|e| e.to_string())?;
-            if meta.is_dir() {
-                let name = entry.file_name().to_string_lossy().to_string();
-                if !skip_names.iter().any(|s| s.eq_ignore_ascii_case(&name)) {
-                    profiles.push(name);
-                }
-            }
-        }
-        profiles.sort();
-        Ok(profiles)
-    }
+/// Benutzerprofile auf diesem Rechner oder im eingestellten Profilordner; mit
+/// `source_root` die einer fremden Windows-Installation, z. B. `E:\`.
+#[tauri::command]
+pub async fn list_windows_profiles(source_root: Option<String>) -> Result<Vec<String>, String> {
+    profiles::source_locator(source_root.as_deref())?.list_profiles()
 }
 
 #[derive(serde::Serialize)]
@@ -47,26 +18,30 @@ pub struct BrowserPresence {
 }
 
 #[tauri::command]
-pub async fn detect_browsers(selected_user: String) -> Result<BrowserPresence, String> {
-    #[cfg(not(target_os = "windows"))]
-    {
-        return Ok(BrowserPresence { chrome: true, edge: false, firefox: true });
-    }
+pub async fn detect_browsers(
+    selected_user: String,
+    source_root: Option<String>,
+) -> Result<BrowserPresence, String> {
+    let profile = profiles::source_profile(source_root.as_deref(), &selected_user)?;
+    let present =
+        |id: &str| known_folders::by_id(id).is_some_and(|folder| profile.folder(folder).is_dir());
+    Ok(BrowserPresence {
+        chrome: present("chrome"),
+        edge: present("edge"),
+        firefox: present("firefox"),
+    })
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        let base = PathBuf::from(r"C:\\Users").join(&selected_user);
-        let chrome = base.join("AppData/Local/Google/Chrome/User Data");
-        let edge = base.join("AppData/Local/Microsoft/Edge/User Data");
-        let firefox = base.join("AppData/Roaming/Mozilla/Firefox");
-        Ok(BrowserPresence {
-            chrome: path_exists_dir(&chrome),
-            edge: path_exists_dir(&edge),
-            firefox: path_exists_dir(&firefox),
+/// Rechnername für das Paketmanifest.
+pub fn machine_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| {
+            fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|s| s.trim().to_string())
         })
-    }
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "Unbekannt".to_string())
 }
-
-fn path_exists_dir(p: &Path) -> bool {
-    p.exists() && p.is_dir()
-}
\ No newline at end of file
@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::conflict::{ConflictPolicy, ConflictResolver};
+use crate::export_operations::{self, ExportOptions};
+use crate::filter_rules::RuleSet;
+use crate::import_operations::{self, FileOutcome, RestorePlan, RestoreStats, SelectedItems};
+use crate::jobs::{self, JobHandle};
+use crate::journal::{self, ImportJournal};
+use crate::package_format::CategorySummary;
+use crate::profiles::{self, UserProfile};
+use crate::progress::{emit_progress, ProgressSink};
+use crate::report::{self, TransferReport, TransferSummary};
+use crate::snapshot::Snapshot;
+
+// Direkte Übertragung von einem Quellprofil in ein Zielprofil ohne Paket: Auswahl und
+// Filterregeln wie beim Export, Zielorte, Konfliktregel und Importprotokoll wie beim
+// Import. Die Quelle ist ein anderes Konto dieses Rechners oder ein angeschlossener
+// alter Datenträger (`source_root`).
+
+const PROGRESS_EVENT: &str = "transfer-progress";
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_transfer_command(
+    app: tauri::AppHandle,
+    options: ExportOptions,
+    source_user: String,
+    source_root: Option<String>,
+    selected_user: String,
+    custom_targets: Option<BTreeMap<String, String>>,
+    conflict_policy: Option<ConflictPolicy>,
+    use_snapshot: Option<bool>,
+    job_id: Option<String>,
+) -> Result<TransferSummary, String> {
+    run_transfer(
+        &ProgressSink::from(app),
+        options,
+        source_user,
+        source_root,
+        selected_user,
+        custom_targets,
+        conflict_policy,
+        use_snapshot,
+        job_id,
+    )
+}
+
+/// Übertragung ohne Fenster, auch für `niloshift-cli`; Parameter wie
+/// `start_transfer_command`.
+#[allow(clippy::too_many_arguments)]
+pub fn run_transfer(
+    sink: &ProgressSink,
+    options: ExportOptions,
+    source_user: String,
+    source_root: Option<String>,
+    selected_user: String,
+    custom_targets: Option<BTreeMap<String, String>>,
+    conflict_policy: Option<ConflictPolicy>,
+    use_snapshot: Option<bool>,
+    job_id: Option<String>,
+) -> Result<TransferSummary, String> {
+    let source_root = profiles::source_root(source_root);
+    let source = profiles::source_profile(source_root.as_deref(), &source_user)?;
+    let target = profiles::user_profile(&selected_user)?;
+    if same_dir(&source.base, &target.base) {
+        return Err("Quell- und Zielprofil sind identisch".to_string());
+    }
+    eprintln!(
+        "🔀 Übertragung von {} nach {}",
+        source.base.display(),
+        target.base.display()
+    );
+
+    // Pausieren/Abbrechen über pause_job/cancel_job
+    let job = jobs::register(job_id.as_deref().unwrap_or("transfer"))?;
+    let start = Instant::now();
+    emit_progress(sink, PROGRESS_EVENT, start, 0, 100, "Vorbereiten");
+
+    // Das Manifest wird nicht gespeichert, es liefert nur Kategorien und Dateianzahl
+    let manifest = export_operations::new_manifest(&source, &options)?;
+    if manifest.categories.is_empty() {
+        return Err("Keine Inhalte zum Übertragen ausgewählt".to_string());
+    }
+    let total: u64 = manifest.categories.iter().map(|c| c.files).sum();
+    let plan = RestorePlan::new(
+        &target,
+        SelectedItems::from_ids(manifest.categories.iter().map(|c| c.id.as_str())),
+        Some(&manifest),
+        &custom_targets.unwrap_or_default(),
+    )?;
+    let conflicts = ConflictResolver::new(conflict_policy.unwrap_or_default(), &target.base);
+    let snapshot = if use_snapshot.unwrap_or(false) {
+        emit_progress(sink, PROGRESS_EVENT, start, 0, 100, "Schattenkopie");
+        Some(Snapshot::create(&source.base)?)
+    } else {
+        None
+    };
+
+    // Wie beim Import wird jede Änderung am Zielprofil protokolliert
+    let mut journal = ImportJournal::create(&target.base, &source.base)?;
+    let mut report = TransferReport::new("transfer", &source.base);
+    let mut run = TransferRun {
+        sink,
+        job: job.handle(),
+        plan: &plan,
+        conflicts: &conflicts,
+        journal: &mut journal,
+        stats: RestoreStats::default(),
+        report: &mut report,
+        start,
+        processed: 0,
+        total,
+    };
+    emit_progress(sink, PROGRESS_EVENT, start, 0, total, "Übertragen");
+    let result = manifest
+        .categories
+        .iter()
+        .try_for_each(|category| run.copy_category(&source, category, snapshot.as_ref()));
+    let stats = run.stats;
+    if let Err(e) = result {
+        let journal_id = journal.id.clone();
+        drop(journal);
+        // Abgebrochen: Zielprofil wieder in den Zustand vor der Übertragung bringen
+        if job.handle().is_cancelled() {
+            emit_progress(sink, PROGRESS_EVENT, start, 0, total, "Abgebrochen");
+            let rollback = journal::rollback(&journal_id)?;
+            return Err(format!("Übertragung abgebrochen – {}", rollback.message));
+        }
+        return Err(format!(
+            "{} – die Übertragung kann mit dem Importprotokoll {} zurückgesetzt werden",
+            e, journal_id
+        ));
+    }
+
+    eprintln!(
+        "Übertragene Dateien: {}, fehlgeschlagen: {}, Konflikte: {}",
+        stats.restored, stats.failed, stats.conflicts
+    );
+    emit_progress(sink, PROGRESS_EVENT, start, total, total, "Fertig");
+    let mut notes = Vec::new();
+    if stats.failed > 0 {
+        notes.push(format!(
+            "{} Dateien konnten nicht übertragen werden",
+            stats.failed
+        ));
+    }
+    if stats.conflicts > 0 {
+        notes.push(format!(
+            "{} Dateien waren bereits vorhanden",
+            stats.conflicts
+        ));
+    }
+    let message = if notes.is_empty() {
+        "Übertragung abgeschlossen".to_string()
+    } else {
+        format!("Übertragung abgeschlossen ({})", notes.join(", "))
+    };
+    let mut summary = report.finish(message, &report::import_report_path(&target.base));
+    summary.journal_id = Some(journal.id);
+    Ok(summary)
+}
+
+/// Zustand einer laufenden Übertragung.
+struct TransferRun<'a> {
+    sink: &'a ProgressSink,
+    job: &'a JobHandle,
+    plan: &'a RestorePlan,
+    conflicts: &'a ConflictResolver,
+    journal: &'a mut ImportJournal,
+    stats: RestoreStats,
+    report: &'a mut TransferReport,
+    start: Instant,
+    processed: u64,
+    total: u64,
+}
+
+impl TransferRun<'_> {
+    fn copy_category(
+        &mut self,
+        source: &UserProfile,
+        category: &CategorySummary,
+        snapshot: Option<&Snapshot>,
+    ) -> Result<(), String> {
+        self.job.checkpoint()?;
+        let src = export_operations::category_source(source, category);
+        // Benutzerdefinierte Pfade außerhalb des Profils liegen vorher wie nachher am selben Ort
+        if self
+            .plan
+            .target(&category.path)
+            .is_some_and(|target| same_dir(&src, &target))
+        {
+            self.report
+                .skipped(&category.path, "Quell- und Zielordner sind identisch");
+            return Ok(());
+        }
+        let src = match snapshot {
+            Some(snapshot) => snapshot.map(&src),
+            None => src,
+        };
+        if !src.exists() || export_operations::is_symlink(&src) {
+            self.report.skipped(
+                &category.path,
+                format!("Ordner {} nicht vorhanden", src.display()),
+            );
+            return Ok(());
+        }
+        let rules = export_operations::category_rules(category)?;
+        self.copy_dir(&src, &category.path, "", &rules)
+    }
+
+    /// Überträgt einen Quellordner rekursiv. Pfade im Bericht und für die Zielorte
+    /// sind dieselben wie im Archiv eines Exports.
+    fn copy_dir(
+        &mut self,
+        src_dir: &Path,
+        prefix: &str,
+        rel: &str,
+        rules: &RuleSet,
+    ) -> Result<(), String> {
+        let entries = match fs::read_dir(src_dir) {
+            Ok(i) => i,
+            Err(e) => {
+                self.report.failed(prefix, e);
+                return Ok(());
+            }
+        };
+        for entry in entries.flatten() {
+            self.job.checkpoint()?;
+            let path = entry.path();
+            if export_operations::is_symlink(&path) {
+                continue;
+            }
+            let name = entry.file_name();
+            let child_rel = export_operations::join_rel(rel, &name);
+            let archive_path = format!("{}/{}", prefix, name.to_string_lossy());
+            if path.is_dir() {
+                if let Some(reason) = rules.check_dir(&child_rel) {
+                    self.report.skipped(&archive_path, reason);
+                    continue;
+                }
+                if let Some(target) = self.plan.target(&archive_path) {
+                    let _ = self.journal.create_dir_all(&target);
+                }
+                self.copy_dir(&path, &archive_path, &child_rel, rules)?;
+                continue;
+            }
+            let meta = entry.metadata().ok();
+            let size = meta.as_ref().map(|m| m.len()).unwrap_or(0);
+            let modified = meta.as_ref().and_then(export_operations::modified_secs);
+            if let Some(reason) = rules.check_file(&child_rel, size, modified) {
+                self.report.skipped(&archive_path, reason);
+                continue;
+            }
+            self.processed = self.processed.saturating_add(1);
+            let Some(target) = self.plan.target(&archive_path) else {
+                continue;
+            };
+            let outcome = match export_operations::open_with_retry(&path) {
+                // Lesefehler der Quelle gelten nur für diese Datei, die vorhandene bleibt
+                Ok(mut file) => import_operations::restore_file(
+                    &target,
+                    modified,
+                    self.conflicts,
+                    self.journal,
+                    |out| {
+                        Ok(match io::copy(&mut file, out) {
+                            Ok(bytes) => FileOutcome::Restored(bytes),
+                            Err(e) => FileOutcome::Failed(e),
+                        })
+                    },
+                )?,
+                Err(e) if export_operations::is_locked(&e) => FileOutcome::Failed(io::Error::new(
+                    e.kind(),
+                    format!("{} (von einem laufenden Programm gesperrt)", e),
+                )),
+                Err(e) => FileOutcome::Failed(e),
+            };
+            self.stats.record(self.report, &archive_path, outcome);
+            emit_progress(
+                self.sink,
+                PROGRESS_EVENT,
+                self.start,
+                self.processed,
+                self.total,
+                "Übertragen",
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Derselbe Ordner, auch wenn die Pfade unterschiedlich geschrieben sind.
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
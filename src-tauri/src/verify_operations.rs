@@ -0,0 +1,180 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read};
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+use zip::read::read_zipfile_from_stream;
+
+use crate::archive::{ArchiveEntry, ArchiveReader};
+use crate::import_operations::CountingReader;
+use crate::package_format::{self, to_hex};
+use crate::progress::{emit_progress, ProgressSink};
+use crate::recipients::Credentials;
+use crate::volumes::VolumeSet;
+
+const PROGRESS_EVENT: &str = "verify-progress";
+
+/// Ergebnis der Paketprüfung.
+#[derive(Serialize, Clone, Default)]
+pub struct VerifyReport {
+    /// Keine beschädigten, fehlenden oder zusätzlichen Dateien gefunden.
+    ok: bool,
+    /// Das Paket enthält Prüfsummen je Datei (ältere Pakete nicht).
+    has_checksums: bool,
+    files_checked: u64,
+    bytes_checked: u64,
+    /// Dateien, deren Inhalt nicht zur gespeicherten Prüfsumme passt.
+    corrupt: Vec<String>,
+    /// Im Index aufgeführte Dateien, die im Archiv fehlen.
+    missing: Vec<String>,
+    /// Dateien im Archiv, die nicht im Index stehen.
+    extra: Vec<String>,
+    /// Dateien, die schon beim Export nicht vollständig gelesen werden konnten.
+    incomplete: Vec<String>,
+}
+
+/// Entschlüsselt ein Paket vollständig und prüft jede Datei gegen ihre Prüfsumme,
+/// ohne etwas auf die Festplatte zu schreiben.
+#[tauri::command]
+pub async fn verify_package(
+    app: tauri::AppHandle,
+    package_path: String,
+    password: String,
+    identity_path: Option<String>,
+) -> Result<VerifyReport, String> {
+    run_verify(
+        &ProgressSink::from(app),
+        package_path,
+        password,
+        identity_path,
+    )
+}
+
+/// Prüfung ohne Fenster, auch für `niloshift-cli`; Parameter wie `verify_package`.
+pub fn run_verify(
+    sink: &ProgressSink,
+    package_path: String,
+    password: String,
+    identity_path: Option<String>,
+) -> Result<VerifyReport, String> {
+    eprintln!("=== PRÜFUNG START ===");
+    let volumes = VolumeSet::discover(&PathBuf::from(package_path))?;
+
+    let start = Instant::now();
+    emit_progress(sink, PROGRESS_EVENT, start, 0, 100, "Entschlüsseln");
+
+    let package_len = volumes.total_len();
+    let (file, bytes_read) = CountingReader::new(volumes.open()?);
+    let credentials = Credentials::new(&password, identity_path.as_deref())?;
+    let package = package_format::open_package_reader(file, &credentials)?;
+    volumes.check_complete(package.volume_size)?;
+    let mut reader = io::BufReader::new(package.content);
+
+    let progress = || {
+        let done = bytes_read.load(Ordering::Relaxed);
+        emit_progress(
+            sink,
+            PROGRESS_EVENT,
+            start,
+            (done.saturating_mul(100) / package_len.max(1)).min(99),
+            100,
+            "Prüfen",
+        );
+    };
+
+    let is_zip = reader
+        .fill_buf()
+        .map_err(|e| e.to_string())?
+        .starts_with(b"PK\x03\x04");
+    let mut report = if is_zip {
+        verify_zip_stream(&mut reader, progress)?
+    } else {
+        verify_archive_stream(reader, progress)?
+    };
+    report.ok = report.corrupt.is_empty() && report.missing.is_empty() && report.extra.is_empty();
+
+    eprintln!(
+        "Geprüft: {} Dateien, beschädigt: {}, fehlend: {}, zusätzlich: {}",
+        report.files_checked,
+        report.corrupt.len(),
+        report.missing.len(),
+        report.extra.len()
+    );
+    eprintln!("=== PRÜFUNG ENDE ===");
+    emit_progress(sink, PROGRESS_EVENT, start, 100, 100, "Fertig");
+    Ok(report)
+}
+
+fn verify_archive_stream<R: Read>(
+    reader: R,
+    mut progress: impl FnMut(),
+) -> Result<VerifyReport, String> {
+    let mut archive = ArchiveReader::new(reader).map_err(|e| e.to_string())?;
+    let mut report = VerifyReport::default();
+    // Pfad -> berechnete Prüfsumme, zum Abgleich mit dem Index am Ende
+    let mut seen: HashMap<String, String> = HashMap::new();
+    while let Some(entry) = archive.next_entry().map_err(|e| e.to_string())? {
+        if let ArchiveEntry::File(header) = entry {
+            let data = archive.skip_data().map_err(|e| e.to_string())?;
+            report.files_checked += 1;
+            report.bytes_checked += data.size;
+            if !data.complete {
+                report.incomplete.push(header.path);
+            } else {
+                if !data.hash_ok() {
+                    report.corrupt.push(header.path.clone());
+                }
+                seen.insert(header.path, to_hex(&data.sha256));
+            }
+            progress();
+        }
+    }
+
+    if let Some(index) = archive.index() {
+        report.has_checksums = true;
+        for item in index {
+            match seen.remove(&item.path) {
+                None => report.missing.push(item.path.clone()),
+                Some(hash) if hash != item.sha256 => {
+                    if !report.corrupt.contains(&item.path) {
+                        report.corrupt.push(item.path.clone());
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        report.extra.extend(seen.into_keys());
+        report.extra.sort();
+    }
+    Ok(report)
+}
+
+/// Ältere Pakete enthalten ein ZIP ohne eigene Prüfsummen; hier prüft das
+/// zip-Crate beim Lesen die CRC32 jedes Eintrags.
+fn verify_zip_stream<R: Read>(
+    reader: &mut R,
+    mut progress: impl FnMut(),
+) -> Result<VerifyReport, String> {
+    let mut report = VerifyReport::default();
+    loop {
+        let mut file = match read_zipfile_from_stream(reader) {
+            Ok(Some(file)) => file,
+            Ok(None) => break,
+            Err(e) => return Err(format!("ZIP fehlerhaft: {}", e)),
+        };
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+        match io::copy(&mut file, &mut io::sink()) {
+            Ok(n) => report.bytes_checked += n,
+            // Falsche CRC meldet das zip-Crate als ErrorKind::Other
+            Err(e) if e.kind() == io::ErrorKind::Other => report.corrupt.push(name),
+            Err(e) => return Err(format!("{}: {}", name, e)),
+        }
+        report.files_checked += 1;
+        progress();
+    }
+    Ok(report)
+}
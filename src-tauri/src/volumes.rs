@@ -0,0 +1,416 @@
+use std::fs;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+// Aufgeteilte Pakete: Die Paketdatei wird fortlaufend in Volumes gleicher Größe
+// geschrieben (`profil.nilo.001`, `profil.nilo.002`, …). Aneinandergehängt ergeben
+// sie wieder das vollständige Paket. Das letzte Volume ist immer kleiner als die
+// Volume-Größe – so lässt sich erkennen, ob am Ende ein Volume fehlt.
+
+/// Kleinste erlaubte Volume-Größe.
+pub const MIN_VOLUME_SIZE: u64 = 1024 * 1024;
+
+/// Name des Volumes `number` (ab 1) zu einem Paketpfad.
+pub fn volume_path(base: &Path, number: u32) -> PathBuf {
+    let mut name = base.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{:03}", number));
+    base.with_file_name(name)
+}
+
+/// Schreibt ein Paket in eine einzelne Datei oder aufgeteilt in Volumes.
+pub struct VolumeWriter {
+    base: PathBuf,
+    volume_size: Option<u64>,
+    current: Option<BufWriter<fs::File>>,
+    current_len: u64,
+    written: Vec<PathBuf>,
+}
+
+impl VolumeWriter {
+    /// Ohne `volume_size` entsteht genau die Datei `path`, sonst `path.001`, `path.002`, …
+    pub fn create(path: &Path, volume_size: Option<u64>) -> io::Result<Self> {
+        if let Some(size) = volume_size {
+            if size < MIN_VOLUME_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Volume-Größe muss mindestens {} MB betragen",
+                        MIN_VOLUME_SIZE / 1024 / 1024
+                    ),
+                ));
+            }
+            // Volumes eines früheren Exports mit gleichem Namen entfernen, sonst
+            // würden sie beim Import für Teile dieses Pakets gehalten
+            remove_volumes(path);
+        }
+        let mut writer = VolumeWriter {
+            base: path.to_path_buf(),
+            volume_size,
+            current: None,
+            current_len: 0,
+            written: Vec::new(),
+        };
+        writer.next_volume()?;
+        Ok(writer)
+    }
+
+    /// Setzt ein unterbrochenes Paket fort: Die ersten `len` Bytes bleiben erhalten,
+    /// alles dahinter (auch spätere Volumes) wird verworfen.
+    pub fn resume(path: &Path, volume_size: Option<u64>, len: u64) -> io::Result<Self> {
+        let (count, current_len) = match volume_size {
+            // Ein exakt volles Volume bleibt aktuell, das nächste entsteht beim Schreiben
+            Some(size) if len > 0 => ((len - 1) / size + 1, (len - 1) % size + 1),
+            Some(_) => (1, 0),
+            None => (1, len),
+        };
+        let written: Vec<PathBuf> = match volume_size {
+            Some(_) => (1..=count as u32).map(|n| volume_path(path, n)).collect(),
+            None => vec![path.to_path_buf()],
+        };
+        let last = written.last().expect("mindestens ein Volume");
+        let file = fs::OpenOptions::new().write(true).open(last)?;
+        if file.metadata()?.len() < current_len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("{} ist kürzer als erwartet", file_name(last)),
+            ));
+        }
+        file.set_len(current_len)?;
+        let mut file = BufWriter::new(file);
+        file.seek(SeekFrom::End(0))?;
+        if volume_size.is_some() {
+            let mut number = count as u32 + 1;
+            while fs::remove_file(volume_path(path, number)).is_ok() {
+                number += 1;
+            }
+        }
+        Ok(VolumeWriter {
+            base: path.to_path_buf(),
+            volume_size,
+            current: Some(file),
+            current_len,
+            written,
+        })
+    }
+
+    /// Insgesamt geschriebene Bytes über alle Volumes.
+    pub fn position(&self) -> u64 {
+        match self.volume_size {
+            Some(size) => (self.written.len() as u64 - 1) * size + self.current_len,
+            None => self.current_len,
+        }
+    }
+
+    /// Schreibt alles bis hierher dauerhaft auf den Datenträger.
+    pub fn sync(&mut self) -> io::Result<()> {
+        if let Some(current) = self.current.as_mut() {
+            current.flush()?;
+            current.get_ref().sync_all()?;
+        }
+        Ok(())
+    }
+
+    fn next_volume(&mut self) -> io::Result<()> {
+        if let Some(mut current) = self.current.take() {
+            current.flush()?;
+            // Frühere Volumes müssen für einen Zwischenstand schon auf dem Datenträger sein
+            current.get_ref().sync_all()?;
+        }
+        let path = match self.volume_size {
+            Some(_) => volume_path(&self.base, self.written.len() as u32 + 1),
+            None => self.base.clone(),
+        };
+        self.current = Some(BufWriter::new(fs::File::create(&path)?));
+        self.current_len = 0;
+        self.written.push(path);
+        Ok(())
+    }
+
+    /// Schließt das letzte Volume ab und liefert alle geschriebenen Dateien.
+    pub fn finish(mut self) -> io::Result<Vec<PathBuf>> {
+        // Ein exakt volles letztes Volume bekommt ein leeres Folge-Volume, damit
+        // "letztes Volume kleiner als die Volume-Größe" immer gilt
+        if self
+            .volume_size
+            .is_some_and(|size| self.current_len == size)
+        {
+            self.next_volume()?;
+        }
+        if let Some(mut current) = self.current.take() {
+            current.flush()?;
+            current.get_ref().sync_all()?;
+        }
+        Ok(self.written)
+    }
+}
+
+impl Write for VolumeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut take = buf.len();
+        if let Some(size) = self.volume_size {
+            if self.current_len == size {
+                self.next_volume()?;
+            }
+            take = take.min((size - self.current_len) as usize);
+        }
+        let current = self
+            .current
+            .as_mut()
+            .ok_or_else(|| io::Error::other("Volume ist bereits geschlossen"))?;
+        let n = current.write(&buf[..take])?;
+        self.current_len += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.current.as_mut() {
+            Some(current) => current.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Entfernt ein Paket samt aller nummerierten Volumes (z. B. nach Abbruch).
+pub fn remove_volumes(path: &Path) {
+    let _ = fs::remove_file(path);
+    let mut number = 1;
+    while fs::remove_file(volume_path(path, number)).is_ok() {
+        number += 1;
+    }
+}
+
+/// Die Dateien eines Pakets – eine einzelne Datei oder alle Volumes in Reihenfolge.
+pub struct VolumeSet {
+    base: PathBuf,
+    paths: Vec<PathBuf>,
+    sizes: Vec<u64>,
+}
+
+impl VolumeSet {
+    /// Ermittelt zu einer Paketdatei oder einem beliebigen Volume den ganzen Satz.
+    /// Fehlende Volumes in der Mitte werden dabei namentlich gemeldet.
+    pub fn discover(path: &Path) -> Result<Self, String> {
+        let base = match volume_number(path) {
+            Some(_) => path.with_extension(""),
+            None if !path.exists() && volume_path(path, 1).exists() => path.to_path_buf(),
+            None => {
+                let len = fs::metadata(path)
+                    .map_err(|_| "Paketdatei nicht gefunden".to_string())?
+                    .len();
+                return Ok(VolumeSet {
+                    base: path.to_path_buf(),
+                    paths: vec![path.to_path_buf()],
+                    sizes: vec![len],
+                });
+            }
+        };
+
+        // Höchste vorhandene Nummer im Ordner suchen
+        let dir = match base.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let base_name = base
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let mut highest = 0u32;
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let number = name
+                    .strip_prefix(&base_name)
+                    .and_then(|rest| rest.strip_prefix('.'))
+                    .filter(|n| n.len() >= 3 && n.chars().all(|c| c.is_ascii_digit()))
+                    .and_then(|n| n.parse::<u32>().ok());
+                if let Some(number) = number {
+                    highest = highest.max(number);
+                }
+            }
+        }
+        if highest == 0 {
+            return Err("Paketdatei nicht gefunden".to_string());
+        }
+
+        let mut paths = Vec::new();
+        let mut sizes = Vec::new();
+        let mut missing = Vec::new();
+        for number in 1..=highest {
+            let volume = volume_path(&base, number);
+            match fs::metadata(&volume) {
+                Ok(meta) => {
+                    paths.push(volume);
+                    sizes.push(meta.len());
+                }
+                Err(_) => missing.push(file_name(&volume)),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(format!(
+                "Paket ist unvollständig, es fehlt: {}",
+                missing.join(", ")
+            ));
+        }
+        Ok(VolumeSet { base, paths, sizes })
+    }
+
+    pub fn is_split(&self) -> bool {
+        self.paths.first() != Some(&self.base)
+    }
+
+    pub fn total_len(&self) -> u64 {
+        self.sizes.iter().sum()
+    }
+
+    /// Prüft die Volumes gegen die Volume-Größe aus dem Paketkopf. Erkennt
+    /// abgeschnittene Volumes und fehlende Volumes am Ende des Satzes.
+    pub fn check_complete(&self, volume_size: Option<u64>) -> Result<(), String> {
+        let Some(size) = volume_size else {
+            return Ok(());
+        };
+        if !self.is_split() {
+            // Wieder zu einer Datei zusammengefügt – Abschneiden erkennt die Entschlüsselung
+            return Ok(());
+        }
+        let last = self.paths.len() - 1;
+        for (i, (path, len)) in self.paths.iter().zip(&self.sizes).enumerate() {
+            if i < last && *len != size {
+                return Err(format!(
+                    "Volume {} ist unvollständig ({} von {} Bytes)",
+                    file_name(path),
+                    len,
+                    size
+                ));
+            }
+        }
+        if self.sizes[last] >= size {
+            return Err(format!(
+                "Paket ist unvollständig, es fehlt: {}",
+                file_name(&volume_path(&self.base, self.paths.len() as u32 + 1))
+            ));
+        }
+        Ok(())
+    }
+
+    /// Liest alle Volumes nacheinander wie eine einzige Datei.
+    pub fn open(&self) -> Result<VolumeReader, String> {
+        let first = fs::File::open(&self.paths[0])
+            .map_err(|e| format!("Konnte Paketdatei nicht lesen: {}", e))?;
+        Ok(VolumeReader {
+            paths: self.paths.clone(),
+            next: 1,
+            current: first,
+        })
+    }
+}
+
+pub struct VolumeReader {
+    paths: Vec<PathBuf>,
+    next: usize,
+    current: fs::File,
+}
+
+impl Read for VolumeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.current.read(buf)?;
+            if n > 0 || buf.is_empty() || self.next >= self.paths.len() {
+                return Ok(n);
+            }
+            let path = &self.paths[self.next];
+            self.current = fs::File::open(path).map_err(|e| {
+                io::Error::new(e.kind(), format!("Volume {}: {}", file_name(path), e))
+            })?;
+            self.next += 1;
+        }
+    }
+}
+
+/// Nummer eines Volumes anhand der Endung (`.001`), sonst `None`.
+fn volume_number(path: &Path) -> Option<u32> {
+    let ext = path.extension()?.to_str()?;
+    if ext.len() >= 3 && ext.chars().all(|c| c.is_ascii_digit()) {
+        ext.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Paket aus drei Volumes: zwei volle und ein halbes.
+    fn split_package(name: &str) -> (PathBuf, Vec<u8>) {
+        let dir =
+            std::env::temp_dir().join(format!("nilo-volumes-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("p.nilo");
+        let data: Vec<u8> = (0..MIN_VOLUME_SIZE * 5 / 2)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let mut writer = VolumeWriter::create(&path, Some(MIN_VOLUME_SIZE)).unwrap();
+        writer.write_all(&data).unwrap();
+        assert_eq!(writer.finish().unwrap().len(), 3);
+        (path, data)
+    }
+
+    fn check(path: &Path) -> Result<(), String> {
+        VolumeSet::discover(path)?.check_complete(Some(MIN_VOLUME_SIZE))
+    }
+
+    #[test]
+    fn volumes_roundtrip() {
+        let (path, data) = split_package("roundtrip");
+        // Jedes Volume findet den ganzen Satz
+        let volumes = VolumeSet::discover(&volume_path(&path, 2)).unwrap();
+        volumes.check_complete(Some(MIN_VOLUME_SIZE)).unwrap();
+        let mut read = Vec::new();
+        volumes.open().unwrap().read_to_end(&mut read).unwrap();
+        assert_eq!(read, data);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn middle_volume_missing() {
+        let (path, _) = split_package("missing");
+        fs::remove_file(volume_path(&path, 2)).unwrap();
+        let err = check(&path).unwrap_err();
+        assert!(err.contains("p.nilo.002"), "{}", err);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn middle_volume_short() {
+        let (path, _) = split_package("short");
+        let volume = fs::OpenOptions::new()
+            .write(true)
+            .open(volume_path(&path, 2))
+            .unwrap();
+        volume.set_len(MIN_VOLUME_SIZE - 1).unwrap();
+        let err = check(&path).unwrap_err();
+        assert!(err.contains("p.nilo.002 ist unvollständig"), "{}", err);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn last_volume_missing() {
+        let (path, _) = split_package("last");
+        fs::remove_file(volume_path(&path, 3)).unwrap();
+        let err = check(&path).unwrap_err();
+        assert!(err.contains("p.nilo.003"), "{}", err);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -4,6 +4,8 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { getCurrentWindow, ProgressBarStatus } from '@tauri-apps/api/window';
 import { isPermissionGranted, requestPermission, sendNotification } from '@tauri-apps/plugin-notification';
+import { InterruptedJob, TransferSummary, describeInterrupted, describeSummary } from './transferSummary';
+import JobControls from './JobControls';
 
 interface ExportOptions {
   desktop: boolean;
@@ -12,12 +14,52 @@ interface ExportOptions {
   videos: boolean;
   music: boolean;
   downloads: boolean;
+  favorites: boolean;
+  contacts: boolean;
+  saved_games: boolean;
+  links: boolean;
+  objects_3d: boolean;
   chrome: boolean;
   edge: boolean;
   firefox: boolean;
   outlook_signatures: boolean;
 }
 
+interface CustomPath {
+  source: string;
+  label: string;
+  restore: string;
+}
+
+interface ExportPreview {
+  categories: {
+    id: string;
+    label: string;
+    files: number;
+    bytes: number;
+    largest_files: { path: string; bytes: number }[];
+    skipped: { path: string; reason: string; is_dir: boolean }[];
+    skipped_count: number;
+  }[];
+  total_files: number;
+  total_bytes: number;
+  estimated_size: number;
+  free_space: number | null;
+  warnings: string[];
+}
+
+interface RunningApp {
+  id: string;
+  label: string;
+  processes: { pid: number; name: string }[];
+}
+
+const formatBytes = (bytes: number) => {
+  if (bytes >= 1024 ** 3) return `${(bytes / 1024 ** 3).toFixed(1)} GB`;
+  if (bytes >= 1024 ** 2) return `${(bytes / 1024 ** 2).toFixed(1)} MB`;
+  return `${Math.ceil(bytes / 1024)} KB`;
+};
+
 function ExportPage() {
   const navigate = useNavigate();
   const [exportOptions, setExportOptions] = useState<ExportOptions>({
@@ -27,23 +69,69 @@ function ExportPage() {
     videos: false,
     music: false,
     downloads: false,
+    favorites: false,
+    contacts: false,
+    saved_games: false,
+    links: false,
+    objects_3d: false,
     chrome: true,
     edge: false,
     firefox: false,
     outlook_signatures: false,
   });
+  const [customPaths, setCustomPaths] = useState<CustomPath[]>([]);
+  const [excludePatterns, setExcludePatterns] = useState('');
+  const [maxFileSizeMb, setMaxFileSizeMb] = useState('');
+  const [extensions, setExtensions] = useState('');
+  const [modifiedAfter, setModifiedAfter] = useState('');
+  const [preview, setPreview] = useState<ExportPreview | null>(null);
+  const [isPreviewing, setIsPreviewing] = useState(false);
+  const [runningApps, setRunningApps] = useState<RunningApp[]>([]);
+  const [ignoreRunning, setIgnoreRunning] = useState(false);
+  const [isClosingApps, setIsClosingApps] = useState(false);
+  const [useSnapshot, setUseSnapshot] = useState(false);
+  const [interrupted, setInterrupted] = useState<InterruptedJob | null>(null);
+  const [resumeInterrupted, setResumeInterrupted] = useState(true);
   const [password, setPassword] = useState('');
   const [confirmPassword, setConfirmPassword] = useState('');
+  // Öffentliche Schlüssel (nilo-pub-…), je Zeile einer; jeder öffnet das Paket allein
+  const [recipientKeys, setRecipientKeys] = useState('');
   const [isLoading, setIsLoading] = useState(false);
   const [message, setMessage] = useState('');
   const [exportPath, setExportPath] = useState('');
   const [profiles, setProfiles] = useState<string[]>([]);
   const [selectedUser, setSelectedUser] = useState('');
+  const [profilePath, setProfilePath] = useState('');
+  // Offline-Migration: Windows-Laufwerk, Profilordner oder Profil eines angeschlossenen Datenträgers
+  const [sourceRoot, setSourceRoot] = useState('');
+  // Direkte Übertragung in ein Profil dieses Rechners statt in ein Paket ('' = Paket)
+  const [transferTarget, setTransferTarget] = useState('');
+  const [localProfiles, setLocalProfiles] = useState<string[]>([]);
+  const [conflictPolicy, setConflictPolicy] = useState('keep_newer');
+  // Fertiges Paket an einen Empfänger im LAN senden (siehe src-tauri/src/network_operations.rs)
+  const [sendPackagePath, setSendPackagePath] = useState('');
+  const [sendAddress, setSendAddress] = useState('');
+  const [sendCode, setSendCode] = useState('');
+  const [isSending, setIsSending] = useState(false);
+  const [sendProgress, setSendProgress] = useState(0);
   const [progress, setProgress] = useState<{percent:number, eta_ms:number, phase:string}>({percent:0, eta_ms:0, phase:'Idle'});
   const [browserPresence, setBrowserPresence] = useState<{chrome:boolean; edge:boolean; firefox:boolean}>({chrome:true, edge:true, firefox:true});
 
   useEffect(() => {
-    const unlistenPromise = listen<any>('export-progress', async (e) => {
+    if (!selectedUser) return;
+    invoke<string>('get_profile_path', { selectedUser, sourceRoot: sourceRoot || undefined })
+      .then(setProfilePath)
+      .catch(() => setProfilePath(''));
+  }, [selectedUser, sourceRoot]);
+
+  useEffect(() => {
+    invoke<string[]>('list_windows_profiles', {})
+      .then(setLocalProfiles)
+      .catch(() => setLocalProfiles([]));
+  }, []);
+
+  useEffect(() => {
+    const onProgress = async (e: { payload: any }) => {
       const { percent, eta_ms, phase } = e.payload as any;
       setProgress({ percent, eta_ms, phase });
       // Windows taskbar progress (also works on macOS Dock)
@@ -54,19 +142,24 @@ function ExportPage() {
           await win.setProgressBar({ status: ProgressBarStatus.None });
         }
       } catch {}
-    });
-    return () => { unlistenPromise.then((off)=>off()); };
+    };
+    const unlistenPromises = [
+      listen<any>('export-progress', onProgress),
+      listen<any>('transfer-progress', onProgress),
+      listen<any>('send-progress', (e) => setSendProgress(e.payload.percent)),
+    ];
+    return () => { unlistenPromises.forEach((p) => p.then((off)=>off())); };
   }, []);
 
   useEffect(() => {
     (async () => {
       try {
-        const list = await invoke<string[]>('list_windows_profiles');
+        const list = await invoke<string[]>('list_windows_profiles', { sourceRoot: sourceRoot || undefined });
         setProfiles(list);
+        setSelectedUser(list.length > 0 ? list[0] : '');
         if (list.length > 0) {
-          setSelectedUser(list[0]);
           try {
-            const presence = await invoke<{chrome:boolean;edge:boolean;firefox:boolean}>('detect_browsers', { selectedUser: list[0] });
+            const presence = await invoke<{chrome:boolean;edge:boolean;firefox:boolean}>('detect_browsers', { selectedUser: list[0], sourceRoot: sourceRoot || undefined });
             setBrowserPresence(presence);
             setExportOptions(prev => ({
               ...prev,
@@ -77,16 +170,18 @@ function ExportPage() {
           } catch {}
         }
       } catch (e) {
+        setProfiles([]);
+        setSelectedUser('');
         setMessage(`Konnte Benutzerprofile nicht laden: ${e}`);
       }
     })();
-  }, []);
+  }, [sourceRoot]);
 
   useEffect(() => {
     if (!selectedUser) return;
     (async () => {
       try {
-        const presence = await invoke<{chrome:boolean;edge:boolean;firefox:boolean}>('detect_browsers', { selectedUser });
+        const presence = await invoke<{chrome:boolean;edge:boolean;firefox:boolean}>('detect_browsers', { selectedUser, sourceRoot: sourceRoot || undefined });
         setBrowserPresence(presence);
         setExportOptions(prev => ({
           ...prev,
@@ -96,7 +191,24 @@ function ExportPage() {
         }));
       } catch {}
     })();
-  }, [selectedUser]);
+  }, [selectedUser, sourceRoot]);
+
+  useEffect(() => {
+    if (!selectedUser) return;
+    (async () => {
+      try {
+        const job = await invoke<InterruptedJob | null>('find_interrupted_export', {
+          exportPath: exportPath || undefined,
+          selectedUser,
+          sourceRoot: sourceRoot || undefined,
+        });
+        setInterrupted(job);
+        setResumeInterrupted(true);
+      } catch {
+        setInterrupted(null);
+      }
+    })();
+  }, [selectedUser, exportPath, sourceRoot]);
 
   const openSaveDialog = async () => {
     try {
@@ -107,6 +219,26 @@ function ExportPage() {
     }
   };
 
+  const selectSourceRoot = async () => {
+    try {
+      const root = await invoke<string>('select_folder', { title: 'Laufwerk oder Profil des alten Rechners auswählen' });
+      setMessage('');
+      setSourceRoot(root);
+    } catch {}
+  };
+
+  const addCustomPath = async () => {
+    try {
+      const source = await invoke<string>('select_folder', { title: 'Ordner für den Export auswählen' });
+      if (customPaths.some((p) => p.source === source)) return;
+      setCustomPaths([...customPaths, { source, label: '', restore: '' }]);
+    } catch {}
+  };
+
+  const updateCustomPath = (index: number, changes: Partial<CustomPath>) => {
+    setCustomPaths(customPaths.map((p, i) => (i === index ? { ...p, ...changes } : p)));
+  };
+
   const handleOptionChange = (option: keyof ExportOptions) => {
     setExportOptions(prev => ({
       ...prev,
@@ -114,18 +246,75 @@ function ExportPage() {
     }));
   };
 
+  const buildOptions = () => ({
+    ...exportOptions,
+    custom_paths: customPaths.map((p) => ({
+      source: p.source,
+      label: p.label || undefined,
+      restore: p.restore || undefined,
+    })),
+    rules: {
+      exclude: excludePatterns.split('\n').map((l) => l.trim()).filter(Boolean),
+      max_file_size: maxFileSizeMb ? Math.round(Number(maxFileSizeMb) * 1024 * 1024) : undefined,
+      extensions: extensions.split(/[\s,;]+/).filter(Boolean),
+      modified_after: modifiedAfter ? Math.floor(new Date(modifiedAfter).getTime() / 1000) : undefined,
+    },
+  });
+
+  const handlePreview = async () => {
+    if (!selectedUser) {
+      setMessage('Bitte wähle ein Benutzerprofil aus.');
+      return;
+    }
+    setIsPreviewing(true);
+    setMessage('');
+    try {
+      const result = await invoke<ExportPreview>('preview_export', {
+        options: buildOptions(),
+        exportPath: exportPath || undefined,
+        selectedUser: selectedUser,
+        sourceRoot: sourceRoot || undefined,
+      });
+      setPreview(result);
+    } catch (error) {
+      setPreview(null);
+      setMessage(`Fehler bei der Vorschau: ${error}`);
+    } finally {
+      setIsPreviewing(false);
+    }
+  };
+
+  const closeRunningApps = async (force: boolean) => {
+    setIsClosingApps(true);
+    try {
+      const remaining = await invoke<RunningApp[]>('close_running_apps', {
+        ids: runningApps.map((a) => a.id),
+        force,
+      });
+      setRunningApps(remaining);
+      if (remaining.length > 0) {
+        setMessage(`Noch geöffnet: ${remaining.map((a) => a.label).join(', ')}`);
+      }
+    } catch (error) {
+      setMessage(`Fehler beim Schließen: ${error}`);
+    } finally {
+      setIsClosingApps(false);
+    }
+  };
+
   const handleExport = async () => {
     if (!selectedUser) {
       setMessage('Bitte wähle ein Benutzerprofil aus.');
       return;
     }
 
-    if (!password) {
-      setMessage('Bitte gib ein Passwort ein.');
+    const recipients = recipientKeys.split('\n').map(key => key.trim()).filter(Boolean);
+    if (!transferTarget && !password && recipients.length === 0) {
+      setMessage('Bitte gib ein Passwort oder einen öffentlichen Schlüssel ein.');
       return;
     }
     
-    if (password !== confirmPassword) {
+    if (!transferTarget && password !== confirmPassword) {
       setMessage('Passwörter stimmen nicht überein.');
       return;
     }
@@ -134,11 +323,23 @@ function ExportPage() {
       .filter(([_, selected]) => selected)
       .map(([option, _]) => option);
 
-    if (selectedOptions.length === 0) {
+    if (selectedOptions.length === 0 && customPaths.length === 0 && !(!transferTarget && interrupted && resumeInterrupted)) {
       setMessage('Bitte wähle mindestens eine Option aus.');
       return;
     }
 
+    // Geöffnete Browser/Outlook sperren ihre Datenbanken – vorher schließen lassen
+    if (!useSnapshot && !ignoreRunning) {
+      try {
+        const running = await invoke<RunningApp[]>('detect_running_apps', { ids: selectedOptions });
+        setRunningApps(running);
+        if (running.length > 0) {
+          setMessage('');
+          return;
+        }
+      } catch {}
+    }
+
     setIsLoading(true);
     setMessage('');
 
@@ -152,24 +353,43 @@ function ExportPage() {
     if (permissionGranted) {
       sendNotification({
         title: "NiloShift",
-        body: "Export erfolgreich gestartet!",
+        body: transferTarget ? "Übertragung gestartet!" : "Export erfolgreich gestartet!",
         icon: "/icon.png",
         group: "NiloShift",
         iconColor: "#059669",
       });
     }
 
+    if (transferTarget) {
+      await handleTransfer();
+      return;
+    }
+
     try {
-      const result = await invoke('start_export_command', {
-        options: exportOptions,
+      const result = await invoke<TransferSummary>('start_export_command', {
+        options: buildOptions(),
         password: password,
         exportPath: exportPath || undefined,
         selectedUser: selectedUser,
+        recipients: recipients.length > 0 ? recipients : undefined,
+        useSnapshot,
+        jobId: 'export',
+        resumeInterrupted: interrupted ? resumeInterrupted : undefined,
+        sourceRoot: sourceRoot || undefined,
       });
-      setMessage('Export erfolgreich gestartet!');
+      setInterrupted(null);
+      setMessage(describeSummary(result));
       console.log('Export result:', result);
     } catch (error) {
       setMessage(`Fehler beim Export: ${error}`);
+      try {
+        setInterrupted(await invoke<InterruptedJob | null>('find_interrupted_export', {
+          exportPath: exportPath || undefined,
+          selectedUser,
+          sourceRoot: sourceRoot || undefined,
+        }));
+        setResumeInterrupted(true);
+      } catch {}
       sendNotification({
         title: "NiloShift",
         body: "Export fehlgeschlagen!",
@@ -186,6 +406,73 @@ function ExportPage() {
     }
   };
 
+  // Ohne Paket direkt ins Zielprofil; vorhandene Dateien wie beim Import behandeln
+  const handleTransfer = async () => {
+    try {
+      const result = await invoke<TransferSummary>('start_transfer_command', {
+        options: buildOptions(),
+        sourceUser: selectedUser,
+        sourceRoot: sourceRoot || undefined,
+        selectedUser: transferTarget,
+        conflictPolicy,
+        useSnapshot,
+        jobId: 'transfer',
+      });
+      setMessage(describeSummary(result));
+      console.log('Transfer result:', result);
+    } catch (error) {
+      setMessage(`Fehler bei der Übertragung: ${error}`);
+      sendNotification({
+        title: "NiloShift",
+        body: "Übertragung fehlgeschlagen!",
+        icon: "/icon.png",
+        group: "NiloShift",
+        iconColor: "#dc2626",
+      });
+    } finally {
+      setIsLoading(false);
+      try {
+        const win = getCurrentWindow();
+        await win.setProgressBar({ status: ProgressBarStatus.None });
+      } catch {}
+    }
+  };
+
+  const handleSend = async () => {
+    const packagePath = sendPackagePath || exportPath;
+    if (!packagePath || !sendAddress || !sendCode) {
+      setMessage('Bitte Paket, Adresse und Kopplungscode des Empfängers angeben.');
+      return;
+    }
+    setIsSending(true);
+    setSendProgress(0);
+    setMessage('');
+    try {
+      const reply = await invoke<string>('send_package_command', {
+        packagePath,
+        address: sendAddress,
+        pairingCode: sendCode,
+        jobId: 'send',
+      });
+      setMessage(`Gesendet – ${reply}`);
+    } catch (error) {
+      setMessage(`Fehler beim Senden: ${error}`);
+    } finally {
+      setIsSending(false);
+    }
+  };
+
+  const generateKeyPair = async () => {
+    try {
+      const outputPath = await invoke<string>('select_key_file', { save: true });
+      const publicKey = await invoke<string>('generate_key_pair', { outputPath });
+      setRecipientKeys(keys => (keys.trim() ? `${keys.trim()}\n${publicKey}` : publicKey));
+      setMessage(`Schlüsseldatei gespeichert unter ${outputPath} – geheim halten, sie öffnet das Paket ohne Passwort.`);
+    } catch (error) {
+      if (!String(error).includes('Keine Datei')) setMessage(`Fehler beim Erzeugen des Schlüssels: ${error}`);
+    }
+  };
+
   const OptionCard = ({ 
     option, 
     icon, 
@@ -258,6 +545,17 @@ function ExportPage() {
 
         {/* User select */}
         <div className="linear-card p-4 mb-8 space-y-3">
+          <div>
+            <label className="block text-sm font-medium linear-text-primary mb-2">Quelle</label>
+            <div className="flex items-center gap-2">
+              <input className="linear-input flex-1" placeholder="Dieser Rechner" value={sourceRoot} onChange={(e)=>setSourceRoot(e.target.value)} />
+              <button className="linear-button-secondary" onClick={selectSourceRoot}>Datenträger wählen</button>
+              {sourceRoot && (
+                <button className="linear-button-secondary" onClick={() => setSourceRoot('')}>Dieser Rechner</button>
+              )}
+            </div>
+            <p className="linear-text-muted text-xs mt-2">Für einen ausgebauten Datenträger das Laufwerk (z. B. E:\\), den Ordner Users oder direkt das Profil wählen.</p>
+          </div>
           <div>
             <label className="block text-sm font-medium linear-text-primary mb-2">Benutzerprofil</label>
             <div className="relative">
@@ -274,8 +572,31 @@ function ExportPage() {
                 <path d="M4 6L8 10L12 6" stroke="currentColor" strokeWidth="1.5" strokeLinecap="round" strokeLinejoin="round"/>
               </svg>
             </div>
-            <p className="linear-text-muted text-xs mt-2">Quelle: C:\\Users\\{selectedUser}</p>
+            <p className="linear-text-muted text-xs mt-2">Quelle: {profilePath || selectedUser}</p>
           </div>
+          <div>
+            <label className="block text-sm font-medium linear-text-primary mb-2">Ziel</label>
+            <select className="linear-input w-full text-sm" value={transferTarget} onChange={(e) => setTransferTarget(e.target.value)} disabled={isLoading}>
+              <option value="">Paket (.nilo)</option>
+              {localProfiles.map((p) => (
+                <option key={p} value={p}>Direkt in Profil {p}</option>
+              ))}
+            </select>
+            <p className="linear-text-muted text-xs mt-2">Liegen altes und neues Profil auf diesem Rechner oder ist der alte Datenträger angeschlossen, werden die Dateien ohne Paket direkt übertragen.</p>
+          </div>
+          {transferTarget ? (
+          <div>
+            <label className="block text-sm font-medium linear-text-primary mb-2">Wenn eine Datei bereits existiert</label>
+            <select className="linear-input w-full text-sm" value={conflictPolicy} onChange={(e) => setConflictPolicy(e.target.value)} disabled={isLoading}>
+              <option value="keep_newer">Neuere Datei behalten</option>
+              <option value="overwrite">Überschreiben</option>
+              <option value="skip">Vorhandene Datei behalten</option>
+              <option value="rename">Beide behalten – neue als „Name (NiloShift)“</option>
+              <option value="backup">Vorhandene Datei sichern, dann ersetzen</option>
+            </select>
+            <p className="linear-text-muted text-xs mt-2">Identische Dateien werden nicht erneut geschrieben. Die Übertragung lässt sich wie ein Import zurücksetzen.</p>
+          </div>
+          ) : (
           <div>
             <label className="block text-sm font-medium linear-text-primary mb-2">Ziel-Datei</label>
             <div className="flex items-center gap-2">
@@ -284,6 +605,7 @@ function ExportPage() {
             </div>
             <p className="linear-text-muted text-xs mt-2">Wenn leer, wird auf dem Desktop des ausgewählten Benutzers gespeichert.</p>
           </div>
+          )}
         </div>
 
         {/* Export Options */}
@@ -315,6 +637,54 @@ function ExportPage() {
                 title="Downloads"
                 description="Heruntergeladene Dateien"
               />
+              <OptionCard
+                option="videos"
+                icon="🎬"
+                title="Videos"
+                description="Videos und Aufnahmen"
+              />
+              <OptionCard
+                option="music"
+                icon="🎵"
+                title="Musik"
+                description="Musik und Audiodateien"
+              />
+            </div>
+          </div>
+
+          <div>
+            <h2 className="text-lg font-medium linear-text-primary mb-4">Weitere Ordner</h2>
+            <div className="grid grid-cols-1 md:grid-cols-2 lg:grid-cols-3 gap-3">
+              <OptionCard
+                option="favorites"
+                icon="⭐"
+                title="Favoriten"
+                description="Internet Explorer-Favoriten"
+              />
+              <OptionCard
+                option="contacts"
+                icon="👥"
+                title="Kontakte"
+                description="Windows-Kontakte"
+              />
+              <OptionCard
+                option="saved_games"
+                icon="🎮"
+                title="Gespeicherte Spiele"
+                description="Spielstände"
+              />
+              <OptionCard
+                option="links"
+                icon="🔗"
+                title="Links"
+                description="Explorer-Schnellzugriff"
+              />
+              <OptionCard
+                option="objects_3d"
+                icon="🧊"
+                title="3D-Objekte"
+                description="3D-Modelle"
+              />
             </div>
           </div>
 
@@ -360,7 +730,75 @@ function ExportPage() {
             </div>
           </div>
 
+          <div>
+            <h2 className="text-lg font-medium linear-text-primary mb-4">Eigene Ordner</h2>
+            <div className="space-y-3">
+              {customPaths.map((p, index) => (
+                <div key={p.source} className="linear-card p-4 space-y-2">
+                  <div className="flex items-center gap-2">
+                    <div className="text-lg">📂</div>
+                    <div className="flex-1 min-w-0 text-sm linear-text-primary truncate" title={p.source}>{p.source}</div>
+                    <button
+                      className="linear-button-secondary text-xs"
+                      onClick={() => setCustomPaths(customPaths.filter((_, i) => i !== index))}
+                    >
+                      Entfernen
+                    </button>
+                  </div>
+                  <div className="grid grid-cols-1 md:grid-cols-2 gap-2">
+                    <input
+                      className="linear-input"
+                      placeholder="Bezeichnung (optional)"
+                      value={p.label}
+                      onChange={(e) => updateCustomPath(index, { label: e.target.value })}
+                    />
+                    <input
+                      className="linear-input"
+                      placeholder="Ziel beim Import (leer = ursprünglicher Ort)"
+                      value={p.restore}
+                      onChange={(e) => updateCustomPath(index, { restore: e.target.value })}
+                    />
+                  </div>
+                </div>
+              ))}
+              <button className="linear-button-secondary text-sm" onClick={addCustomPath}>
+                Ordner hinzufügen
+              </button>
+              <p className="linear-text-muted text-xs">Ordner im Benutzerprofil werden beim Import im Zielprofil wiederhergestellt, andere Ordner am ursprünglichen Ort.</p>
+            </div>
+          </div>
+
+          <div>
+            <h2 className="text-lg font-medium linear-text-primary mb-4">Filterregeln</h2>
+            <div className="linear-card p-4 grid grid-cols-1 md:grid-cols-2 gap-4">
+              <div className="md:col-span-2">
+                <label className="block text-sm font-medium linear-text-primary mb-2">Auslassen (ein Muster pro Zeile)</label>
+                <textarea
+                  className="linear-input w-full font-mono text-xs"
+                  rows={3}
+                  placeholder={'*.iso\nVirtual Machines/\n!wichtig.iso'}
+                  value={excludePatterns}
+                  onChange={(e) => setExcludePatterns(e.target.value)}
+                />
+                <p className="linear-text-muted text-xs mt-2">Muster wie in .gitignore. Browser-Caches und node_modules werden standardmäßig ausgelassen.</p>
+              </div>
+              <div>
+                <label className="block text-sm font-medium linear-text-primary mb-2">Maximale Dateigröße (MB)</label>
+                <input className="linear-input w-full" type="number" min="0" placeholder="unbegrenzt" value={maxFileSizeMb} onChange={(e) => setMaxFileSizeMb(e.target.value)} />
+              </div>
+              <div>
+                <label className="block text-sm font-medium linear-text-primary mb-2">Nur Dateiendungen</label>
+                <input className="linear-input w-full" placeholder="z.B. docx, xlsx, pdf (leer = alle)" value={extensions} onChange={(e) => setExtensions(e.target.value)} />
+              </div>
+              <div>
+                <label className="block text-sm font-medium linear-text-primary mb-2">Nur geändert seit</label>
+                <input className="linear-input w-full" type="date" value={modifiedAfter} onChange={(e) => setModifiedAfter(e.target.value)} />
+              </div>
+            </div>
+          </div>
+
           {/* Security Section */}
+          {!transferTarget && (
           <div>
             <h2 className="text-lg font-medium linear-text-primary mb-4">Sicherheit</h2>
             <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
@@ -391,7 +829,126 @@ function ExportPage() {
                 />
               </div>
             </div>
+            <div className="mt-4">
+              <label htmlFor="recipientKeys" className="block text-sm font-medium linear-text-primary mb-2">
+                Öffentliche Schlüssel (optional)
+              </label>
+              <textarea
+                id="recipientKeys"
+                value={recipientKeys}
+                onChange={(e) => setRecipientKeys(e.target.value)}
+                placeholder="nilo-pub-… (ein Schlüssel je Zeile)"
+                className="linear-input w-full font-mono text-xs h-20"
+              />
+              <div className="flex items-center justify-between mt-2">
+                <p className="linear-text-muted text-xs">Jeder Schlüssel öffnet das Paket mit der passenden Schlüsseldatei, auch ohne Passwort.</p>
+                <button className="linear-button-secondary text-xs" onClick={generateKeyPair} disabled={isLoading}>Schlüsselpaar erzeugen</button>
+              </div>
+            </div>
           </div>
+          )}
+
+          {/* Interrupted export */}
+          {interrupted && !transferTarget && (
+            <div className="linear-card p-4 space-y-3 border-yellow-500/20">
+              <div className="text-sm text-yellow-400">
+                Ein unterbrochener Export nach {interrupted.package} kann fortgesetzt werden.
+              </div>
+              <div className="text-xs linear-text-secondary">
+                {describeInterrupted(interrupted)}. Beim Fortsetzen gilt die Auswahl des ersten Laufs; das Passwort muss dasselbe sein.
+              </div>
+              <div className="flex flex-wrap gap-2">
+                <button
+                  className={resumeInterrupted ? 'linear-button-primary text-sm' : 'linear-button-secondary text-sm'}
+                  onClick={() => setResumeInterrupted(true)}
+                >
+                  Fortsetzen
+                </button>
+                <button
+                  className={resumeInterrupted ? 'linear-button-secondary text-sm' : 'linear-button-primary text-sm'}
+                  onClick={() => setResumeInterrupted(false)}
+                >
+                  Neu beginnen
+                </button>
+              </div>
+            </div>
+          )}
+
+          {/* Locked files */}
+          <label className="flex items-center gap-2 text-sm linear-text-secondary cursor-pointer">
+            <input type="checkbox" checked={useSnapshot} onChange={() => setUseSnapshot(!useSnapshot)} />
+            Aus Schattenkopie lesen (VSS) – auch geöffnete Dateien vollständig sichern
+          </label>
+
+          {runningApps.length > 0 && (
+            <div className="linear-card p-4 space-y-3 border-yellow-500/20">
+              <div className="text-sm text-yellow-400">
+                Diese Programme sind geöffnet und sperren Dateien, die exportiert werden sollen:
+              </div>
+              <ul className="text-xs linear-text-secondary list-disc ml-5">
+                {runningApps.map((a) => (
+                  <li key={a.id}>{a.label} ({a.processes.length} {a.processes.length === 1 ? 'Prozess' : 'Prozesse'})</li>
+                ))}
+              </ul>
+              <div className="flex flex-wrap gap-2">
+                <button className="linear-button-primary text-sm" disabled={isClosingApps} onClick={() => closeRunningApps(false)}>
+                  {isClosingApps ? 'Schließe…' : 'Programme schließen'}
+                </button>
+                <button className="linear-button-secondary text-sm" disabled={isClosingApps} onClick={() => closeRunningApps(true)}>
+                  Beenden erzwingen
+                </button>
+                <button className="linear-button-secondary text-sm" onClick={() => { setUseSnapshot(true); setRunningApps([]); }}>
+                  Schattenkopie verwenden
+                </button>
+                <button className="linear-button-secondary text-sm" onClick={() => { setIgnoreRunning(true); setRunningApps([]); }}>
+                  Trotzdem fortfahren
+                </button>
+              </div>
+            </div>
+          )}
+
+          {/* Preview */}
+          {preview && (
+            <div className="linear-card p-4 space-y-3">
+              <div className="flex items-center justify-between text-sm">
+                <span className="linear-text-primary font-medium">Vorschau</span>
+                <span className="linear-text-muted">
+                  {preview.total_files} Dateien · {formatBytes(preview.total_bytes)} · Paket ca. {formatBytes(preview.estimated_size)}
+                  {preview.free_space !== null && ` · frei ${formatBytes(preview.free_space)}`}
+                </span>
+              </div>
+              {preview.warnings.map((w) => (
+                <div key={w} className="px-3 py-2 rounded-md text-xs bg-yellow-500/10 text-yellow-400 border border-yellow-500/20">{w}</div>
+              ))}
+              {preview.categories.map((c) => (
+                <details key={c.id} className="text-xs">
+                  <summary className="cursor-pointer linear-text-secondary">
+                    {c.label}: {c.files} Dateien, {formatBytes(c.bytes)}
+                    {c.skipped_count > 0 && ` (${c.skipped_count} ausgelassen)`}
+                  </summary>
+                  <div className="mt-2 ml-4 space-y-2 linear-text-muted">
+                    {c.largest_files.length > 0 && (
+                      <div>
+                        <div className="linear-text-primary">Größte Dateien</div>
+                        {c.largest_files.map((f) => (
+                          <div key={f.path} className="flex justify-between gap-4"><span className="truncate">{f.path}</span><span>{formatBytes(f.bytes)}</span></div>
+                        ))}
+                      </div>
+                    )}
+                    {c.skipped.length > 0 && (
+                      <div>
+                        <div className="linear-text-primary">Ausgelassen</div>
+                        {c.skipped.map((f) => (
+                          <div key={f.path} className="flex justify-between gap-4"><span className="truncate">{f.path}{f.is_dir ? '/' : ''}</span><span>{f.reason}</span></div>
+                        ))}
+                        {c.skipped_count > c.skipped.length && <div>… und {c.skipped_count - c.skipped.length} weitere</div>}
+                      </div>
+                    )}
+                  </div>
+                </details>
+              ))}
+            </div>
+          )}
 
           {/* Export Button */}
           <div className="flex flex-col items-start gap-4 pt-6 border-t linear-border">
@@ -405,6 +962,7 @@ function ExportPage() {
                 <div className="w-full bg-[#21262d] rounded-full h-2 overflow-hidden">
                   <div className="h-full bg-[#5e6ad2] rounded-full transition-all duration-200" style={{ width: `${Math.min(100, Math.max(0, progress.percent))}%` }} />
                 </div>
+                <JobControls jobId={transferTarget ? 'transfer' : 'export'} />
               </div>
             )}
             {message && (
@@ -417,6 +975,14 @@ function ExportPage() {
               </div>
             )}
             
+            <div className="flex items-center gap-2">
+            <button
+              className={`linear-button-secondary ${isLoading || isPreviewing ? 'opacity-50 cursor-not-allowed' : ''}`}
+              onClick={handlePreview}
+              disabled={isLoading || isPreviewing}
+            >
+              {isPreviewing ? 'Berechne…' : 'Vorschau'}
+            </button>
             <button
               className={`linear-button-primary flex items-center gap-2 ${
                 isLoading ? 'opacity-50 cursor-not-allowed' : ''
@@ -427,7 +993,7 @@ function ExportPage() {
               {isLoading ? (
                 <>
                   <div className="w-4 h-4 border-2 border-white/30 border-t-white rounded-full animate-spin" />
-                  Export läuft...
+                  {transferTarget ? 'Übertragung läuft...' : 'Export läuft...'}
                 </>
               ) : (
                 <>
@@ -435,10 +1001,35 @@ function ExportPage() {
                     <path d="M10 2L10 13M10 2L6 6M10 2L14 6" stroke="currentColor" strokeWidth="1.5" strokeLinecap="round" strokeLinejoin="round"/>
                     <path d="M18 14V16C18 17.1046 17.1046 18 16 18H4C2.89543 18 2 17.1046 2 16V14" stroke="currentColor" strokeWidth="1.5" strokeLinecap="round"/>
                   </svg>
-                  Export starten
+                  {transferTarget ? 'Übertragung starten' : 'Export starten'}
                 </>
               )}
             </button>
+            </div>
+          </div>
+
+          {/* Send over LAN */}
+          <div>
+            <h2 className="text-lg font-medium linear-text-primary mb-4">Über das Netzwerk senden</h2>
+            <div className="linear-card p-4 space-y-3">
+              <p className="linear-text-muted text-xs">Auf dem neuen Rechner im Import „Über das Netzwerk empfangen“ starten und die angezeigte Adresse und den Kopplungscode hier eingeben. Das Paket wird direkt dort importiert.</p>
+              <input className="linear-input w-full" placeholder={exportPath || 'Pfad zur .nilo-Datei'} value={sendPackagePath} onChange={(e) => setSendPackagePath(e.target.value)} disabled={isSending} />
+              <div className="grid grid-cols-1 md:grid-cols-2 gap-2">
+                <input className="linear-input" placeholder="Adresse, z. B. 192.168.1.20" value={sendAddress} onChange={(e) => setSendAddress(e.target.value)} disabled={isSending} />
+                <input className="linear-input font-mono" placeholder="Kopplungscode, z. B. 4821-7390" value={sendCode} onChange={(e) => setSendCode(e.target.value)} disabled={isSending} />
+              </div>
+              {isSending && (
+                <div className="w-full space-y-2">
+                  <div className="w-full bg-[#21262d] rounded-full h-2 overflow-hidden">
+                    <div className="h-full bg-[#5e6ad2] rounded-full transition-all duration-200" style={{ width: `${Math.min(100, Math.max(0, sendProgress))}%` }} />
+                  </div>
+                  <JobControls jobId="send" />
+                </div>
+              )}
+              <button className={`linear-button-secondary ${isSending || isLoading ? 'opacity-50 cursor-not-allowed' : ''}`} onClick={handleSend} disabled={isSending || isLoading}>
+                {isSending ? 'Sende…' : 'Senden'}
+              </button>
+            </div>
           </div>
         </div>
       </div>
@@ -4,29 +4,116 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { getCurrentWindow, ProgressBarStatus } from '@tauri-apps/api/window';
 import { isPermissionGranted, requestPermission, sendNotification } from '@tauri-apps/plugin-notification';
+import { InterruptedJob, TransferSummary, describeInterrupted, describeSummary } from './transferSummary';
+import JobControls from './JobControls';
+
+type Selection = Record<string, boolean>;
+
+// Gleiche Kennungen wie in src-tauri/src/known_folders.rs
+const CONTENT_GROUPS: { title: string; items: { id: string; label: string }[] }[] = [
+  {
+    title: 'Ordner',
+    items: [
+      { id: 'desktop', label: 'Desktop' },
+      { id: 'documents', label: 'Dokumente' },
+      { id: 'pictures', label: 'Bilder' },
+      { id: 'videos', label: 'Videos' },
+      { id: 'music', label: 'Musik' },
+      { id: 'downloads', label: 'Downloads' },
+      { id: 'favorites', label: 'Favoriten' },
+      { id: 'contacts', label: 'Kontakte' },
+      { id: 'saved_games', label: 'Gespeicherte Spiele' },
+      { id: 'links', label: 'Links' },
+      { id: 'objects_3d', label: '3D-Objekte' },
+    ],
+  },
+  {
+    title: 'Browser-Daten',
+    items: [
+      { id: 'chrome', label: 'Google Chrome' },
+      { id: 'edge', label: 'Microsoft Edge' },
+      { id: 'firefox', label: 'Mozilla Firefox' },
+    ],
+  },
+  {
+    title: 'Microsoft Office',
+    items: [{ id: 'outlook_signatures', label: 'Outlook-Signaturen' }],
+  },
+];
+
+const ALL_ITEMS = CONTENT_GROUPS.flatMap((g) => g.items);
+
+interface CustomCategory {
+  id: string;
+  files: number;
+  custom: { source: string; label?: string; restore?: string };
+}
+
+// Ziel laut Paket; ohne Angabe im Import wird dorthin wiederhergestellt
+const originalTarget = (c: CustomCategory) => c.custom.restore || c.custom.source;
+
+// Nur Pfade innerhalb des Zielprofils werden ohne Bestätigung übernommen
+const isProfileRelative = (path: string) =>
+  !/^([a-zA-Z]:|[\\/])/.test(path) && !path.split(/[\\/]/).includes('..');
+
+const selectAll = (value: boolean): Selection =>
+  Object.fromEntries(ALL_ITEMS.map((item) => [item.id, value]));
 
 function ImportPage() {
   const navigate = useNavigate();
   const [selectedFile, setSelectedFile] = useState<string>('');
   const [password, setPassword] = useState('');
+  // Schlüsseldatei für Pakete, die für einen öffentlichen Schlüssel exportiert wurden
+  const [identityPath, setIdentityPath] = useState('');
   const [isLoading, setIsLoading] = useState(false);
   const [message, setMessage] = useState('');
   const [, setImportProgress] = useState(0);
   const [profiles, setProfiles] = useState<string[]>([]);
   const [selectedUser, setSelectedUser] = useState('');
+  const [profilePath, setProfilePath] = useState('');
   const [progress, setProgress] = useState<{percent:number, eta_ms:number, phase:string}>({percent:0, eta_ms:0, phase:'Idle'});
-  const [detected, setDetected] = useState<{desktop:boolean;documents:boolean;pictures:boolean;downloads:boolean;chrome:boolean;edge:boolean;firefox:boolean;outlook_signatures:boolean} | null>(null);
-  const [selectedItems, setSelectedItems] = useState<{desktop:boolean;documents:boolean;pictures:boolean;downloads:boolean;chrome:boolean;edge:boolean;firefox:boolean;outlook_signatures:boolean}>({
-    desktop: true,
-    documents: true,
-    pictures: true,
-    downloads: true,
-    chrome: true,
-    edge: true,
-    firefox: true,
-    outlook_signatures: true
-  });
+  const [detected, setDetected] = useState<Selection | null>(null);
+  const [selectedItems, setSelectedItems] = useState<Selection>(selectAll(true));
+  const [customCategories, setCustomCategories] = useState<CustomCategory[]>([]);
+  const [customTargets, setCustomTargets] = useState<Record<string, string>>({});
   const [isDetecting, setIsDetecting] = useState(false);
+  const [conflictPolicy, setConflictPolicy] = useState('keep_newer');
+  const [journalId, setJournalId] = useState<string | null>(null);
+  const [isRollingBack, setIsRollingBack] = useState(false);
+  const [interrupted, setInterrupted] = useState<InterruptedJob | null>(null);
+  const [resumeInterrupted, setResumeInterrupted] = useState(true);
+  // Paket über das LAN empfangen statt aus einer Datei (siehe src-tauri/src/network_operations.rs)
+  const [fromNetwork, setFromNetwork] = useState(false);
+  // Abgebrochenen Empfang desselben Pakets fortsetzen statt neu beginnen
+  const [resumeReceive, setResumeReceive] = useState(false);
+  const [receiver, setReceiver] = useState<{ code: string; port: number; addresses: string[] } | null>(null);
+
+  const findInterrupted = async () => {
+    if (!selectedFile || !selectedUser) {
+      setInterrupted(null);
+      return;
+    }
+    try {
+      setInterrupted(await invoke<InterruptedJob | null>('find_interrupted_import', {
+        packagePath: selectedFile,
+        selectedUser,
+      }));
+      setResumeInterrupted(true);
+    } catch {
+      setInterrupted(null);
+    }
+  };
+
+  useEffect(() => {
+    if (!selectedUser) return;
+    invoke<string>('get_profile_path', { selectedUser })
+      .then(setProfilePath)
+      .catch(() => setProfilePath(''));
+  }, [selectedUser]);
+
+  useEffect(() => {
+    findInterrupted();
+  }, [selectedFile, selectedUser]);
 
   useEffect(() => {
     const unlistenPromise = listen<any>('import-progress', async (e) => {
@@ -40,7 +127,11 @@ function ImportPage() {
         }
       } catch {}
     });
-    return () => { unlistenPromise.then((off)=>off()); };
+    const unlistenReady = listen<{ code: string; port: number; addresses: string[] }>('receive-ready', (e) => setReceiver(e.payload));
+    return () => {
+      unlistenPromise.then((off)=>off());
+      unlistenReady.then((off)=>off());
+    };
   }, []);
 
   useEffect(() => {
@@ -63,6 +154,7 @@ function ImportPage() {
         setMessage('');
         // Setze detected zurück wenn neue Datei gewählt wird
         setDetected(null);
+        setCustomCategories([]);
       }
     } catch (error) {
       setMessage(`Fehler beim Dateiwählen: ${error}`);
@@ -71,7 +163,7 @@ function ImportPage() {
 
   const detectContents = async (filePath: string, pwd: string) => {
     if (isDetecting) return; // Verhindere mehrfache gleichzeitige Aufrufe
-    if (!pwd || pwd.length < 1) return; // Mindestens 1 Zeichen erforderlich
+    if (!pwd && !identityPath) return; // Passwort oder Schlüsseldatei erforderlich
     
     setIsDetecting(true);
     setMessage('');
@@ -83,13 +175,18 @@ function ImportPage() {
     
     try {
       const info = await Promise.race([
-        invoke<{desktop:boolean;documents:boolean;pictures:boolean;downloads:boolean;chrome:boolean;edge:boolean;firefox:boolean;outlook_signatures:boolean}>(
+        invoke<any>(
           'detect_package_contents',
-          { packagePath: filePath, password: pwd }
+          { packagePath: filePath, password: pwd, identityPath: identityPath || undefined }
         ),
         timeoutPromise
       ]);
       
+      // Benutzerdefinierte Ordner stehen nur im Manifest
+      const custom: CustomCategory[] = (info.manifest?.categories ?? []).filter((c: any) => c.custom && c.files > 0);
+      setCustomCategories(custom);
+      setCustomTargets({});
+      setSelectedItems((prev) => ({ ...prev, ...Object.fromEntries(custom.map((c) => [c.id, true])) }));
       setDetected(info);
       setMessage('Paket-Inhalte erfolgreich erkannt!');
     } catch (e) {
@@ -104,16 +201,7 @@ function ImportPage() {
         setDetected(null);
       } else {
         // Setze Standard-Werte als Fallback
-        setDetected({
-          desktop: true,
-          documents: true,
-          pictures: true,
-          downloads: true,
-          chrome: true,
-          edge: true,
-          firefox: true,
-          outlook_signatures: true
-        });
+        setDetected(selectAll(true));
         setMessage(`Automatische Erkennung fehlgeschlagen. Alle Optionen werden angezeigt. (${errorMsg})`);
       }
     } finally {
@@ -121,8 +209,17 @@ function ImportPage() {
     }
   };
 
+  const toggleNetwork = () => {
+    const network = !fromNetwork;
+    setFromNetwork(network);
+    setMessage('');
+    setCustomCategories([]);
+    // Ohne Datei lässt sich der Inhalt nicht vorab erkennen
+    setDetected(network ? selectAll(true) : null);
+  };
+
   const handleImport = async () => {
-    if (!selectedFile) {
+    if (!selectedFile && !fromNetwork) {
       setMessage('Bitte wähle eine .nilo-Datei aus.');
       return;
     }
@@ -130,8 +227,8 @@ function ImportPage() {
       setMessage('Bitte wähle ein Ziel-Benutzerprofil aus.');
       return;
     }
-    if (!password) {
-      setMessage('Bitte gib das Passwort ein.');
+    if (!password && !identityPath) {
+      setMessage('Bitte gib das Passwort ein oder wähle eine Schlüsseldatei.');
       return;
     }
     if (!detected) {
@@ -151,12 +248,28 @@ function ImportPage() {
     setImportProgress(0);
 
     try {
-      const result = await invoke('start_import_command', {
-        packagePath: selectedFile,
-        password: password,
-        selectedUser: selectedUser,
-        selectedItems: selectedItems,
-      });
+      const result = fromNetwork
+        ? await invoke<TransferSummary>('start_receive_command', {
+            password: password,
+            identityPath: identityPath || undefined,
+            selectedUser: selectedUser,
+            selectedItems: selectedItems,
+            conflictPolicy: conflictPolicy,
+            jobId: 'import',
+            resumeInterrupted: resumeReceive,
+          })
+        : await invoke<TransferSummary>('start_import_command', {
+            packagePath: selectedFile,
+            password: password,
+            identityPath: identityPath || undefined,
+            selectedUser: selectedUser,
+            selectedItems: selectedItems,
+            customTargets: customTargets,
+            conflictPolicy: conflictPolicy,
+            jobId: 'import',
+            resumeInterrupted: interrupted ? resumeInterrupted : undefined,
+          });
+      setInterrupted(null);
       setProgress({percent:100, eta_ms:0, phase:'Fertig'});
     let permissionGranted = await isPermissionGranted();
 
@@ -175,7 +288,8 @@ function ImportPage() {
       });
     }
 
-      setMessage('Import erfolgreich abgeschlossen!');
+      setMessage(describeSummary(result));
+      setJournalId(result.journal_id);
       console.log('Import result:', result);
     } catch (error) {
       sendNotification({
@@ -187,8 +301,15 @@ function ImportPage() {
       });
       setMessage(`Fehler beim Import: ${error}`);
       setProgress({percent:0, eta_ms:0, phase:'Idle'});
+      // Abgebrochener Import: bereits geschriebene Dateien lassen sich zurücksetzen
+      try {
+        const journals = await invoke<{ id: string; changes: number }[]>('list_import_journals');
+        if (journals.length > 0 && journals[0].changes > 0) setJournalId(journals[0].id);
+      } catch {}
+      await findInterrupted();
     } finally {
       setIsLoading(false);
+      setReceiver(null);
       try {
         const win = getCurrentWindow();
         await win.setProgressBar({ status: ProgressBarStatus.None });
@@ -196,6 +317,21 @@ function ImportPage() {
     }
   };
 
+  const handleRollback = async () => {
+    if (!journalId) return;
+    setIsRollingBack(true);
+    try {
+      const result = await invoke<{ message: string; restored: number; removed: number; failed: string[] }>('rollback_import', { journalId });
+      setMessage(`${result.message} (${result.restored} Dateien wiederhergestellt, ${result.removed} entfernt)`);
+      if (result.failed.length === 0) setJournalId(null);
+      setProgress({percent:0, eta_ms:0, phase:'Idle'});
+    } catch (error) {
+      setMessage(`Fehler beim Rückgängigmachen: ${error}`);
+    } finally {
+      setIsRollingBack(false);
+    }
+  };
+
   return (
     <div className="min-h-screen linear-bg-primary">
       <div className="max-w-4xl mx-auto px-6 py-8">
@@ -235,14 +371,36 @@ function ImportPage() {
                 <path d="M4 6L8 10L12 6" stroke="currentColor" strokeWidth="1.5" strokeLinecap="round" strokeLinejoin="round"/>
               </svg>
             </div>
-            <p className="linear-text-muted text-xs mt-2">Ziel: C:\\Users\\{selectedUser}</p>
+            <p className="linear-text-muted text-xs mt-2">Ziel: {profilePath || selectedUser}</p>
           </div>
           <div>
             <label className="block text-sm font-medium linear-text-primary mb-2">NiloShift-Paket</label>
             <div className="flex items-center gap-2">
-              <input className="linear-input flex-1" placeholder="Pfad zur .nilo-Datei" value={selectedFile} onChange={(e)=>setSelectedFile(e.target.value)} />
-              <button className="linear-button-secondary" onClick={handleFileSelect}>Datei wählen</button>
+              {!fromNetwork && (
+                <>
+                  <input className="linear-input flex-1" placeholder="Pfad zur .nilo-Datei" value={selectedFile} onChange={(e)=>setSelectedFile(e.target.value)} />
+                  <button className="linear-button-secondary" onClick={handleFileSelect}>Datei wählen</button>
+                </>
+              )}
+              <button className="linear-button-secondary" onClick={toggleNetwork} disabled={isLoading}>
+                {fromNetwork ? 'Aus Datei importieren' : 'Über das Netzwerk empfangen'}
+              </button>
             </div>
+            {fromNetwork && (
+              <>
+                <p className="linear-text-muted text-xs mt-2">Nach dem Start zeigt NiloShift hier Adresse und Kopplungscode. Auf dem alten Rechner unter „Über das Netzwerk senden“ eingeben.</p>
+                <label className="flex items-center gap-2 mt-2 cursor-pointer">
+                  <input
+                    type="checkbox"
+                    checked={resumeReceive}
+                    onChange={(e) => setResumeReceive(e.target.checked)}
+                    disabled={isLoading}
+                    className="w-4 h-4 rounded border-[#484f58] bg-[#0d1117] text-[#059669] focus:ring-[#059669] focus:ring-offset-0 focus:ring-2"
+                  />
+                  <span className="text-sm linear-text-secondary">Abgebrochenen Empfang desselben Pakets fortsetzen</span>
+                </label>
+              </>
+            )}
           </div>
         </div>
 
@@ -256,11 +414,26 @@ function ImportPage() {
               setPassword(val);
               // Entferne automatische Erkennung - nur manuell per Button
               // Setze detected zurück wenn Passwort geändert wird
-              if (detected) {
+              if (detected && !fromNetwork) {
                 setDetected(null);
               }
             }} placeholder="Passwort zum Entschlüsseln eingeben" className="linear-input w-full" disabled={isLoading} />
-            {selectedFile && password && !detected && !isDetecting && (
+            <label htmlFor="import-identity" className="text-sm font-medium text-white mb-2 mt-4 block">Schlüsseldatei (optional)</label>
+            <div className="flex gap-2">
+              <input id="import-identity" value={identityPath} onChange={(e) => {
+                setIdentityPath(e.target.value);
+                if (detected && !fromNetwork) {
+                  setDetected(null);
+                }
+              }} placeholder="Statt Passwort, wenn für einen Schlüssel exportiert" className="linear-input flex-1" disabled={isLoading} />
+              <button className="linear-button-secondary text-sm" disabled={isLoading} onClick={async () => {
+                try {
+                  setIdentityPath(await invoke<string>('select_key_file', {}));
+                  if (!fromNetwork) setDetected(null);
+                } catch {}
+              }}>Wählen</button>
+            </div>
+            {selectedFile && (password || identityPath) && !detected && !isDetecting && (
               <div className="flex gap-2 mt-2">
                 <button 
                   onClick={() => detectContents(selectedFile, password)}
@@ -270,16 +443,7 @@ function ImportPage() {
                 </button>
                 <button 
                   onClick={() => {
-                    setDetected({
-                      desktop: true,
-                      documents: true,
-                      pictures: true,
-                      downloads: true,
-                      chrome: true,
-                      edge: true,
-                      firefox: true,
-                      outlook_signatures: true
-                    });
+                    setDetected(selectAll(true));
                     setMessage('Automatische Erkennung übersprungen. Alle Optionen verfügbar.');
                   }}
                   className="linear-button-secondary text-sm"
@@ -307,6 +471,35 @@ function ImportPage() {
             {message}
           </div>
         )}
+        {interrupted && !isLoading && (
+          <div className="linear-card p-4 space-y-3 mt-2 border-yellow-500/20">
+            <div className="text-sm text-yellow-400">
+              Ein unterbrochener Import dieses Pakets kann fortgesetzt werden.
+            </div>
+            <div className="text-xs linear-text-secondary">
+              {describeInterrupted(interrupted)}. Bitte dieselbe Auswahl beibehalten.
+            </div>
+            <div className="flex flex-wrap gap-2">
+              <button
+                className={resumeInterrupted ? 'linear-button-primary text-sm' : 'linear-button-secondary text-sm'}
+                onClick={() => setResumeInterrupted(true)}
+              >
+                Fortsetzen
+              </button>
+              <button
+                className={resumeInterrupted ? 'linear-button-secondary text-sm' : 'linear-button-primary text-sm'}
+                onClick={() => setResumeInterrupted(false)}
+              >
+                Neu beginnen
+              </button>
+            </div>
+          </div>
+        )}
+        {journalId && !isLoading && (
+          <button className="linear-button-secondary text-sm mt-2" onClick={handleRollback} disabled={isRollingBack}>
+            {isRollingBack ? 'Wird rückgängig gemacht…' : 'Import rückgängig machen'}
+          </button>
+        )}
 
         {/* Selection Section */}
         {detected && (
@@ -314,144 +507,95 @@ function ImportPage() {
             <h2 className="text-lg font-semibold text-white">Zu importierende Inhalte auswählen</h2>
             <div className="linear-card p-4">
               <div className="grid grid-cols-3 gap-4">
-                {/* Ordner-Auswahl */}
-                <div>
-                  <h3 className="text-sm font-medium linear-text-primary mb-3">Ordner</h3>
-                  <div className="space-y-2">
-                    {detected.desktop && (
-                      <label className="flex items-center gap-2 cursor-pointer hover:linear-text-primary">
-                        <input
-                          type="checkbox"
-                          checked={selectedItems.desktop}
-                          onChange={(e) => setSelectedItems({...selectedItems, desktop: e.target.checked})}
-                          className="w-4 h-4 rounded border-[#484f58] bg-[#0d1117] text-[#059669] focus:ring-[#059669] focus:ring-offset-0 focus:ring-2"
-                        />
-                        <span className="text-sm linear-text-secondary">Desktop</span>
-                      </label>
-                    )}
-                    {detected.documents && (
-                      <label className="flex items-center gap-2 cursor-pointer hover:linear-text-primary">
-                        <input
-                          type="checkbox"
-                          checked={selectedItems.documents}
-                          onChange={(e) => setSelectedItems({...selectedItems, documents: e.target.checked})}
-                          className="w-4 h-4 rounded border-[#484f58] bg-[#0d1117] text-[#059669] focus:ring-[#059669] focus:ring-offset-0 focus:ring-2"
-                        />
-                        <span className="text-sm linear-text-secondary">Dokumente</span>
-                      </label>
-                    )}
-                    {detected.pictures && (
-                      <label className="flex items-center gap-2 cursor-pointer hover:linear-text-primary">
-                        <input
-                          type="checkbox"
-                          checked={selectedItems.pictures}
-                          onChange={(e) => setSelectedItems({...selectedItems, pictures: e.target.checked})}
-                          className="w-4 h-4 rounded border-[#484f58] bg-[#0d1117] text-[#059669] focus:ring-[#059669] focus:ring-offset-0 focus:ring-2"
-                        />
-                        <span className="text-sm linear-text-secondary">Bilder</span>
-                      </label>
-                    )}
-                    {detected.downloads && (
-                      <label className="flex items-center gap-2 cursor-pointer hover:linear-text-primary">
-                        <input
-                          type="checkbox"
-                          checked={selectedItems.downloads}
-                          onChange={(e) => setSelectedItems({...selectedItems, downloads: e.target.checked})}
-                          className="w-4 h-4 rounded border-[#484f58] bg-[#0d1117] text-[#059669] focus:ring-[#059669] focus:ring-offset-0 focus:ring-2"
-                        />
-                        <span className="text-sm linear-text-secondary">Downloads</span>
-                      </label>
-                    )}
+                {CONTENT_GROUPS.map((group) => (
+                  <div key={group.title}>
+                    <h3 className="text-sm font-medium linear-text-primary mb-3">{group.title}</h3>
+                    <div className="space-y-2">
+                      {group.items.filter((item) => detected[item.id] === true).map((item) => (
+                        <label key={item.id} className="flex items-center gap-2 cursor-pointer hover:linear-text-primary">
+                          <input
+                            type="checkbox"
+                            checked={selectedItems[item.id] === true}
+                            onChange={(e) => setSelectedItems({...selectedItems, [item.id]: e.target.checked})}
+                            className="w-4 h-4 rounded border-[#484f58] bg-[#0d1117] text-[#059669] focus:ring-[#059669] focus:ring-offset-0 focus:ring-2"
+                          />
+                          <span className="text-sm linear-text-secondary">{item.label}</span>
+                        </label>
+                      ))}
+                    </div>
                   </div>
-                </div>
-                
-                {/* Browser-Auswahl */}
-                <div>
-                  <h3 className="text-sm font-medium linear-text-primary mb-3">Browser-Daten</h3>
-                  <div className="space-y-2">
-                    {detected.chrome && (
-                      <label className="flex items-center gap-2 cursor-pointer hover:linear-text-primary">
-                        <input
-                          type="checkbox"
-                          checked={selectedItems.chrome}
-                          onChange={(e) => setSelectedItems({...selectedItems, chrome: e.target.checked})}
-                          className="w-4 h-4 rounded border-[#484f58] bg-[#0d1117] text-[#059669] focus:ring-[#059669] focus:ring-offset-0 focus:ring-2"
-                        />
-                        <span className="text-sm linear-text-secondary">Google Chrome</span>
-                      </label>
-                    )}
-                    {detected.edge && (
-                      <label className="flex items-center gap-2 cursor-pointer hover:linear-text-primary">
-                        <input
-                          type="checkbox"
-                          checked={selectedItems.edge}
-                          onChange={(e) => setSelectedItems({...selectedItems, edge: e.target.checked})}
-                          className="w-4 h-4 rounded border-[#484f58] bg-[#0d1117] text-[#059669] focus:ring-[#059669] focus:ring-offset-0 focus:ring-2"
-                        />
-                        <span className="text-sm linear-text-secondary">Microsoft Edge</span>
-                      </label>
-                    )}
-                    {detected.firefox && (
-                      <label className="flex items-center gap-2 cursor-pointer hover:linear-text-primary">
-                        <input
-                          type="checkbox"
-                          checked={selectedItems.firefox}
-                          onChange={(e) => setSelectedItems({...selectedItems, firefox: e.target.checked})}
-                          className="w-4 h-4 rounded border-[#484f58] bg-[#0d1117] text-[#059669] focus:ring-[#059669] focus:ring-offset-0 focus:ring-2"
-                        />
-                        <span className="text-sm linear-text-secondary">Mozilla Firefox</span>
-                      </label>
-                    )}
-                  </div>
-                </div>
-                
-                {/* Microsoft Office-Auswahl */}
-                <div>
-                  <h3 className="text-sm font-medium linear-text-primary mb-3">Microsoft Office</h3>
-                  <div className="space-y-2">
-                    {detected.outlook_signatures && (
-                      <label className="flex items-center gap-2 cursor-pointer hover:linear-text-primary">
-                        <input
-                          type="checkbox"
-                          checked={selectedItems.outlook_signatures}
-                          onChange={(e) => setSelectedItems({...selectedItems, outlook_signatures: e.target.checked})}
-                          className="w-4 h-4 rounded border-[#484f58] bg-[#0d1117] text-[#059669] focus:ring-[#059669] focus:ring-offset-0 focus:ring-2"
-                        />
-                        <span className="text-sm linear-text-secondary">Outlook-Signaturen</span>
-                      </label>
-                    )}
+                ))}
+              </div>
+
+              {/* Benutzerdefinierte Ordner mit wählbarem Ziel */}
+              {customCategories.length > 0 && (
+                <div className="mt-4 pt-4 border-t linear-border">
+                  <h3 className="text-sm font-medium linear-text-primary mb-3">Eigene Ordner</h3>
+                  <div className="space-y-3">
+                    {customCategories.map((c) => (
+                      <div key={c.id} className="grid grid-cols-1 md:grid-cols-2 gap-2 items-center">
+                        <label className="flex items-center gap-2 cursor-pointer hover:linear-text-primary min-w-0">
+                          <input
+                            type="checkbox"
+                            checked={selectedItems[c.id] === true}
+                            onChange={(e) => setSelectedItems({...selectedItems, [c.id]: e.target.checked})}
+                            className="w-4 h-4 rounded border-[#484f58] bg-[#0d1117] text-[#059669] focus:ring-[#059669] focus:ring-offset-0 focus:ring-2"
+                          />
+                          <span className="text-sm linear-text-secondary truncate" title={c.custom.source}>{c.custom.label || c.custom.source}</span>
+                        </label>
+                        <div className="flex gap-2 min-w-0">
+                          <input
+                            className="linear-input text-sm flex-1 min-w-0"
+                            placeholder={isProfileRelative(originalTarget(c))
+                              ? originalTarget(c)
+                              : `Ziel angeben (ursprünglich ${originalTarget(c)})`}
+                            value={customTargets[c.id] ?? ''}
+                            onChange={(e) => setCustomTargets({...customTargets, [c.id]: e.target.value})}
+                            disabled={isLoading}
+                          />
+                          {!isProfileRelative(originalTarget(c)) && !customTargets[c.id] && (
+                            <button
+                              className="linear-button-secondary text-xs whitespace-nowrap"
+                              onClick={() => setCustomTargets({...customTargets, [c.id]: originalTarget(c)})}
+                              disabled={isLoading}
+                            >
+                              Ursprünglicher Ort
+                            </button>
+                          )}
+                        </div>
+                      </div>
+                    ))}
                   </div>
+                  <p className="linear-text-muted text-xs mt-2">Leeres Ziel: Wiederherstellung am ursprünglichen Ort im Zielprofil. Ordner, die außerhalb des Profils lagen, brauchen ein Ziel. Relative Pfade beziehen sich auf das Zielprofil.</p>
                 </div>
-              </div>
+              )}
               
+              {/* Vorhandene Dateien im Zielprofil */}
+              <div className="mt-4 pt-4 border-t linear-border">
+                <label className="block text-sm font-medium linear-text-primary mb-2">Wenn eine Datei bereits existiert</label>
+                <select className="linear-input w-full text-sm" value={conflictPolicy} onChange={(e) => setConflictPolicy(e.target.value)} disabled={isLoading}>
+                  <option value="keep_newer">Neuere Datei behalten</option>
+                  <option value="overwrite">Überschreiben</option>
+                  <option value="skip">Vorhandene Datei behalten</option>
+                  <option value="rename">Beide behalten – neue als „Name (NiloShift)“</option>
+                  <option value="backup">Vorhandene Datei sichern, dann ersetzen</option>
+                </select>
+                <p className="linear-text-muted text-xs mt-2">Identische Dateien werden nicht erneut geschrieben.</p>
+              </div>
+
               {/* Alle/Keine auswählen Buttons */}
               <div className="flex gap-2 mt-4 pt-4 border-t linear-border">
                 <button
                   onClick={() => setSelectedItems({
-                    desktop: detected.desktop,
-                    documents: detected.documents,
-                    pictures: detected.pictures,
-                    downloads: detected.downloads,
-                    chrome: detected.chrome,
-                    edge: detected.edge,
-                    firefox: detected.firefox,
-                    outlook_signatures: detected.outlook_signatures
+                    ...Object.fromEntries(ALL_ITEMS.map((item) => [item.id, detected[item.id] === true])),
+                    ...Object.fromEntries(customCategories.map((c) => [c.id, true])),
                   })}
                   className="text-xs linear-button-secondary"
                 >
                   Alle auswählen
                 </button>
                 <button
-                  onClick={() => setSelectedItems({
-                    desktop: false,
-                    documents: false,
-                    pictures: false,
-                    downloads: false,
-                    chrome: false,
-                    edge: false,
-                    firefox: false,
-                    outlook_signatures: false
-                  })}
+                  onClick={() => setSelectedItems(selectAll(false))}
                   className="text-xs linear-button-secondary"
                 >
                   Keine auswählen
@@ -461,6 +605,14 @@ function ImportPage() {
           </div>
         )}
 
+        {/* Warten auf den Sender */}
+        {receiver && isLoading && (
+          <div className="linear-card p-4 mt-6 space-y-1">
+            <div className="text-sm linear-text-primary">Kopplungscode: <span className="font-mono text-lg">{receiver.code}</span></div>
+            <div className="text-xs linear-text-secondary">Adresse: {receiver.addresses.map((a) => (a.includes(':') ? `[${a}]:${receiver.port}` : `${a}:${receiver.port}`)).join(', ')}</div>
+          </div>
+        )}
+
         {/* Import Progress */}
         {(isLoading || progress.percent > 0) && (
           <div className="flex flex-col gap-2 mt-6 w-full">
@@ -471,6 +623,7 @@ function ImportPage() {
             <div className="w-full bg-[#21262d] rounded-full h-2 overflow-hidden">
               <div className="h-full bg-[#059669] rounded-full transition-all duration-200" style={{ width: `${Math.min(100, Math.max(0, progress.percent))}%` }} />
             </div>
+            {isLoading && <JobControls jobId="import" />}
           </div>
         )}
 
@@ -480,19 +633,13 @@ function ImportPage() {
             <div className="text-xs linear-text-muted">
               <span className="linear-text-primary">Erkannt:</span>
               <span className="ml-2">{[
-                detected.desktop && 'Desktop',
-                detected.documents && 'Dokumente',
-                detected.pictures && 'Bilder',
-                detected.downloads && 'Downloads',
-                detected.chrome && 'Chrome',
-                detected.edge && 'Edge',
-                detected.firefox && 'Firefox',
-                detected.outlook_signatures && 'Outlook-Signaturen',
-              ].filter(Boolean).join(', ') || 'Keine Inhalte erkannt'}</span>
+                ...ALL_ITEMS.filter((item) => detected[item.id] === true).map((item) => item.label),
+                ...customCategories.map((c) => c.custom.label || c.custom.source),
+              ].join(', ') || 'Keine Inhalte erkannt'}</span>
             </div>
           )}
-          <button className={`linear-button-primary flex items-center gap-2 ${isLoading || !selectedFile || !password || !detected || !Object.values(selectedItems).some(v => v) ? 'opacity-50 cursor-not-allowed' : ''}`} onClick={handleImport} disabled={isLoading || !selectedFile || !password || !detected || !Object.values(selectedItems).some(v => v)}>
-            {isLoading ? (<><div className="w-4 h-4 border-2 border-white/30 border-t-white rounded-full animate-spin" />Import läuft...</>) : (<><svg width="16" height="16" viewBox="0 0 20 20" fill="none"><path d="M10 18L10 5M10 18L6 14M10 18L14 14" stroke="currentColor" strokeWidth="1.5" strokeLinecap="round" strokeLinejoin="round"/><path d="M18 6V4C18 2.89543 17.1046 2 16 2H4C2.89543 2 2 2.89543 2 4V6" stroke="currentColor" strokeWidth="1.5" strokeLinecap="round"/></svg>Import starten</>)}
+          <button className={`linear-button-primary flex items-center gap-2 ${isLoading || (!selectedFile && !fromNetwork) || (!password && !identityPath) || !detected || !Object.values(selectedItems).some(v => v) ? 'opacity-50 cursor-not-allowed' : ''}`} onClick={handleImport} disabled={isLoading || (!selectedFile && !fromNetwork) || (!password && !identityPath) || !detected || !Object.values(selectedItems).some(v => v)}>
+            {isLoading ? (<><div className="w-4 h-4 border-2 border-white/30 border-t-white rounded-full animate-spin" />{fromNetwork ? 'Empfang läuft...' : 'Import läuft...'}</>) : (<><svg width="16" height="16" viewBox="0 0 20 20" fill="none"><path d="M10 18L10 5M10 18L6 14M10 18L14 14" stroke="currentColor" strokeWidth="1.5" strokeLinecap="round" strokeLinejoin="round"/><path d="M18 6V4C18 2.89543 17.1046 2 16 2H4C2.89543 2 2 2.89543 2 4V6" stroke="currentColor" strokeWidth="1.5" strokeLinecap="round"/></svg>{fromNetwork ? 'Empfang starten' : 'Import starten'}</>)}
           </button>
         </div>
       </div>
@@ -0,0 +1,38 @@
+import { useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+
+// Pausieren, Fortsetzen und Abbrechen eines laufenden Exports/Imports (siehe src-tauri/src/jobs.rs)
+function JobControls({ jobId }: { jobId: string }) {
+  const [isPaused, setIsPaused] = useState(false);
+  const [isCancelling, setIsCancelling] = useState(false);
+
+  const togglePause = async () => {
+    try {
+      await invoke(isPaused ? 'resume_job' : 'pause_job', { jobId });
+      setIsPaused(!isPaused);
+    } catch {}
+  };
+
+  const cancel = async () => {
+    setIsCancelling(true);
+    try {
+      await invoke('cancel_job', { jobId });
+    } catch {
+      setIsCancelling(false);
+    }
+  };
+
+  return (
+    <div className="flex items-center gap-2">
+      <button className="linear-button-secondary text-xs" onClick={togglePause} disabled={isCancelling}>
+        {isPaused ? 'Fortsetzen' : 'Pausieren'}
+      </button>
+      <button className="linear-button-secondary text-xs" onClick={cancel} disabled={isCancelling}>
+        {isCancelling ? 'Wird abgebrochen…' : 'Abbrechen'}
+      </button>
+      {isPaused && !isCancelling && <span className="text-xs linear-text-muted">Pausiert</span>}
+    </div>
+  );
+}
+
+export default JobControls;
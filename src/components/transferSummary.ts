@@ -0,0 +1,33 @@
+// Ergebnis von start_export_command / start_import_command / start_transfer_command (siehe src-tauri/src/report.rs)
+export interface TransferSummary {
+  message: string;
+  copied: number;
+  copied_bytes: number;
+  skipped: number;
+  failed: number;
+  conflicts: number;
+  report_path: string | null;
+  journal_id: string | null;
+}
+
+export const describeSummary = (s: TransferSummary) => {
+  let counts = `${s.copied} übernommen, ${s.skipped} ausgelassen, ${s.failed} fehlgeschlagen`;
+  if (s.conflicts > 0) counts += `, ${s.conflicts} Konflikte`;
+  const report = s.report_path ? ` Bericht: ${s.report_path}` : '';
+  return `${s.message} (${counts}).${report}`;
+};
+
+// Unterbrochener Vorgang aus find_interrupted_export / find_interrupted_import (siehe src-tauri/src/resume.rs)
+export interface InterruptedJob {
+  package: string;
+  files: number;
+  bytes: number;
+  saved_at: number;
+  journal_id: string | null;
+}
+
+export const describeInterrupted = (job: InterruptedJob) => {
+  const gb = (job.bytes / 1024 ** 3).toFixed(1);
+  const savedAt = new Date(job.saved_at * 1000).toLocaleString('de-DE');
+  return `${job.files} Dateien (${gb} GB) bereits übernommen, Stand ${savedAt}`;
+};